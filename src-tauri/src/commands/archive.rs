@@ -0,0 +1,862 @@
+//! Session lifecycle: app-managed trash, compressed archives and a preview of
+//! what Claude Code's `cleanupPeriodDays` retention will delete
+//!
+//! A session is its `<id>.jsonl` file plus the sibling `<id>/` folder Claude
+//! Code uses for subagent transcripts, if any. Trashed sessions are moved to
+//! `~/.claude-history-viewer/trash/<entry id>/`, archives are written to
+//! `~/.claude-history-viewer/archives/<archive id>.tar.gz`; each folder keeps
+//! an `index.json`.
+
+use super::claude_settings::effective_cleanup_period_days;
+use super::fs_utils::atomic_rename;
+use super::metadata::get_metadata_folder;
+use super::project::scan_projects;
+use super::session::{load_project_sessions, validate_claude_path};
+use super::trusted_symlinks::{check_claude_folder, claude_dir};
+use crate::models::{
+    ArchiveRestoreResult, ArchivedSession, ClaudeSession, RetentionCandidate, RetentionPreview,
+    SessionArchive, TrashEntry,
+};
+use chrono::{DateTime, Duration, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+/// Serializes trash and archive index updates within the app
+static ARCHIVE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Sessions expiring within this many days are included in the retention preview
+const DEFAULT_RETENTION_WINDOW_DAYS: u64 = 7;
+
+/// Name of the manifest stored inside each archive
+const ARCHIVE_MANIFEST: &str = "manifest.json";
+
+/// A session file and the paths that belong to it
+struct SessionFiles {
+    session_id: String,
+    project_name: String,
+    project_dir: PathBuf,
+    /// The `.jsonl` file first, then the subagent folder if present
+    paths: Vec<PathBuf>,
+    last_modified: String,
+    size_bytes: u64,
+}
+
+/// `~/.claude/projects`, resolved the same way session paths are
+///
+/// Only symlinks into a trusted target are followed.
+pub(crate) fn projects_dir() -> Result<PathBuf, String> {
+    let claude_dir = claude_dir()?;
+    check_claude_folder(&claude_dir)?;
+    let projects = claude_dir.join("projects");
+    projects
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve projects folder: {e}"))
+}
+
+fn to_rfc3339(time: SystemTime) -> String {
+    let dt: DateTime<Utc> = time.into();
+    dt.to_rfc3339()
+}
+
+fn path_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter_map(|e| e.metadata().ok())
+        .filter(std::fs::Metadata::is_file)
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Validate a session file under `~/.claude/projects/<project>/` and collect its paths
+fn locate_session(file_path: &str) -> Result<SessionFiles, String> {
+    let path = Path::new(file_path);
+    if !path.is_file() {
+        return Err(format!("Session file not found: {file_path}"));
+    }
+    validate_claude_path(file_path)?;
+    if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+        return Err("Not a session file (expected .jsonl)".to_string());
+    }
+
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve session path: {e}"))?;
+    let project_dir = path
+        .parent()
+        .ok_or("Invalid session file path")?
+        .to_path_buf();
+    let projects_dir = projects_dir()?;
+    if project_dir.parent() != Some(projects_dir.as_path()) {
+        return Err("Session file must be directly inside a ~/.claude/projects folder".to_string());
+    }
+
+    let session_id = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or("Invalid session file name")?
+        .to_string();
+    let project_name = project_dir
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or("Invalid project folder name")?
+        .to_string();
+    let metadata = fs::metadata(&path).map_err(|e| format!("Failed to read metadata: {e}"))?;
+    let last_modified = metadata
+        .modified()
+        .map_or_else(|_| Utc::now().to_rfc3339(), to_rfc3339);
+
+    let mut paths = vec![path];
+    let subagent_dir = project_dir.join(&session_id);
+    if fs::symlink_metadata(&subagent_dir).is_ok_and(|m| m.file_type().is_dir()) {
+        paths.push(subagent_dir);
+    }
+    let size_bytes = paths.iter().map(|p| path_size(p)).sum();
+
+    Ok(SessionFiles {
+        session_id,
+        project_name,
+        project_dir,
+        paths,
+        last_modified,
+        size_bytes,
+    })
+}
+
+fn load_index<T: DeserializeOwned>(folder: &Path) -> Result<Vec<T>, String> {
+    let path = folder.join("index.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read index: {e}"))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse index: {e}"))
+}
+
+fn save_index<T: Serialize>(folder: &Path, entries: &[T]) -> Result<(), String> {
+    let path = folder.join("index.json");
+    let temp_path = folder.join("index.json.tmp");
+    let content = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize index: {e}"))?;
+    fs::write(&temp_path, content).map_err(|e| format!("Failed to write temp file: {e}"))?;
+    atomic_rename(&temp_path, &path)
+}
+
+/// `(project folder, session id)` of every session in the trash or an archive
+///
+/// Restoring puts these back at their original path, so their metadata is
+/// still wanted even though the file is missing from `projects/`.
+pub(crate) fn parked_sessions() -> Result<HashSet<(String, String)>, String> {
+    let folder = get_metadata_folder()?;
+    let trash: Vec<TrashEntry> = load_index(&folder.join("trash"))?;
+    let archives: Vec<SessionArchive> = load_index(&folder.join("archives"))?;
+    let trashed = trash
+        .into_iter()
+        .map(|entry| (entry.project_name, entry.session_id));
+    let archived = archives
+        .into_iter()
+        .flat_map(|archive| archive.sessions)
+        .map(|session| (session.project_name, session.session_id));
+    Ok(trashed.chain(archived).collect())
+}
+
+fn ensure_folder(name: &str) -> Result<PathBuf, String> {
+    let folder = get_metadata_folder()?.join(name);
+    fs::create_dir_all(&folder).map_err(|e| format!("Failed to create {name} folder: {e}"))?;
+    Ok(folder)
+}
+
+/// Copy a file or folder, preserving the file modification times
+fn copy_recursive(from: &Path, to: &Path) -> Result<(), String> {
+    for entry in WalkDir::new(from)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+    {
+        let relative = entry.path().strip_prefix(from).map_err(|e| e.to_string())?;
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target).map_err(|e| format!("Failed to create folder: {e}"))?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target).map_err(|e| format!("Failed to copy file: {e}"))?;
+            if let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) {
+                let _ = fs::File::options()
+                    .write(true)
+                    .open(&target)
+                    .and_then(|f| f.set_modified(modified));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if fs::symlink_metadata(path).is_ok_and(|m| m.is_dir()) {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Move a file or folder, falling back to copy + delete across filesystems
+///
+/// A failed copy is cleaned up, leaving `from` as it was. If only deleting
+/// `from` fails, `to` holds a complete copy.
+fn move_path(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if let Err(e) = copy_recursive(from, to) {
+        let _ = remove_path(to);
+        return Err(e);
+    }
+    remove_path(from).map_err(|e| format!("Failed to remove {}: {e}", from.display()))
+}
+
+/// Refresh the modification time so Claude Code's cleanup does not purge a
+/// restored session again on its next start
+fn touch(path: &Path) {
+    let _ = fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|f| f.set_modified(SystemTime::now()));
+}
+
+/// Move a session into the trash, adding its entry to `trashed`
+///
+/// If a move fails partway, the files already moved are put back. Any that
+/// can't be are still added as an entry, so they stay restorable.
+fn trash_session(
+    file_path: &str,
+    trash_dir: &Path,
+    trashed: &mut Vec<TrashEntry>,
+) -> Result<(), String> {
+    let session = locate_session(file_path)?;
+    let id = Uuid::new_v4().to_string();
+    let entry_dir = trash_dir.join(&id);
+    fs::create_dir_all(&entry_dir).map_err(|e| format!("Failed to create trash folder: {e}"))?;
+
+    let mut files = Vec::new();
+    let mut result = Ok(());
+    for path in &session.paths {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            result = Err("Invalid file name".to_string());
+            break;
+        };
+        let target = entry_dir.join(name);
+        if let Err(e) = move_path(path, &target) {
+            // Copied but not deleted from the project: the trash has it whole
+            if target.exists() {
+                files.push(name.to_string());
+            }
+            result = Err(e);
+            break;
+        }
+        files.push(name.to_string());
+    }
+
+    if result.is_err() {
+        let project_dir = &session.project_dir;
+        files.retain(|name| move_path(&entry_dir.join(name), &project_dir.join(name)).is_err());
+        if files.is_empty() {
+            let _ = fs::remove_dir_all(&entry_dir);
+            return result;
+        }
+    }
+
+    trashed.push(TrashEntry {
+        id,
+        session_id: session.session_id,
+        project_name: session.project_name,
+        original_path: session.paths[0].to_string_lossy().to_string(),
+        trashed_at: Utc::now().to_rfc3339(),
+        last_modified: session.last_modified,
+        size_bytes: session.size_bytes,
+        files,
+    });
+    result
+}
+
+fn trash_sessions_sync(file_paths: &[String]) -> Result<Vec<TrashEntry>, String> {
+    let _guard = ARCHIVE_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock trash: {e}"))?;
+    let trash_dir = ensure_folder("trash")?;
+    let mut index: Vec<TrashEntry> = load_index(&trash_dir)?;
+
+    let mut trashed = Vec::new();
+    let mut result = Ok(());
+    for file_path in file_paths {
+        if let Err(e) = trash_session(file_path, &trash_dir, &mut trashed) {
+            result = Err(format!("Failed to trash {file_path}: {e}"));
+            break;
+        }
+    }
+
+    // Record what was moved even if a later session failed
+    index.extend(trashed.iter().cloned());
+    save_index(&trash_dir, &index)?;
+    result.map(|()| trashed)
+}
+
+fn restore_trash_entry(entry_id: &str) -> Result<TrashEntry, String> {
+    let _guard = ARCHIVE_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock trash: {e}"))?;
+    let trash_dir = ensure_folder("trash")?;
+    let mut index: Vec<TrashEntry> = load_index(&trash_dir)?;
+    let position = index
+        .iter()
+        .position(|e| e.id == entry_id)
+        .ok_or_else(|| format!("Trash entry not found: {entry_id}"))?;
+    let entry = index[position].clone();
+
+    // Only restore into the folder the session came from
+    let original = PathBuf::from(&entry.original_path);
+    let project_dir = projects_dir()?.join(&entry.project_name);
+    if original.parent() != Some(project_dir.as_path()) {
+        return Err("Invalid original path in trash entry".to_string());
+    }
+    for name in &entry.files {
+        if Path::new(name).components().count() != 1 || project_dir.join(name).exists() {
+            return Err(format!(
+                "Cannot restore: {} already exists",
+                project_dir.join(name).display()
+            ));
+        }
+    }
+
+    fs::create_dir_all(&project_dir)
+        .map_err(|e| format!("Failed to create project folder: {e}"))?;
+    let entry_dir = trash_dir.join(&entry.id);
+    for name in &entry.files {
+        move_path(&entry_dir.join(name), &project_dir.join(name))?;
+    }
+    touch(&original);
+    let _ = fs::remove_dir(&entry_dir);
+
+    index.remove(position);
+    save_index(&trash_dir, &index)?;
+    Ok(entry)
+}
+
+fn empty_trash_sync(entry_ids: Option<&[String]>) -> Result<usize, String> {
+    let _guard = ARCHIVE_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock trash: {e}"))?;
+    let trash_dir = ensure_folder("trash")?;
+    let (removed, kept): (Vec<TrashEntry>, Vec<TrashEntry>) = load_index::<TrashEntry>(&trash_dir)?
+        .into_iter()
+        .partition(|e| entry_ids.map_or(true, |ids| ids.contains(&e.id)));
+
+    for entry in &removed {
+        let entry_dir = trash_dir.join(&entry.id);
+        if entry_dir.parent() == Some(trash_dir.as_path()) && entry_dir.exists() {
+            fs::remove_dir_all(&entry_dir)
+                .map_err(|e| format!("Failed to delete trashed session: {e}"))?;
+        }
+    }
+    save_index(&trash_dir, &kept)?;
+    Ok(removed.len())
+}
+
+/// Write the sessions into `<archive id>.tar.gz` under `projects/<project>/`
+fn write_archive(path: &Path, sessions: &[SessionFiles], manifest: &[u8]) -> Result<(), String> {
+    let file = fs::File::create(path).map_err(|e| format!("Failed to create archive: {e}"))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(u64::try_from(Utc::now().timestamp()).unwrap_or(0));
+    builder
+        .append_data(&mut header, ARCHIVE_MANIFEST, manifest)
+        .map_err(|e| format!("Failed to write archive manifest: {e}"))?;
+
+    for session in sessions {
+        for source in &session.paths {
+            let name = Path::new("projects").join(&session.project_name).join(
+                source
+                    .strip_prefix(&session.project_dir)
+                    .map_err(|e| e.to_string())?,
+            );
+            if source.is_dir() {
+                builder.append_dir_all(&name, source)
+            } else {
+                builder.append_path_with_name(source, &name)
+            }
+            .map_err(|e| format!("Failed to add {} to archive: {e}", source.display()))?;
+        }
+    }
+
+    builder
+        .into_inner()
+        .and_then(GzEncoder::finish)
+        .and_then(|file| file.sync_all())
+        .map_err(|e| format!("Failed to finish archive: {e}"))
+}
+
+fn archive_sessions_sync(
+    file_paths: &[String],
+    remove_originals: bool,
+) -> Result<SessionArchive, String> {
+    if file_paths.is_empty() {
+        return Err("No sessions to archive".to_string());
+    }
+    let sessions = file_paths
+        .iter()
+        .map(|p| locate_session(p))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let _guard = ARCHIVE_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock archives: {e}"))?;
+    let archive_dir = ensure_folder("archives")?;
+    let id = format!(
+        "{}-{}",
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        &Uuid::new_v4().to_string()[..8]
+    );
+    let archive_path = archive_dir.join(format!("{id}.tar.gz"));
+
+    let archived: Vec<ArchivedSession> = sessions
+        .iter()
+        .map(|s| ArchivedSession {
+            session_id: s.session_id.clone(),
+            project_name: s.project_name.clone(),
+            original_path: s.paths[0].to_string_lossy().to_string(),
+            last_modified: s.last_modified.clone(),
+            size_bytes: s.size_bytes,
+        })
+        .collect();
+    let manifest = serde_json::to_vec_pretty(&archived)
+        .map_err(|e| format!("Failed to serialize archive manifest: {e}"))?;
+
+    let temp_path = archive_dir.join(format!("{id}.tar.gz.tmp"));
+    if let Err(e) = write_archive(&temp_path, &sessions, &manifest) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    atomic_rename(&temp_path, &archive_path)?;
+
+    let mut archive = SessionArchive {
+        id,
+        compressed_size: fs::metadata(&archive_path).map(|m| m.len()).unwrap_or(0),
+        archive_path: archive_path.to_string_lossy().to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        original_size: sessions.iter().map(|s| s.size_bytes).sum(),
+        originals_removed: false,
+        sessions: archived,
+    };
+    let mut index: Vec<SessionArchive> = load_index(&archive_dir)?;
+    index.push(archive.clone());
+    save_index(&archive_dir, &index)?;
+    if !remove_originals {
+        return Ok(archive);
+    }
+
+    // Only delete once the archive is safely on disk and indexed
+    let mut removal = Ok(());
+    for path in sessions.iter().flat_map(|s| &s.paths) {
+        if let Err(e) = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        } {
+            removal = Err(format!(
+                "Archived, but failed to remove {}: {e}",
+                path.display()
+            ));
+            break;
+        }
+        archive.originals_removed = true;
+    }
+    // Record what was removed even when a removal failed, so it can be restored
+    if archive.originals_removed {
+        if let Some(entry) = index.last_mut() {
+            entry.originals_removed = true;
+        }
+        save_index(&archive_dir, &index)?;
+    }
+    removal.map(|()| archive)
+}
+
+fn restore_archive_sync(archive_id: &str) -> Result<ArchiveRestoreResult, String> {
+    let _guard = ARCHIVE_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock archives: {e}"))?;
+    let archive_dir = ensure_folder("archives")?;
+    let index: Vec<SessionArchive> = load_index(&archive_dir)?;
+    let archive = index
+        .iter()
+        .find(|a| a.id == archive_id)
+        .ok_or_else(|| format!("Archive not found: {archive_id}"))?;
+    let archive_path = PathBuf::from(&archive.archive_path);
+    if archive_path.parent() != Some(archive_dir.as_path()) {
+        return Err("Invalid archive path".to_string());
+    }
+
+    let claude_dir = claude_dir()?;
+    check_claude_folder(&claude_dir)?;
+    let file = fs::File::open(&archive_path).map_err(|e| format!("Failed to open archive: {e}"))?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    let mut result = ArchiveRestoreResult {
+        restored_files: Vec::new(),
+        skipped_files: Vec::new(),
+    };
+
+    for entry in tar
+        .entries()
+        .map_err(|e| format!("Failed to read archive: {e}"))?
+    {
+        let mut entry = entry.map_err(|e| format!("Failed to read archive entry: {e}"))?;
+        let relative = entry
+            .path()
+            .map_err(|e| format!("Invalid archive entry path: {e}"))?
+            .into_owned();
+        let safe = relative.starts_with("projects")
+            && relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+        if !safe {
+            continue;
+        }
+        let target = claude_dir.join(&relative);
+        if entry.header().entry_type().is_file() {
+            if target.exists() {
+                result
+                    .skipped_files
+                    .push(target.to_string_lossy().to_string());
+                continue;
+            }
+            result
+                .restored_files
+                .push(target.to_string_lossy().to_string());
+        }
+        entry
+            .unpack_in(&claude_dir)
+            .map_err(|e| format!("Failed to extract {}: {e}", relative.display()))?;
+        if target.extension().and_then(|e| e.to_str()) == Some("jsonl") {
+            touch(&target);
+        }
+    }
+    Ok(result)
+}
+
+/// A day count as a duration, saturating for absurdly large settings
+fn days(count: u64) -> Duration {
+    i64::try_from(count)
+        .ok()
+        .and_then(Duration::try_days)
+        .unwrap_or(Duration::MAX)
+}
+
+/// Sessions whose file expires within `within_days`, soonest first
+fn retention_candidates(
+    sessions: Vec<(String, ClaudeSession)>,
+    cleanup_period_days: u64,
+    within_days: u64,
+    now: DateTime<Utc>,
+) -> Vec<RetentionCandidate> {
+    let period = days(cleanup_period_days);
+    let horizon = now.checked_add_signed(days(within_days)).unwrap_or(now);
+
+    let mut candidates: Vec<(DateTime<Utc>, RetentionCandidate)> = sessions
+        .into_iter()
+        .filter_map(|(project_path, session)| {
+            let metadata = fs::metadata(&session.file_path).ok()?;
+            let modified: DateTime<Utc> = metadata.modified().ok()?.into();
+            let expires_at = modified.checked_add_signed(period)?;
+            (expires_at <= horizon).then(|| {
+                (
+                    expires_at,
+                    RetentionCandidate {
+                        project_path,
+                        expires_at: expires_at.to_rfc3339(),
+                        expired: expires_at <= now,
+                        size_bytes: metadata.len(),
+                        session,
+                    },
+                )
+            })
+        })
+        .collect();
+    candidates.sort_by_key(|(expires_at, _)| *expires_at);
+    candidates.into_iter().map(|(_, c)| c).collect()
+}
+
+/// Move sessions (and their subagent folders) to the app-managed trash
+#[tauri::command]
+pub async fn trash_sessions(file_paths: Vec<String>) -> Result<Vec<TrashEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || trash_sessions_sync(&file_paths))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// List trashed sessions, most recently trashed first
+#[tauri::command]
+pub async fn list_trash() -> Result<Vec<TrashEntry>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let mut index: Vec<TrashEntry> = load_index(&get_metadata_folder()?.join("trash"))?;
+        index.reverse();
+        Ok(index)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Move a trashed session back to its project folder
+#[tauri::command]
+pub async fn restore_from_trash(entry_id: String) -> Result<TrashEntry, String> {
+    tauri::async_runtime::spawn_blocking(move || restore_trash_entry(&entry_id))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Permanently delete trashed sessions (all of them if `entry_ids` is omitted)
+#[tauri::command]
+pub async fn empty_trash(entry_ids: Option<Vec<String>>) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || empty_trash_sync(entry_ids.as_deref()))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Bundle sessions into a compressed archive, optionally removing them from `projects/`
+#[tauri::command]
+pub async fn archive_sessions(
+    file_paths: Vec<String>,
+    remove_originals: bool,
+) -> Result<SessionArchive, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        archive_sessions_sync(&file_paths, remove_originals)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// List archives, newest first
+#[tauri::command]
+pub async fn list_session_archives() -> Result<Vec<SessionArchive>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let mut index: Vec<SessionArchive> = load_index(&get_metadata_folder()?.join("archives"))?;
+        index.reverse();
+        Ok(index)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Extract an archive back into `~/.claude/projects`, never overwriting existing files
+#[tauri::command]
+pub async fn restore_session_archive(archive_id: String) -> Result<ArchiveRestoreResult, String> {
+    tauri::async_runtime::spawn_blocking(move || restore_archive_sync(&archive_id))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Preview which sessions Claude Code's `cleanupPeriodDays` will delete next
+///
+/// Claude Code deletes session files whose modification time is older than the
+/// period when it starts. `project_path` (the project's actual directory) adds
+/// its project and local settings to the lookup.
+#[tauri::command]
+pub async fn preview_retention_cleanup(
+    claude_path: String,
+    project_path: Option<String>,
+    within_days: Option<u64>,
+) -> Result<RetentionPreview, String> {
+    let (cleanup_period_days, setting_scope) = {
+        let project_path = project_path.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            effective_cleanup_period_days(project_path.as_deref())
+        })
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+    };
+    let within_days = within_days.unwrap_or(DEFAULT_RETENTION_WINDOW_DAYS);
+
+    let mut sessions = Vec::new();
+    for project in scan_projects(claude_path).await? {
+        for session in load_project_sessions(project.path.clone(), Some(false)).await? {
+            sessions.push((project.path.clone(), session));
+        }
+    }
+
+    let now = Utc::now();
+    let cutoff = now
+        .checked_sub_signed(days(cleanup_period_days))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let candidates = tauri::async_runtime::spawn_blocking(move || {
+        retention_candidates(sessions, cleanup_period_days, within_days, now)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?;
+
+    Ok(RetentionPreview {
+        cleanup_period_days,
+        setting_scope,
+        cutoff: cutoff.to_rfc3339(),
+        within_days,
+        candidates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use tempfile::TempDir;
+
+    /// Sets up a test environment with a temporary HOME directory.
+    /// NOTE: Tests using this MUST run with --test-threads=1 because
+    /// `env::set_var("HOME")` is process-global and not thread-safe.
+    fn setup_test_env() -> (TempDir, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let home = temp_dir.path().canonicalize().unwrap();
+        env::set_var("HOME", &home);
+        let project_dir = home.join(".claude/projects/-test-project");
+        fs::create_dir_all(&project_dir).unwrap();
+        (temp_dir, project_dir)
+    }
+
+    fn write_session(project_dir: &Path, id: &str, age_days: u64) -> String {
+        let path = project_dir.join(format!("{id}.jsonl"));
+        fs::write(
+            &path,
+            format!(
+                r#"{{"type":"user","uuid":"u1","sessionId":"{id}","timestamp":"2025-01-01T00:00:00Z","message":{{"role":"user","content":"hello"}}}}"#
+            ) + "\n",
+        )
+        .unwrap();
+        let modified = SystemTime::now() - std::time::Duration::from_secs(age_days * 86_400);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_trash_and_restore_round_trip() {
+        let (_temp, project_dir) = setup_test_env();
+        let session = write_session(&project_dir, "s1", 40);
+        fs::create_dir_all(project_dir.join("s1/subagents")).unwrap();
+        fs::write(project_dir.join("s1/subagents/agent-1.jsonl"), "{}\n").unwrap();
+
+        let trashed = trash_sessions_sync(std::slice::from_ref(&session)).unwrap();
+        assert_eq!(trashed[0].files, ["s1.jsonl", "s1"]);
+        assert!(!Path::new(&session).exists());
+        assert!(!project_dir.join("s1").exists());
+
+        // A new session with the same name blocks the restore
+        fs::write(&session, "{}\n").unwrap();
+        assert!(restore_trash_entry(&trashed[0].id).is_err());
+        fs::remove_file(&session).unwrap();
+
+        let restored = restore_trash_entry(&trashed[0].id).unwrap();
+        assert_eq!(restored.session_id, "s1");
+        assert!(project_dir.join("s1/subagents/agent-1.jsonl").exists());
+        let modified: DateTime<Utc> = fs::metadata(&session).unwrap().modified().unwrap().into();
+        assert!(Utc::now() - modified < Duration::minutes(1));
+        assert!(
+            load_index::<TrashEntry>(&get_metadata_folder().unwrap().join("trash"))
+                .unwrap()
+                .is_empty()
+        );
+
+        trash_sessions_sync(std::slice::from_ref(&session)).unwrap();
+        assert_eq!(empty_trash_sync(None).unwrap(), 1);
+        assert!(!Path::new(&session).exists());
+    }
+
+    #[test]
+    fn test_trash_rejects_paths_outside_projects() {
+        let (_temp, project_dir) = setup_test_env();
+        let outside = project_dir
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("x.jsonl");
+        fs::write(&outside, "{}\n").unwrap();
+        assert!(trash_sessions_sync(&[outside.to_string_lossy().to_string()]).is_err());
+        assert!(outside.exists());
+    }
+
+    #[test]
+    fn test_archive_and_restore() {
+        let (_temp, project_dir) = setup_test_env();
+        let first = write_session(&project_dir, "s1", 5);
+        let second = write_session(&project_dir, "s2", 5);
+        fs::create_dir_all(project_dir.join("s2/subagents")).unwrap();
+        fs::write(project_dir.join("s2/subagents/agent-1.jsonl"), "{}\n").unwrap();
+
+        let archive = archive_sessions_sync(&[first.clone(), second.clone()], true).unwrap();
+        assert_eq!(archive.sessions.len(), 2);
+        assert!(archive.compressed_size > 0);
+        assert!(!Path::new(&first).exists());
+        assert!(!project_dir.join("s2").exists());
+        let index: Vec<SessionArchive> =
+            load_index(&get_metadata_folder().unwrap().join("archives")).unwrap();
+        assert_eq!(index, std::slice::from_ref(&archive));
+        assert!(index[0].originals_removed);
+
+        // An existing file is never overwritten
+        fs::write(&first, "kept\n").unwrap();
+        let result = restore_archive_sync(&archive.id).unwrap();
+        assert_eq!(result.skipped_files, std::slice::from_ref(&first));
+        assert_eq!(result.restored_files.len(), 2);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "kept\n");
+        assert!(fs::read_to_string(&second)
+            .unwrap()
+            .contains("\"sessionId\":\"s2\""));
+        assert!(project_dir.join("s2/subagents/agent-1.jsonl").exists());
+    }
+
+    #[test]
+    fn test_retention_candidates() {
+        let (_temp, project_dir) = setup_test_env();
+        let session_for = |id: &str, age_days| {
+            let file_path = write_session(&project_dir, id, age_days);
+            (
+                project_dir.to_string_lossy().to_string(),
+                ClaudeSession {
+                    session_id: file_path.clone(),
+                    actual_session_id: id.to_string(),
+                    file_path,
+                    project_name: "test-project".to_string(),
+                    message_count: 1,
+                    first_message_time: String::new(),
+                    last_message_time: String::new(),
+                    last_modified: String::new(),
+                    has_tool_use: false,
+                    has_errors: false,
+                    summary: None,
+                    auto_tags: Vec::new(),
+                },
+            )
+        };
+        let sessions = vec![
+            session_for("fresh", 1),
+            session_for("soon", 27),
+            session_for("expired", 45),
+        ];
+
+        let candidates = retention_candidates(sessions, 30, 7, Utc::now());
+        let ids: Vec<_> = candidates
+            .iter()
+            .map(|c| (c.session.actual_session_id.as_str(), c.expired))
+            .collect();
+        assert_eq!(ids, [("expired", true), ("soon", false)]);
+    }
+}
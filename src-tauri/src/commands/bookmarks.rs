@@ -0,0 +1,353 @@
+//! Bookmarks and notes on individual messages
+//!
+//! Annotations live in `SessionMetadata.annotations` next to the session's
+//! other metadata; [`list_bookmarks`] resolves them against the session files
+//! so the frontend can jump straight to the message.
+
+use super::metadata::{current_metadata, update_metadata, MetadataState};
+use super::session::locate_messages;
+use crate::models::{BookmarkEntry, ClaudeMessage, MessageAnnotation, UserMetadata};
+use crate::utils::extract_project_name;
+use rayon::prelude::*;
+use serde_json::Value;
+use std::path::Path;
+use tauri::State;
+use uuid::Uuid;
+
+/// Characters of message text shown with a bookmark
+const PREVIEW_CHARS: usize = 200;
+
+fn validate_annotation(annotation: &MessageAnnotation) -> Result<(), String> {
+    if annotation.message_uuid.trim().is_empty() {
+        return Err("Annotation must reference a message uuid".to_string());
+    }
+    if let Some(anchor) = &annotation.anchor {
+        if anchor.start >= anchor.end {
+            return Err(format!(
+                "Invalid text anchor: start {} must be before end {}",
+                anchor.start, anchor.end
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Insert or replace an annotation, filling in its id and timestamps
+///
+/// An annotation that is neither bookmarked nor has a note is removed.
+fn upsert_annotation(
+    metadata: &mut UserMetadata,
+    session_id: &str,
+    mut annotation: MessageAnnotation,
+    now: &str,
+) {
+    if annotation
+        .note
+        .as_ref()
+        .is_some_and(|note| note.trim().is_empty())
+    {
+        annotation.note = None;
+    }
+
+    let session = metadata.get_session_mut(session_id);
+    let existing = session
+        .annotations
+        .iter()
+        .position(|a| !annotation.id.is_empty() && a.id == annotation.id);
+    let keep = annotation.bookmarked || annotation.note.is_some();
+
+    match existing {
+        Some(index) if !keep => {
+            session.annotations.remove(index);
+        }
+        Some(index) => {
+            annotation
+                .created_at
+                .clone_from(&session.annotations[index].created_at);
+            annotation.updated_at = now.to_string();
+            session.annotations[index] = annotation;
+        }
+        None if keep => {
+            if annotation.id.is_empty() {
+                annotation.id = Uuid::new_v4().to_string();
+            }
+            annotation.created_at = now.to_string();
+            annotation.updated_at = now.to_string();
+            session.annotations.push(annotation);
+        }
+        None => {}
+    }
+
+    if session.is_empty() {
+        metadata.sessions.remove(session_id);
+    }
+}
+
+fn message_preview(message: &ClaudeMessage) -> Option<String> {
+    let text = match message.content.as_ref()? {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let mut preview: String = text.chars().take(PREVIEW_CHARS).collect();
+    if text.chars().nth(PREVIEW_CHARS).is_some() {
+        preview.push('…');
+    }
+    Some(preview)
+}
+
+/// Resolve bookmarks (and, with `include_notes`, noted messages) to their messages
+///
+/// Sessions whose file is gone still list their bookmarks with `found: false`.
+fn collect_bookmarks(
+    metadata: &UserMetadata,
+    include_notes: bool,
+    exclude_sidechain: bool,
+) -> Vec<BookmarkEntry> {
+    let sessions: Vec<(&String, Vec<&MessageAnnotation>)> = metadata
+        .sessions
+        .iter()
+        .map(|(session_id, session)| {
+            let listed = session
+                .annotations
+                .iter()
+                .filter(|a| a.bookmarked || (include_notes && a.note.is_some()))
+                .collect::<Vec<_>>();
+            (session_id, listed)
+        })
+        .filter(|(_, listed)| !listed.is_empty())
+        .collect();
+
+    let mut entries: Vec<BookmarkEntry> = sessions
+        .par_iter()
+        .flat_map_iter(|(session_id, annotations)| {
+            let uuids: Vec<&str> = annotations
+                .iter()
+                .map(|a| a.message_uuid.as_str())
+                .collect();
+            let located = locate_messages(session_id, &uuids, exclude_sidechain).ok();
+            let project_name = Path::new(session_id.as_str())
+                .parent()
+                .and_then(|p| p.file_name())
+                .map(|n| extract_project_name(&n.to_string_lossy()))
+                .unwrap_or_default();
+
+            annotations.iter().map(move |annotation| {
+                let message = located.as_ref().and_then(|(total, found)| {
+                    found
+                        .get(&annotation.message_uuid)
+                        .map(|(index, message)| (*total, *index, message))
+                });
+                BookmarkEntry {
+                    session_id: (*session_id).clone(),
+                    project_name: project_name.clone(),
+                    annotation: (*annotation).clone(),
+                    found: message.is_some(),
+                    timestamp: message.map(|(_, _, m)| m.timestamp.clone()),
+                    message_type: message.map(|(_, _, m)| m.message_type.clone()),
+                    preview: message.and_then(|(_, _, m)| message_preview(m)),
+                    page_offset: message.map(|(total, index, _)| total - index - 1),
+                    total_count: located.as_ref().map(|(total, _)| *total),
+                }
+            })
+        })
+        .collect();
+
+    // Most recently added first
+    entries.sort_by(|a, b| {
+        b.annotation
+            .created_at
+            .cmp(&a.annotation.created_at)
+            .then_with(|| a.annotation.id.cmp(&b.annotation.id))
+    });
+    entries
+}
+
+/// Add or update a bookmark/note on a message
+///
+/// Leave `id` empty to create a new annotation. Clearing both `bookmarked`
+/// and `note` removes it.
+#[tauri::command]
+pub async fn set_message_annotation(
+    session_id: String,
+    annotation: MessageAnnotation,
+    state: State<'_, MetadataState>,
+) -> Result<UserMetadata, String> {
+    validate_annotation(&annotation)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    update_metadata(&state, |metadata| {
+        upsert_annotation(metadata, &session_id, annotation, &now);
+    })
+    .await
+}
+
+/// Remove a bookmark/note from a session
+#[tauri::command]
+pub async fn remove_message_annotation(
+    session_id: String,
+    annotation_id: String,
+    state: State<'_, MetadataState>,
+) -> Result<UserMetadata, String> {
+    update_metadata(&state, |metadata| {
+        if let Some(session) = metadata.sessions.get_mut(&session_id) {
+            session.annotations.retain(|a| a.id != annotation_id);
+            if session.is_empty() {
+                metadata.sessions.remove(&session_id);
+            }
+        }
+    })
+    .await
+}
+
+/// List bookmarked messages across all projects, newest bookmark first
+///
+/// `exclude_sidechain` must match what the session view passes to
+/// `load_session_messages_paginated`, so `page_offset` counts the same messages.
+#[tauri::command]
+pub async fn list_bookmarks(
+    include_notes: Option<bool>,
+    exclude_sidechain: Option<bool>,
+    state: State<'_, MetadataState>,
+) -> Result<Vec<BookmarkEntry>, String> {
+    let metadata = current_metadata(&state).await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        Ok(collect_bookmarks(
+            &metadata,
+            include_notes.unwrap_or(false),
+            exclude_sidechain.unwrap_or(false),
+        ))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TextAnchor;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn annotation(message_uuid: &str, bookmarked: bool, note: Option<&str>) -> MessageAnnotation {
+        MessageAnnotation {
+            message_uuid: message_uuid.to_string(),
+            bookmarked,
+            note: note.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_upsert_annotation_lifecycle() {
+        let mut metadata = UserMetadata::new();
+        let mut bookmark = annotation("m1", true, None);
+        bookmark.anchor = Some(TextAnchor {
+            start: 4,
+            end: 12,
+            quote: "went wro".to_string(),
+        });
+        upsert_annotation(&mut metadata, "s", bookmark, "2025-01-01T00:00:00Z");
+        let saved = metadata.sessions["s"].annotations[0].clone();
+        assert!(!saved.id.is_empty());
+        assert_eq!(saved.created_at, "2025-01-01T00:00:00Z");
+
+        let mut edited = saved.clone();
+        edited.note = Some("**this** is where it went wrong".to_string());
+        upsert_annotation(&mut metadata, "s", edited, "2025-01-02T00:00:00Z");
+        let updated = &metadata.sessions["s"].annotations[0];
+        assert_eq!(metadata.sessions["s"].annotations.len(), 1);
+        assert_eq!(updated.created_at, "2025-01-01T00:00:00Z");
+        assert_eq!(updated.updated_at, "2025-01-02T00:00:00Z");
+
+        let mut cleared = updated.clone();
+        cleared.bookmarked = false;
+        cleared.note = Some("  ".to_string());
+        upsert_annotation(&mut metadata, "s", cleared, "2025-01-03T00:00:00Z");
+        assert!(!metadata.sessions.contains_key("s"));
+
+        let mut bad_anchor = annotation("m1", true, None);
+        bad_anchor.anchor = Some(TextAnchor {
+            start: 5,
+            end: 5,
+            quote: String::new(),
+        });
+        assert!(validate_annotation(&bad_anchor).is_err());
+        assert!(validate_annotation(&annotation("", true, None)).is_err());
+        assert!(validate_annotation(&annotation("m1", true, None)).is_ok());
+    }
+
+    #[test]
+    fn test_collect_bookmarks_locates_messages() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("-Users-me-app");
+        fs::create_dir_all(&project_dir).unwrap();
+        let session_path = project_dir.join("s1.jsonl");
+        let lines = [
+            json!({"type": "user", "uuid": "u1", "sessionId": "s1", "timestamp": "2025-01-01T00:00:00Z",
+                   "message": {"role": "user", "content": "refactor the parser"}}),
+            json!({"type": "assistant", "uuid": "a1", "parentUuid": "u1", "sessionId": "s1",
+                   "timestamp": "2025-01-01T00:00:01Z",
+                   "message": {"role": "assistant", "content": [{"type": "text", "text": "Deleted the tests."}]}}),
+            json!({"type": "user", "uuid": "u2", "parentUuid": "a1", "sessionId": "s1",
+                   "timestamp": "2025-01-01T00:00:02Z",
+                   "message": {"role": "user", "content": "why?"}}),
+        ];
+        let content = lines.iter().fold(String::new(), |mut content, line| {
+            content.push_str(&line.to_string());
+            content.push('\n');
+            content
+        });
+        fs::write(&session_path, content).unwrap();
+        let session_id = session_path.to_string_lossy().to_string();
+
+        let mut metadata = UserMetadata::new();
+        upsert_annotation(
+            &mut metadata,
+            &session_id,
+            annotation("a1", true, None),
+            "2",
+        );
+        upsert_annotation(
+            &mut metadata,
+            &session_id,
+            annotation("u1", false, Some("context")),
+            "1",
+        );
+        upsert_annotation(
+            &mut metadata,
+            "/gone.jsonl",
+            annotation("x", true, None),
+            "3",
+        );
+
+        let bookmarks = collect_bookmarks(&metadata, false, false);
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].session_id, "/gone.jsonl");
+        assert!(!bookmarks[0].found);
+
+        let entry = &bookmarks[1];
+        assert!(entry.found);
+        assert_eq!(entry.project_name, "app");
+        assert_eq!(entry.preview.as_deref(), Some("Deleted the tests."));
+        assert_eq!(entry.total_count, Some(3));
+        // One newer message: a page loaded at offset 1 ends with the bookmark
+        assert_eq!(entry.page_offset, Some(1));
+
+        let with_notes = collect_bookmarks(&metadata, true, false);
+        assert_eq!(with_notes.len(), 3);
+        assert_eq!(
+            with_notes[2].preview.as_deref(),
+            Some("refactor the parser")
+        );
+    }
+}
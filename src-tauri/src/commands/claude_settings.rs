@@ -2,11 +2,21 @@
 //!
 //! This module provides commands for reading and writing Claude Code settings
 //! across different scopes (user, project, local, managed) and MCP server configurations.
+//!
+//! These files are also written by Claude Code itself (`~/.claude.json` on
+//! almost every run), so reads return a version of each file (or MCP field)
+//! and saves send back the version the edit was based on: content that
+//! changed since then is not overwritten.
 
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs;
+use std::hash::Hasher;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 /// All settings scopes in a single structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +25,8 @@ pub struct AllSettings {
     pub project: Option<String>,
     pub local: Option<String>,
     pub managed: Option<String>,
+    /// Version of the user, project and local content, to pass to `save_settings`
+    pub versions: HashMap<String, String>,
 }
 
 /// MCP servers from both settings.json and .mcp.json
@@ -37,6 +49,108 @@ pub struct AllMCPServers {
     pub user_claude_json: Option<serde_json::Value>,
     /// Local/Project-scoped MCP from `~/.claude.json` → `projects.<path>.mcpServers` (official)
     pub local_claude_json: Option<serde_json::Value>,
+    /// Version of each source's servers, keyed by `save_mcp_servers` source
+    pub versions: HashMap<String, String>,
+}
+
+lazy_static! {
+    /// Hash of each config file as last written by the app
+    static ref WRITTEN_VERSIONS: Mutex<HashMap<PathBuf, u64>> = Mutex::new(HashMap::new());
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(content.as_bytes());
+    hasher.finish()
+}
+
+fn value_hash(value: Option<&serde_json::Value>) -> u64 {
+    content_hash(&value.map_or_else(String::new, serde_json::Value::to_string))
+}
+
+/// Version string of a content hash; hex, as a `u64` doesn't fit a JS number
+fn version(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+/// Reject a save unless the content on disk (hashing to `current`) is still
+/// the `expected` version the edit was based on
+///
+/// Without an expected version (imports replacing the whole file) the save
+/// goes ahead.
+fn check_unchanged(path: &Path, expected: Option<&str>, current: u64) -> Result<(), String> {
+    match expected {
+        Some(expected) if expected != version(current) => Err(format!(
+            "Settings file changed on disk since it was read: {}. Reload it before saving.",
+            path.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Whether `path` now holds something other than what the app last wrote
+pub(crate) fn is_unseen_change(path: &Path) -> bool {
+    let current = content_hash(&read_settings_file(path).unwrap_or_default());
+    WRITTEN_VERSIONS
+        .lock()
+        .map_or(true, |written| written.get(path) != Some(&current))
+}
+
+/// Parse a JSON config file; a missing or unparsable file counts as empty
+fn read_json_file(path: &Path) -> Result<serde_json::Value, String> {
+    if !path.exists() {
+        return Ok(serde_json::json!({}));
+    }
+    let content = read_settings_file(path)?;
+    Ok(serde_json::from_str(&content).unwrap_or(serde_json::json!({})))
+}
+
+/// The value at `keys` in `json`
+fn json_field<'a>(json: &'a serde_json::Value, keys: &[&str]) -> Option<&'a serde_json::Value> {
+    keys.iter().try_fold(json, |value, key| value.get(key))
+}
+
+const MCP_SERVERS_FIELD: &str = "mcpServers";
+
+/// Keys of a project's MCP servers in `~/.claude.json`
+fn local_mcp_keys(project_path: &str) -> [&str; 3] {
+    ["projects", project_path, MCP_SERVERS_FIELD]
+}
+
+/// The servers in a `.mcp.json`, with or without the `mcpServers` wrapper
+fn read_mcp_file(path: &Path) -> Option<serde_json::Value> {
+    if !path.exists() {
+        return None;
+    }
+    let content = read_settings_file(path).ok()?;
+    let json = serde_json::from_str::<serde_json::Value>(&content).ok()?;
+    Some(match json.get(MCP_SERVERS_FIELD) {
+        Some(servers) => servers.clone(),
+        None => json,
+    })
+}
+
+/// Config files the settings manager shows, by scope
+///
+/// Scopes are the settings scopes plus `user_mcp` (`~/.claude/.mcp.json`),
+/// `project_mcp` (`<project>/.mcp.json`) and `claude_json` (`~/.claude.json`).
+pub(crate) fn config_files(
+    project_path: Option<&str>,
+) -> Result<Vec<(&'static str, PathBuf)>, String> {
+    let mut files = vec![
+        ("user", get_user_settings_path()?),
+        ("user_mcp", get_user_mcp_path()?),
+        ("claude_json", get_claude_json_path()?),
+    ];
+    if let Ok(path) = get_managed_settings_path() {
+        files.push(("managed", path));
+    }
+    if let Some(project_path) = project_path {
+        files.push(("project", get_settings_path("project", Some(project_path))?));
+        files.push(("local", get_settings_path("local", Some(project_path))?));
+        files.push(("project_mcp", get_project_mcp_path(project_path)?));
+    }
+    Ok(files)
 }
 
 /// Get the user settings path (~/.claude/settings.json)
@@ -164,9 +278,39 @@ fn write_settings_file(path: &Path, content: &str) -> Result<(), String> {
 
     super::fs_utils::atomic_rename(&temp_path, path)?;
 
+    if let Ok(mut written) = WRITTEN_VERSIONS.lock() {
+        written.insert(path.to_path_buf(), content_hash(content));
+    }
     Ok(())
 }
 
+/// Claude Code's `cleanupPeriodDays` when no scope sets it
+pub(crate) const DEFAULT_CLEANUP_PERIOD_DAYS: u64 = 30;
+
+/// Resolve `cleanupPeriodDays` with Claude Code's precedence (managed > local >
+/// project > user)
+///
+/// # Returns
+/// The period in days and the scope it came from ("default" if none sets it)
+pub(crate) fn effective_cleanup_period_days(project_path: Option<&str>) -> (u64, String) {
+    let scopes: &[&str] = if project_path.is_some() {
+        &["managed", "local", "project", "user"]
+    } else {
+        &["managed", "user"]
+    };
+    for scope in scopes {
+        let days = get_settings_path(scope, project_path)
+            .and_then(|path| read_settings_file(&path))
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .and_then(|settings| settings.get("cleanupPeriodDays")?.as_u64());
+        if let Some(days) = days {
+            return (days, (*scope).to_string());
+        }
+    }
+    (DEFAULT_CLEANUP_PERIOD_DAYS, "default".to_string())
+}
+
 /// Get settings for a specific scope
 ///
 /// # Arguments
@@ -194,14 +338,18 @@ pub async fn get_settings_by_scope(
 /// * `scope` - One of: "user", "project", "local" (NOT "managed" - read-only)
 /// * `content` - JSON string to save
 /// * `project_path` - Required for "project" and "local" scopes (must be absolute path)
+/// * `expected_version` - The scope's version from `get_all_settings` the edit
+///   was based on; omitted to replace the file whatever it holds
 ///
 /// # Errors
-/// Returns error if scope is "managed" or if JSON is invalid
+/// Returns error if scope is "managed", if JSON is invalid, or if the file
+/// no longer holds the expected version
 #[tauri::command]
 pub async fn save_settings(
     scope: String,
     content: String,
     project_path: Option<String>,
+    expected_version: Option<String>,
 ) -> Result<(), String> {
     // Managed settings are read-only
     if scope == "managed" {
@@ -210,6 +358,11 @@ pub async fn save_settings(
 
     tauri::async_runtime::spawn_blocking(move || {
         let path = get_settings_path(&scope, project_path.as_deref())?;
+        check_unchanged(
+            &path,
+            expected_version.as_deref(),
+            content_hash(&read_settings_file(&path)?),
+        )?;
         write_settings_file(&path, &content)
     })
     .await
@@ -222,23 +375,22 @@ pub async fn save_settings(
 /// * `project_path` - Optional project path for project/local settings (must be absolute)
 ///
 /// # Returns
-/// `AllSettings` struct with all 4 scopes (each is `Option<String>`)
+/// `AllSettings` struct with all 4 scopes (each is `Option<String>`) and the
+/// versions of the editable ones
 #[tauri::command]
 pub async fn get_all_settings(project_path: Option<String>) -> Result<AllSettings, String> {
     tauri::async_runtime::spawn_blocking(move || {
-        let user = get_user_settings_path()
-            .ok()
-            .and_then(|p| read_settings_file(&p).ok());
-
-        let project = project_path
-            .as_deref()
-            .and_then(|pp| get_settings_path("project", Some(pp)).ok())
-            .and_then(|p| read_settings_file(&p).ok());
-
-        let local = project_path
-            .as_deref()
-            .and_then(|pp| get_settings_path("local", Some(pp)).ok())
-            .and_then(|p| read_settings_file(&p).ok());
+        let mut versions = HashMap::new();
+        let mut read_scope = |scope: &str| {
+            let path = get_settings_path(scope, project_path.as_deref()).ok()?;
+            let content = read_settings_file(&path).ok()?;
+            versions.insert(scope.to_string(), version(content_hash(&content)));
+            Some(content)
+        };
+
+        let user = read_scope("user");
+        let project = read_scope("project");
+        let local = read_scope("local");
 
         let managed = get_managed_settings_path()
             .ok()
@@ -249,6 +401,7 @@ pub async fn get_all_settings(project_path: Option<String>) -> Result<AllSetting
             project,
             local,
             managed,
+            versions,
         })
     })
     .await
@@ -312,76 +465,60 @@ pub async fn get_mcp_servers() -> Result<MCPServers, String> {
 #[tauri::command]
 pub async fn get_all_mcp_servers(project_path: Option<String>) -> Result<AllMCPServers, String> {
     tauri::async_runtime::spawn_blocking(move || {
+        let mut versions = HashMap::new();
+        let mut versioned = |source: &str, servers: Option<serde_json::Value>| {
+            versions.insert(source.to_string(), version(value_hash(servers.as_ref())));
+            servers
+        };
+
         // User settings.json mcpServers (legacy)
         let user_settings = get_user_settings_path().ok().and_then(|p| {
-            read_settings_file(&p).ok().and_then(|content| {
+            let servers = read_settings_file(&p).ok().and_then(|content| {
                 serde_json::from_str::<serde_json::Value>(&content)
                     .ok()
-                    .and_then(|json| json.get("mcpServers").cloned())
-            })
+                    .and_then(|json| json.get(MCP_SERVERS_FIELD).cloned())
+            });
+            versioned("user_settings", servers)
         });
 
         // User .mcp.json (legacy)
-        let user_mcp_file = get_user_mcp_path().ok().and_then(|p| {
-            if !p.exists() {
-                return None;
-            }
-            read_settings_file(&p).ok().and_then(|content| {
-                serde_json::from_str::<serde_json::Value>(&content)
-                    .ok()
-                    .map(|json| {
-                        // Check if it has mcpServers key or is servers directly
-                        if let Some(servers) = json.get("mcpServers") {
-                            servers.clone()
-                        } else {
-                            json
-                        }
-                    })
-            })
-        });
+        let user_mcp_file = get_user_mcp_path()
+            .ok()
+            .and_then(|p| versioned("user_mcp", read_mcp_file(&p)));
 
         // Project .mcp.json
         let project_mcp_file = project_path.as_deref().and_then(|pp| {
             let p = get_project_mcp_path(pp).ok()?;
-            if !p.exists() {
-                return None;
-            }
-            read_settings_file(&p).ok().and_then(|content| {
-                serde_json::from_str::<serde_json::Value>(&content)
-                    .ok()
-                    .map(|json| {
-                        // Check if it has mcpServers key or is servers directly
-                        if let Some(servers) = json.get("mcpServers") {
-                            servers.clone()
-                        } else {
-                            json
-                        }
-                    })
-            })
+            versioned("project_mcp", read_mcp_file(&p))
         });
 
         // Read ~/.claude.json (official config file)
-        let claude_json = get_claude_json_path().ok().and_then(|p| {
+        let claude_json_path = get_claude_json_path().ok();
+        let claude_json = claude_json_path.as_ref().and_then(|p| {
             if !p.exists() {
                 return None;
             }
-            read_settings_file(&p)
+            read_settings_file(p)
                 .ok()
                 .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
         });
 
         // User-scoped MCP from ~/.claude.json → mcpServers
-        let user_claude_json = claude_json
-            .as_ref()
-            .and_then(|json| json.get("mcpServers").cloned());
+        let user_claude_json = versioned(
+            "user_claude_json",
+            claude_json
+                .as_ref()
+                .and_then(|json| json.get(MCP_SERVERS_FIELD).cloned()),
+        );
 
         // Local/Project-scoped MCP from ~/.claude.json → projects.<path>.mcpServers
         let local_claude_json = project_path.as_deref().and_then(|pp| {
-            claude_json.as_ref().and_then(|json| {
-                json.get("projects")
-                    .and_then(|projects| projects.get(pp))
-                    .and_then(|project| project.get("mcpServers").cloned())
-            })
+            versioned(
+                "local_claude_json",
+                claude_json
+                    .as_ref()
+                    .and_then(|json| json_field(json, &local_mcp_keys(pp)).cloned()),
+            )
         });
 
         Ok(AllMCPServers {
@@ -390,6 +527,7 @@ pub async fn get_all_mcp_servers(project_path: Option<String>) -> Result<AllMCPS
             project_mcp_file,
             user_claude_json,
             local_claude_json,
+            versions,
         })
     })
     .await
@@ -402,48 +540,46 @@ pub async fn get_all_mcp_servers(project_path: Option<String>) -> Result<AllMCPS
 /// * `source` - One of: `user_settings`, `user_mcp`, `project_mcp`, `user_claude_json`, `local_claude_json`
 /// * `servers` - JSON string of MCP servers object
 /// * `project_path` - Required for `project_mcp` and `local_claude_json` sources
+/// * `expected_version` - The source's version from `get_all_mcp_servers` the
+///   edit was based on
 #[tauri::command]
 pub async fn save_mcp_servers(
     source: String,
     servers: String,
     project_path: Option<String>,
+    expected_version: Option<String>,
 ) -> Result<(), String> {
     // Validate servers JSON
     let servers_value: serde_json::Value =
         serde_json::from_str(&servers).map_err(|e| format!("Invalid MCP servers JSON: {e}"))?;
 
     tauri::async_runtime::spawn_blocking(move || {
+        // Only the servers are replaced, so only they must be unchanged since read
+        let expected = expected_version.as_deref();
         match source.as_str() {
             "user_settings" => {
                 // Update mcpServers field in ~/.claude/settings.json (legacy)
                 let path = get_user_settings_path()?;
-                let mut settings: serde_json::Value = if path.exists() {
-                    let content = read_settings_file(&path)?;
-                    serde_json::from_str(&content).unwrap_or(serde_json::json!({}))
-                } else {
-                    serde_json::json!({})
-                };
+                let mut settings = read_json_file(&path)?;
+                check_unchanged(&path, expected, value_hash(settings.get(MCP_SERVERS_FIELD)))?;
 
-                settings["mcpServers"] = servers_value;
+                settings[MCP_SERVERS_FIELD] = servers_value;
                 let content = serde_json::to_string_pretty(&settings)
                     .map_err(|e| format!("Failed to serialize settings: {e}"))?;
                 write_settings_file(&path, &content)?;
             }
-            "user_mcp" => {
-                // Write to ~/.claude/.mcp.json (legacy)
-                let path = get_user_mcp_path()?;
-                // Store with mcpServers wrapper for consistency
-                let mcp_json = serde_json::json!({ "mcpServers": servers_value });
-                let content = serde_json::to_string_pretty(&mcp_json)
-                    .map_err(|e| format!("Failed to serialize MCP config: {e}"))?;
-                write_settings_file(&path, &content)?;
-            }
-            "project_mcp" => {
-                // Write to <project>/.mcp.json
-                let pp = project_path.ok_or("project_path required for project_mcp source")?;
-                let path = get_project_mcp_path(&pp)?;
+            "user_mcp" | "project_mcp" => {
+                // Write to ~/.claude/.mcp.json (legacy) or <project>/.mcp.json
+                let path = if source == "user_mcp" {
+                    get_user_mcp_path()?
+                } else {
+                    let pp = project_path.ok_or("project_path required for project_mcp source")?;
+                    get_project_mcp_path(&pp)?
+                };
+                check_unchanged(&path, expected, value_hash(read_mcp_file(&path).as_ref()))?;
+
                 // Store with mcpServers wrapper for consistency
-                let mcp_json = serde_json::json!({ "mcpServers": servers_value });
+                let mcp_json = serde_json::json!({ MCP_SERVERS_FIELD: servers_value });
                 let content = serde_json::to_string_pretty(&mcp_json)
                     .map_err(|e| format!("Failed to serialize MCP config: {e}"))?;
                 write_settings_file(&path, &content)?;
@@ -451,14 +587,14 @@ pub async fn save_mcp_servers(
             "user_claude_json" => {
                 // Update mcpServers field in ~/.claude.json (official)
                 let path = get_claude_json_path()?;
-                let mut claude_json: serde_json::Value = if path.exists() {
-                    let content = read_settings_file(&path)?;
-                    serde_json::from_str(&content).unwrap_or(serde_json::json!({}))
-                } else {
-                    serde_json::json!({})
-                };
-
-                claude_json["mcpServers"] = servers_value;
+                let mut claude_json = read_json_file(&path)?;
+                check_unchanged(
+                    &path,
+                    expected,
+                    value_hash(claude_json.get(MCP_SERVERS_FIELD)),
+                )?;
+
+                claude_json[MCP_SERVERS_FIELD] = servers_value;
                 let content = serde_json::to_string_pretty(&claude_json)
                     .map_err(|e| format!("Failed to serialize claude.json: {e}"))?;
                 write_settings_file(&path, &content)?;
@@ -468,12 +604,12 @@ pub async fn save_mcp_servers(
                 let pp =
                     project_path.ok_or("project_path required for local_claude_json source")?;
                 let path = get_claude_json_path()?;
-                let mut claude_json: serde_json::Value = if path.exists() {
-                    let content = read_settings_file(&path)?;
-                    serde_json::from_str(&content).unwrap_or(serde_json::json!({}))
-                } else {
-                    serde_json::json!({})
-                };
+                let mut claude_json = read_json_file(&path)?;
+                check_unchanged(
+                    &path,
+                    expected,
+                    value_hash(json_field(&claude_json, &local_mcp_keys(&pp))),
+                )?;
 
                 // Ensure projects object exists
                 if claude_json.get("projects").is_none() {
@@ -485,7 +621,7 @@ pub async fn save_mcp_servers(
                     claude_json["projects"][&pp] = serde_json::json!({});
                 }
 
-                claude_json["projects"][&pp]["mcpServers"] = servers_value;
+                claude_json["projects"][&pp][MCP_SERVERS_FIELD] = servers_value;
                 let content = serde_json::to_string_pretty(&claude_json)
                     .map_err(|e| format!("Failed to serialize claude.json: {e}"))?;
                 write_settings_file(&path, &content)?;
@@ -747,6 +883,41 @@ mod tests {
         assert!(result.unwrap_err().contains("project_path required"));
     }
 
+    #[test]
+    fn test_effective_cleanup_period_days_precedence() {
+        let temp = setup_test_env();
+        assert_eq!(
+            effective_cleanup_period_days(None),
+            (DEFAULT_CLEANUP_PERIOD_DAYS, "default".to_string())
+        );
+
+        let claude_dir = temp.path().join(".claude");
+        fs::create_dir_all(&claude_dir).unwrap();
+        fs::write(
+            claude_dir.join("settings.json"),
+            r#"{"cleanupPeriodDays":90}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            effective_cleanup_period_days(None),
+            (90, "user".to_string())
+        );
+
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join(".claude")).unwrap();
+        fs::write(
+            project.join(".claude/settings.local.json"),
+            r#"{"cleanupPeriodDays":7}"#,
+        )
+        .unwrap();
+        let project_path = project.to_string_lossy().to_string();
+        assert_eq!(
+            effective_cleanup_period_days(Some(&project_path)),
+            (7, "local".to_string())
+        );
+        drop(temp);
+    }
+
     #[tokio::test]
     async fn test_get_settings_by_scope_user() {
         let temp = setup_test_env();
@@ -766,7 +937,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_save_settings_managed_readonly() {
-        let result = save_settings("managed".to_string(), "{}".to_string(), None).await;
+        let result = save_settings("managed".to_string(), "{}".to_string(), None, None).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("read-only"));
     }
@@ -863,7 +1034,7 @@ mod tests {
         let content = r#"{"theme":"dark","fontSize":14}"#;
 
         // Save
-        let save_result = save_settings("user".to_string(), content.to_string(), None).await;
+        let save_result = save_settings("user".to_string(), content.to_string(), None, None).await;
         assert!(save_result.is_ok());
 
         // Retrieve
@@ -877,4 +1048,75 @@ mod tests {
 
         drop(temp);
     }
+
+    #[tokio::test]
+    async fn test_save_rejected_after_outside_change() {
+        let temp = setup_test_env();
+        let path = get_user_settings_path().unwrap();
+        let save = |content: &str, expected: Option<String>| {
+            save_settings("user".to_string(), content.to_string(), None, expected)
+        };
+        save(r#"{"a":1}"#, None).await.unwrap();
+        assert!(!is_unseen_change(&path));
+
+        let base = get_all_settings(None).await.unwrap().versions["user"].clone();
+        fs::write(&path, r#"{"a":2}"#).unwrap();
+        assert!(is_unseen_change(&path));
+
+        // A background refresh doesn't make the edit's base any newer
+        let refreshed = get_all_settings(None).await.unwrap().versions["user"].clone();
+        assert_ne!(refreshed, base);
+        let result = save(r#"{"a":3}"#, Some(base)).await;
+        assert!(result.unwrap_err().contains("changed on disk"));
+        assert!(is_unseen_change(&path));
+
+        // An edit based on the new content goes ahead
+        save(r#"{"a":3}"#, Some(refreshed)).await.unwrap();
+        assert!(!is_unseen_change(&path));
+
+        // In ~/.claude.json only the MCP servers have to be unchanged
+        let claude_json = get_claude_json_path().unwrap();
+        let project = temp.path().join("project");
+        let project_path = project.to_string_lossy().to_string();
+        fs::write(&claude_json, r#"{"numStartups":1,"mcpServers":{}}"#).unwrap();
+        let versions = get_all_mcp_servers(Some(project_path.clone()))
+            .await
+            .unwrap()
+            .versions;
+        fs::write(&claude_json, r#"{"numStartups":2,"mcpServers":{}}"#).unwrap();
+        let servers = r#"{"s1":{"command":"a"}}"#.to_string();
+        save_mcp_servers(
+            "user_claude_json".to_string(),
+            servers.clone(),
+            None,
+            Some(versions["user_claude_json"].clone()),
+        )
+        .await
+        .unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&claude_json).unwrap()).unwrap();
+        assert_eq!(saved["numStartups"], 2);
+
+        // Saving one field keeps the version of another read alongside it
+        save_mcp_servers(
+            "local_claude_json".to_string(),
+            servers.clone(),
+            Some(project_path),
+            Some(versions["local_claude_json"].clone()),
+        )
+        .await
+        .unwrap();
+
+        fs::write(&claude_json, r#"{"mcpServers":{"s2":{}}}"#).unwrap();
+        let result = save_mcp_servers(
+            "user_claude_json".to_string(),
+            servers,
+            None,
+            Some(versions["user_claude_json"].clone()),
+        )
+        .await;
+        assert!(result.unwrap_err().contains("changed on disk"));
+
+        drop(temp);
+    }
 }
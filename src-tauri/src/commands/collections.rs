@@ -0,0 +1,403 @@
+//! Saved searches and smart collections
+//!
+//! A saved search is a [`SessionQuery`] stored in `UserMetadata`. Evaluating
+//! it loads sessions from every project and keeps those that pass all of its
+//! filters, cheapest first: metadata and dates before anything that has to
+//! read session files.
+
+use super::metadata::{current_metadata, update_metadata, MetadataState};
+use super::project::scan_projects;
+use super::session::{load_project_sessions, session_contains_text, session_uses_model};
+use crate::glob::GlobRules;
+use crate::models::{
+    ClaudeSession, SavedSearch, SessionQuery, SessionQueryResult, SkippedProject, UserMetadata,
+};
+use chrono::{DateTime, Duration, Utc};
+use rayon::prelude::*;
+use std::path::Path;
+use tauri::State;
+use uuid::Uuid;
+
+/// Insert or replace a saved search, filling in its id and timestamps
+fn upsert_saved_search(metadata: &mut UserMetadata, mut search: SavedSearch, now: &str) {
+    search.updated_at = now.to_string();
+    if let Some(existing) = metadata
+        .saved_searches
+        .iter_mut()
+        .find(|s| !search.id.is_empty() && s.id == search.id)
+    {
+        search.created_at.clone_from(&existing.created_at);
+        *existing = search;
+        return;
+    }
+    if search.id.is_empty() {
+        search.id = Uuid::new_v4().to_string();
+    }
+    search.created_at = now.to_string();
+    metadata.saved_searches.push(search);
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Keep the sessions matching `query`, most recent first
+///
+/// `sessions` pairs each session with its project's actual path.
+fn filter_sessions(
+    sessions: Vec<(String, ClaudeSession)>,
+    query: &SessionQuery,
+    metadata: &UserMetadata,
+    now: DateTime<Utc>,
+) -> Result<Vec<ClaudeSession>, String> {
+    let parse_bound = |value: &Option<String>, name: &str| {
+        value
+            .as_deref()
+            .map(|v| parse_time(v).ok_or_else(|| format!("Invalid {name} date: {v}")))
+            .transpose()
+    };
+    let mut from = parse_bound(&query.date_from, "start")?;
+    let to = parse_bound(&query.date_to, "end")?;
+    if let Some(days) = query.within_days {
+        let window_start = now
+            .checked_sub_signed(Duration::days(i64::from(days)))
+            .ok_or_else(|| format!("Invalid within_days: {days}"))?;
+        from = Some(from.map_or(window_start, |from| from.max(window_start)));
+    }
+    let projects = GlobRules::new(&query.projects);
+    let text = query
+        .text
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase);
+    let model = query.model.as_deref().filter(|m| !m.trim().is_empty());
+
+    let mut matched: Vec<ClaudeSession> = sessions
+        .into_par_iter()
+        .filter(|(project_path, session)| {
+            if !query.include_hidden && metadata.is_project_hidden(project_path) {
+                return false;
+            }
+            if !query.projects.is_empty() && !projects.matches_path(project_path) {
+                return false;
+            }
+
+            let session_metadata = metadata.get_session(&session.session_id);
+            if let Some(starred) = query.starred {
+                if session_metadata.and_then(|m| m.starred).unwrap_or(false) != starred {
+                    return false;
+                }
+            }
+            if let Some(has_errors) = query.has_errors {
+                if session.has_errors != has_errors {
+                    return false;
+                }
+            }
+            let has_tag = |tag: &String| {
+                session.auto_tags.contains(tag)
+                    || session_metadata.is_some_and(|m| m.tags.contains(tag))
+            };
+            if !query.tags.iter().all(has_tag) {
+                return false;
+            }
+
+            if from.is_some() || to.is_some() {
+                let Some(last) = parse_time(&session.last_message_time) else {
+                    return false;
+                };
+                if from.is_some_and(|from| last < from) || to.is_some_and(|to| last > to) {
+                    return false;
+                }
+            }
+
+            if let Some(text) = &text {
+                let named = session_metadata
+                    .and_then(|m| m.custom_name.as_deref())
+                    .into_iter()
+                    .chain(session.summary.as_deref())
+                    .any(|name| name.to_lowercase().contains(text));
+                if !named && !session_contains_text(Path::new(&session.file_path), text) {
+                    return false;
+                }
+            }
+            model.map_or(true, |model| {
+                session_uses_model(Path::new(&session.file_path), model)
+            })
+        })
+        .map(|(_, session)| session)
+        .collect();
+
+    matched.sort_by(|a, b| b.last_message_time.cmp(&a.last_message_time));
+    Ok(matched)
+}
+
+/// Add a saved search, or update the one with the same id
+///
+/// Leave `id` empty to create a new one.
+#[tauri::command]
+pub async fn save_search(
+    search: SavedSearch,
+    state: State<'_, MetadataState>,
+) -> Result<UserMetadata, String> {
+    if search.name.trim().is_empty() {
+        return Err("Saved search must have a name".to_string());
+    }
+    let now = Utc::now().to_rfc3339();
+    update_metadata(&state, |metadata| {
+        upsert_saved_search(metadata, search, &now);
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_saved_search(
+    search_id: String,
+    state: State<'_, MetadataState>,
+) -> Result<UserMetadata, String> {
+    update_metadata(&state, |metadata| {
+        metadata.saved_searches.retain(|s| s.id != search_id);
+    })
+    .await
+}
+
+/// Sessions from all projects matching a query, most recent first
+///
+/// A project whose sessions can't be loaded is skipped and listed in the
+/// result instead of failing the whole query.
+#[tauri::command]
+pub async fn query_sessions(
+    claude_path: String,
+    query: SessionQuery,
+    limit: Option<usize>,
+    state: State<'_, MetadataState>,
+) -> Result<SessionQueryResult, String> {
+    let metadata = current_metadata(&state).await?;
+
+    let mut sessions = Vec::new();
+    let mut skipped_projects = Vec::new();
+    for project in scan_projects(claude_path).await? {
+        match load_project_sessions(project.path.clone(), Some(false)).await {
+            Ok(loaded) => sessions.extend(
+                loaded
+                    .into_iter()
+                    .map(|session| (project.actual_path.clone(), session)),
+            ),
+            Err(error) => skipped_projects.push(SkippedProject {
+                project_path: project.path,
+                error,
+            }),
+        }
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut matched = filter_sessions(sessions, &query, &metadata, Utc::now())?;
+        if let Some(limit) = limit {
+            matched.truncate(limit);
+        }
+        Ok(SessionQueryResult {
+            sessions: matched,
+            skipped_projects,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Evaluate a saved search into its current list of sessions
+#[tauri::command]
+pub async fn evaluate_smart_collection(
+    claude_path: String,
+    search_id: String,
+    limit: Option<usize>,
+    state: State<'_, MetadataState>,
+) -> Result<SessionQueryResult, String> {
+    let metadata = current_metadata(&state).await?;
+    let query = metadata
+        .saved_searches
+        .iter()
+        .find(|s| s.id == search_id)
+        .map(|s| s.query.clone())
+        .ok_or_else(|| format!("Saved search not found: {search_id}"))?;
+    query_sessions(claude_path, query, limit, state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn session(
+        dir: &Path,
+        id: &str,
+        last_message_time: &str,
+        lines: &[serde_json::Value],
+    ) -> ClaudeSession {
+        let file_path = dir.join(format!("{id}.jsonl"));
+        let content: Vec<String> = lines.iter().map(ToString::to_string).collect();
+        fs::write(&file_path, content.join("\n") + "\n").unwrap();
+        ClaudeSession {
+            session_id: file_path.to_string_lossy().to_string(),
+            actual_session_id: id.to_string(),
+            file_path: file_path.to_string_lossy().to_string(),
+            project_name: "app".to_string(),
+            message_count: lines.len(),
+            first_message_time: last_message_time.to_string(),
+            last_message_time: last_message_time.to_string(),
+            last_modified: last_message_time.to_string(),
+            has_tool_use: false,
+            has_errors: false,
+            summary: None,
+            auto_tags: Vec::new(),
+        }
+    }
+
+    fn message(role: &str, text: &str, model: Option<&str>) -> serde_json::Value {
+        let mut content = json!({"role": role, "content": text});
+        if let Some(model) = model {
+            content["model"] = json!(model);
+        }
+        json!({
+            "type": role,
+            "uuid": format!("{role}-{text}"),
+            "timestamp": "2025-06-01T10:00:00Z",
+            "sessionId": "s",
+            "message": content,
+        })
+    }
+
+    #[test]
+    fn test_upsert_saved_search_keeps_created_at() {
+        let mut metadata = UserMetadata::new();
+        let search = SavedSearch {
+            name: "Failing deploys".to_string(),
+            ..Default::default()
+        };
+        upsert_saved_search(&mut metadata, search, "2025-01-01T00:00:00Z");
+        let mut saved = metadata.saved_searches[0].clone();
+        assert!(!saved.id.is_empty());
+
+        saved.query.has_errors = Some(true);
+        upsert_saved_search(&mut metadata, saved, "2025-01-02T00:00:00Z");
+        assert_eq!(metadata.saved_searches.len(), 1);
+        let updated = &metadata.saved_searches[0];
+        assert_eq!(updated.created_at, "2025-01-01T00:00:00Z");
+        assert_eq!(updated.updated_at, "2025-01-02T00:00:00Z");
+        assert_eq!(updated.query.has_errors, Some(true));
+    }
+
+    #[test]
+    fn test_filter_sessions_applies_every_filter() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let now = parse_time("2025-06-10T00:00:00Z").unwrap();
+
+        let mut deploy = session(
+            dir,
+            "deploy",
+            "2025-06-09T00:00:00Z",
+            &[
+                message("user", "Why does the Deploy fail?", None),
+                message("assistant", "Checking", Some("claude-opus-4")),
+            ],
+        );
+        deploy.has_errors = true;
+        deploy.auto_tags = vec!["infra".to_string()];
+        let old = session(
+            dir,
+            "old",
+            "2025-05-01T00:00:00Z",
+            &[message("user", "deploy again", None)],
+        );
+        let other = session(
+            dir,
+            "other",
+            "2025-06-08T00:00:00Z",
+            &[message("assistant", "hello", Some("claude-sonnet-4"))],
+        );
+
+        let mut metadata = UserMetadata::new();
+        metadata.get_session_mut(&deploy.session_id).starred = Some(true);
+        metadata.get_session_mut(&other.session_id).tags = vec!["infra".to_string()];
+        metadata.settings.hidden_patterns = vec!["secret-*".to_string()];
+
+        let candidates = vec![
+            ("/Users/me/app".to_string(), deploy),
+            ("/Users/me/app".to_string(), old),
+            ("/Users/me/web".to_string(), other),
+            (
+                "/Users/me/secret-app".to_string(),
+                session(dir, "hidden", "2025-06-09T00:00:00Z", &[]),
+            ),
+        ];
+        let ids = |query: SessionQuery| -> Vec<String> {
+            filter_sessions(candidates.clone(), &query, &metadata, now)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.actual_session_id)
+                .collect()
+        };
+
+        assert_eq!(ids(SessionQuery::default()), ["deploy", "other", "old"]);
+        let include_hidden = SessionQuery {
+            include_hidden: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(include_hidden).len(), 4);
+
+        let text = SessionQuery {
+            text: Some("DEPLOY".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(text), ["deploy", "old"]);
+
+        let recent = SessionQuery {
+            text: Some("deploy".to_string()),
+            within_days: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(ids(recent), ["deploy"]);
+
+        let tagged = SessionQuery {
+            tags: vec!["infra".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(ids(tagged), ["deploy", "other"]);
+
+        let starred = SessionQuery {
+            starred: Some(true),
+            has_errors: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(ids(starred), ["deploy"]);
+
+        let model = SessionQuery {
+            model: Some("sonnet".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(model), ["other"]);
+
+        let project = SessionQuery {
+            projects: vec!["/Users/me/*".to_string(), "!web".to_string()],
+            date_to: Some("2025-06-08T12:00:00Z".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(project), ["old"]);
+
+        let invalid = SessionQuery {
+            date_from: Some("yesterday".to_string()),
+            ..Default::default()
+        };
+        assert!(filter_sessions(Vec::new(), &invalid, &metadata, now).is_err());
+
+        let too_far = SessionQuery {
+            within_days: Some(u32::MAX),
+            ..Default::default()
+        };
+        assert!(filter_sessions(Vec::new(), &too_far, &metadata, now).is_err());
+    }
+}
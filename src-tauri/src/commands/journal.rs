@@ -0,0 +1,501 @@
+//! Journaled writes to Claude Code session files
+//!
+//! Every modification of a file under `~/.claude/projects` goes through
+//! [`journaled_write`]: the original content is snapshotted to
+//! `~/.claude-history-viewer/journal/`, concurrent appends by Claude Code are
+//! detected by comparing size and mtime between read and rename (the write is
+//! retried on fresh content), and the modification is recorded in a history
+//! that can be reverted.
+
+use super::fs_utils::atomic_rename;
+use super::metadata::get_metadata_folder;
+use super::session::validate_claude_path;
+use crate::models::JournalEntry;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// Attempts before giving up when the file keeps changing underneath us
+const MAX_WRITE_ATTEMPTS: usize = 3;
+
+/// Oldest entries (and their snapshots) are pruned beyond this count
+const MAX_HISTORY_ENTRIES: usize = 200;
+
+/// Serializes journaled writes and history updates within the app
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+/// Size and modification time, used to detect concurrent appends
+#[derive(Debug, PartialEq)]
+struct FileState {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+fn file_state(path: &Path) -> Result<FileState, String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("Failed to stat file: {e}"))?;
+    Ok(FileState {
+        len: metadata.len(),
+        modified: metadata.modified().ok(),
+    })
+}
+
+/// Get the journal folder path (~/.claude-history-viewer/journal)
+fn get_journal_folder() -> Result<PathBuf, String> {
+    Ok(get_metadata_folder()?.join("journal"))
+}
+
+fn ensure_journal_folder() -> Result<PathBuf, String> {
+    let folder = get_journal_folder()?;
+    fs::create_dir_all(&folder).map_err(|e| format!("Failed to create journal folder: {e}"))?;
+    Ok(folder)
+}
+
+fn load_history(journal_dir: &Path) -> Result<Vec<JournalEntry>, String> {
+    let path = journal_dir.join("history.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read journal history: {e}"))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse journal history: {e}"))
+}
+
+fn save_history(journal_dir: &Path, history: &[JournalEntry]) -> Result<(), String> {
+    let path = journal_dir.join("history.json");
+    let temp_path = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(history)
+        .map_err(|e| format!("Failed to serialize journal history: {e}"))?;
+    fs::write(&temp_path, content).map_err(|e| format!("Failed to write temp file: {e}"))?;
+    atomic_rename(&temp_path, &path)
+}
+
+/// Put `original` back after a write whose journal entry couldn't be saved
+///
+/// Lines appended since the write are kept. Best effort: the write stays if
+/// the file no longer starts with what was written.
+fn undo_write(path: &Path, temp_path: &Path, original: &str, written: &str) {
+    let Ok(current) = fs::read_to_string(path) else {
+        return;
+    };
+    let Some(appended) = current.strip_prefix(written) else {
+        log::error!("Could not undo unjournaled write of {}", path.display());
+        return;
+    };
+    let restored = fs::write(temp_path, format!("{original}{appended}"))
+        .map_err(|e| format!("Failed to write temp file: {e}"))
+        .and_then(|()| atomic_rename(temp_path, path));
+    if let Err(e) = restored {
+        let _ = fs::remove_file(temp_path);
+        log::error!(
+            "Could not undo unjournaled write of {}: {e}",
+            path.display()
+        );
+    }
+}
+
+/// Modify a session file through the journal
+///
+/// `transform` receives the current content and returns the new content. It may
+/// run more than once: if the file changes between reading and renaming (e.g.
+/// Claude Code appended a line), the write is discarded and retried on the
+/// fresh content, up to `MAX_WRITE_ATTEMPTS` times.
+pub(crate) fn journaled_write<F>(
+    path: &Path,
+    operation: &str,
+    description: &str,
+    transform: F,
+) -> Result<JournalEntry, String>
+where
+    F: FnMut(&str) -> Result<String, String>,
+{
+    let _guard = JOURNAL_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock journal: {e}"))?;
+    write_locked(path, operation, description, None, transform)
+}
+
+/// Journaled write; the caller must hold `JOURNAL_LOCK`
+fn write_locked<F>(
+    path: &Path,
+    operation: &str,
+    description: &str,
+    reverts: Option<&str>,
+    mut transform: F,
+) -> Result<JournalEntry, String>
+where
+    F: FnMut(&str) -> Result<String, String>,
+{
+    let journal_dir = ensure_journal_folder()?;
+    let temp_path = PathBuf::from(format!("{}.journal.tmp", path.display()));
+    // Read before touching the file: without a history entry the snapshot
+    // could never be reverted
+    let mut history = load_history(&journal_dir)?;
+
+    for _ in 0..MAX_WRITE_ATTEMPTS {
+        let before = file_state(path)?;
+        let original = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {e}"))?;
+        if file_state(path)? != before {
+            continue;
+        }
+
+        let updated = transform(&original)?;
+        fs::write(&temp_path, &updated).map_err(|e| format!("Failed to write temp file: {e}"))?;
+
+        let id = Uuid::new_v4().to_string();
+        let snapshot_path = journal_dir.join(format!("{id}.snapshot"));
+        if let Err(e) = fs::write(&snapshot_path, &original) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(format!("Failed to write journal snapshot: {e}"));
+        }
+
+        // Claude Code may have appended while we were working; start over
+        if file_state(path)? != before {
+            let _ = fs::remove_file(&temp_path);
+            let _ = fs::remove_file(&snapshot_path);
+            continue;
+        }
+
+        if let Err(e) = atomic_rename(&temp_path, path) {
+            let _ = fs::remove_file(&temp_path);
+            let _ = fs::remove_file(&snapshot_path);
+            return Err(e);
+        }
+
+        let entry = JournalEntry {
+            id,
+            file_path: path.to_string_lossy().to_string(),
+            operation: operation.to_string(),
+            description: description.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            snapshot_path: snapshot_path.to_string_lossy().to_string(),
+            original_size: original.len() as u64,
+            new_size: updated.len() as u64,
+            new_checksum: Some(crc32fast::hash(updated.as_bytes())),
+            reverted_by: None,
+            reverts: reverts.map(std::string::ToString::to_string),
+        };
+
+        if let Some(target) = reverts.and_then(|id| history.iter_mut().find(|e| e.id == id)) {
+            target.reverted_by = Some(entry.id.clone());
+        }
+        history.push(entry.clone());
+        let excess = history.len().saturating_sub(MAX_HISTORY_ENTRIES);
+        let pruned: Vec<JournalEntry> = history.drain(..excess).collect();
+        if let Err(e) = save_history(&journal_dir, &history) {
+            undo_write(path, &temp_path, &original, &updated);
+            let _ = fs::remove_file(&snapshot_path);
+            return Err(e);
+        }
+        for old in pruned {
+            let _ = fs::remove_file(&old.snapshot_path);
+        }
+
+        return Ok(entry);
+    }
+
+    Err(format!(
+        "File kept changing while writing {} (gave up after {MAX_WRITE_ATTEMPTS} attempts)",
+        path.display()
+    ))
+}
+
+/// Revert a journaled modification by restoring its snapshot
+///
+/// Lines Claude Code appended after the modification are kept. Fails if a later
+/// modification of the same file is still in effect.
+fn revert_entry(entry_id: &str) -> Result<JournalEntry, String> {
+    let _guard = JOURNAL_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock journal: {e}"))?;
+    let journal_dir = ensure_journal_folder()?;
+    let history = load_history(&journal_dir)?;
+
+    let position = history
+        .iter()
+        .position(|e| e.id == entry_id)
+        .ok_or_else(|| format!("Journal entry not found: {entry_id}"))?;
+    let entry = &history[position];
+    if entry.reverted_by.is_some() {
+        return Err("Modification was already reverted".to_string());
+    }
+    let newer_in_effect = history[position + 1..].iter().any(|later| {
+        later.file_path == entry.file_path && later.reverted_by.is_none() && later.reverts.is_none()
+    });
+    if newer_in_effect {
+        return Err("Revert newer modifications of this file first".to_string());
+    }
+
+    validate_claude_path(&entry.file_path)?;
+    if Path::new(&entry.snapshot_path).parent() != Some(journal_dir.as_path()) {
+        return Err("Invalid journal snapshot path".to_string());
+    }
+    let snapshot = fs::read_to_string(&entry.snapshot_path)
+        .map_err(|e| format!("Failed to read journal snapshot: {e}"))?;
+    let written_len = usize::try_from(entry.new_size).map_err(|e| e.to_string())?;
+
+    write_locked(
+        Path::new(&entry.file_path),
+        "revert",
+        &format!("Revert: {}", entry.description),
+        Some(&entry.id),
+        |current| {
+            // Claude Code only appends, so anything past the written size is new
+            let appended = current.get(written_len..).ok_or_else(|| {
+                "File was truncated after the modification; cannot revert safely".to_string()
+            })?;
+            // Restoring over content someone else rewrote would lose it
+            if let Some(checksum) = entry.new_checksum {
+                if crc32fast::hash(&current.as_bytes()[..written_len]) != checksum {
+                    return Err(
+                        "File was changed after the modification; cannot revert safely".to_string(),
+                    );
+                }
+            }
+            Ok(format!("{snapshot}{appended}"))
+        },
+    )
+}
+
+/// Forget every journaled modification of a file and delete its snapshots
+///
+/// Used after redaction, where keeping the original content around would
+/// defeat the purpose. Returns the number of entries removed.
+pub(crate) fn discard_file_history(path: &Path) -> Result<usize, String> {
+    let _guard = JOURNAL_LOCK
+        .lock()
+        .map_err(|e| format!("Failed to lock journal: {e}"))?;
+    let journal_dir = ensure_journal_folder()?;
+    let file_path = path.to_string_lossy();
+    let (discarded, kept): (Vec<JournalEntry>, Vec<JournalEntry>) = load_history(&journal_dir)?
+        .into_iter()
+        .partition(|e| e.file_path == file_path);
+
+    for entry in &discarded {
+        if Path::new(&entry.snapshot_path).parent() == Some(journal_dir.as_path()) {
+            fs::remove_file(&entry.snapshot_path)
+                .or_else(|e| {
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        Ok(())
+                    } else {
+                        Err(e)
+                    }
+                })
+                .map_err(|e| format!("Failed to delete journal snapshot: {e}"))?;
+        }
+    }
+    save_history(&journal_dir, &kept)?;
+    Ok(discarded.len())
+}
+
+/// Get journaled modifications, newest first, optionally for a single file
+#[tauri::command]
+pub async fn get_journal_history(file_path: Option<String>) -> Result<Vec<JournalEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut history = load_history(&get_journal_folder()?)?;
+        if let Some(file_path) = file_path {
+            history.retain(|e| e.file_path == file_path);
+        }
+        history.reverse();
+        Ok(history)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Revert a journaled modification
+#[tauri::command]
+pub async fn revert_journal_entry(entry_id: String) -> Result<JournalEntry, String> {
+    tauri::async_runtime::spawn_blocking(move || revert_entry(&entry_id))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use tempfile::TempDir;
+
+    /// Sets up a test environment with a temporary HOME directory.
+    /// NOTE: Tests using this MUST run with --test-threads=1 because
+    /// `env::set_var("HOME")` is process-global and not thread-safe.
+    fn setup_test_env() -> (TempDir, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let home = temp_dir.path().canonicalize().unwrap();
+        env::set_var("HOME", &home);
+        let project_dir = home.join(".claude/projects/-test-project");
+        fs::create_dir_all(&project_dir).unwrap();
+        let session = project_dir.join("session-1.jsonl");
+        fs::write(&session, "{\"a\":1}\n{\"b\":2}\n").unwrap();
+        (temp_dir, session)
+    }
+
+    #[test]
+    fn test_journaled_write_snapshots_original() {
+        let (_temp, session) = setup_test_env();
+
+        let entry = journaled_write(&session, "rename", "Rename", |content| {
+            Ok(content.replace("\"a\":1", "\"a\":2"))
+        })
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&session).unwrap(),
+            "{\"a\":2}\n{\"b\":2}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&entry.snapshot_path).unwrap(),
+            "{\"a\":1}\n{\"b\":2}\n"
+        );
+        assert!(!PathBuf::from(format!("{}.journal.tmp", session.display())).exists());
+
+        let history = load_history(&get_journal_folder().unwrap()).unwrap();
+        assert_eq!(history, vec![entry]);
+    }
+
+    #[test]
+    fn test_journaled_write_retries_on_concurrent_append() {
+        let (_temp, session) = setup_test_env();
+        let mut attempts = 0;
+
+        journaled_write(&session, "rename", "Rename", |content| {
+            attempts += 1;
+            if attempts == 1 {
+                // Simulate Claude Code appending while we transform
+                let mut appended = fs::read_to_string(&session).unwrap();
+                appended.push_str("{\"c\":3}\n");
+                fs::write(&session, appended).unwrap();
+            }
+            Ok(content.replace("\"a\":1", "\"a\":2"))
+        })
+        .unwrap();
+
+        assert_eq!(attempts, 2);
+        // The appended line survives
+        assert_eq!(
+            fs::read_to_string(&session).unwrap(),
+            "{\"a\":2}\n{\"b\":2}\n{\"c\":3}\n"
+        );
+    }
+
+    #[test]
+    fn test_journaled_write_aborts_when_file_keeps_changing() {
+        let (_temp, session) = setup_test_env();
+
+        let result = journaled_write(&session, "rename", "Rename", |content| {
+            let mut appended = fs::read_to_string(&session).unwrap();
+            appended.push_str("{\"c\":3}\n");
+            fs::write(&session, appended).unwrap();
+            Ok(content.to_string())
+        });
+
+        assert!(result.unwrap_err().contains("gave up"));
+        assert!(load_history(&get_journal_folder().unwrap())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_revert_keeps_appended_lines() {
+        let (_temp, session) = setup_test_env();
+        let entry = journaled_write(&session, "rename", "Rename", |content| {
+            Ok(content.replace("\"a\":1", "\"a\":22"))
+        })
+        .unwrap();
+
+        // Claude Code appends after our modification
+        let mut content = fs::read_to_string(&session).unwrap();
+        content.push_str("{\"c\":3}\n");
+        fs::write(&session, content).unwrap();
+
+        let revert = revert_entry(&entry.id).unwrap();
+        assert_eq!(revert.reverts.as_deref(), Some(entry.id.as_str()));
+        assert_eq!(
+            fs::read_to_string(&session).unwrap(),
+            "{\"a\":1}\n{\"b\":2}\n{\"c\":3}\n"
+        );
+
+        let history = load_history(&get_journal_folder().unwrap()).unwrap();
+        assert_eq!(history[0].reverted_by.as_deref(), Some(revert.id.as_str()));
+        assert!(revert_entry(&entry.id)
+            .unwrap_err()
+            .contains("already reverted"));
+    }
+
+    #[test]
+    fn test_unreadable_history_leaves_file_untouched() {
+        let (_temp, session) = setup_test_env();
+        let journal_dir = ensure_journal_folder().unwrap();
+        fs::write(journal_dir.join("history.json"), "not json").unwrap();
+
+        let err = journaled_write(&session, "rename", "Rename", |content| {
+            Ok(content.replace("\"a\":1", "\"a\":2"))
+        })
+        .unwrap_err();
+        assert!(err.contains("journal history"));
+        assert_eq!(
+            fs::read_to_string(&session).unwrap(),
+            "{\"a\":1}\n{\"b\":2}\n"
+        );
+        let snapshots = fs::read_dir(&journal_dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "snapshot"))
+            .count();
+        assert_eq!(snapshots, 0);
+    }
+
+    #[test]
+    fn test_revert_refuses_after_outside_rewrite() {
+        let (_temp, session) = setup_test_env();
+        let entry = journaled_write(&session, "rename", "Rename", |content| {
+            Ok(content.replace("\"a\":1", "\"a\":2"))
+        })
+        .unwrap();
+
+        // Same length, different content
+        fs::write(&session, "{\"a\":3}\n{\"b\":2}\n").unwrap();
+
+        assert!(revert_entry(&entry.id).unwrap_err().contains("changed"));
+        assert_eq!(
+            fs::read_to_string(&session).unwrap(),
+            "{\"a\":3}\n{\"b\":2}\n"
+        );
+    }
+
+    #[test]
+    fn test_revert_requires_newer_modifications_reverted_first() {
+        let (_temp, session) = setup_test_env();
+        let first =
+            journaled_write(&session, "rename", "First", |c| Ok(c.replace('1', "5"))).unwrap();
+        let second =
+            journaled_write(&session, "rename", "Second", |c| Ok(c.replace('2', "6"))).unwrap();
+
+        assert!(revert_entry(&first.id).unwrap_err().contains("newer"));
+
+        revert_entry(&second.id).unwrap();
+        revert_entry(&first.id).unwrap();
+        assert_eq!(
+            fs::read_to_string(&session).unwrap(),
+            "{\"a\":1}\n{\"b\":2}\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_journal_history_filters_and_orders() {
+        let (_temp, session) = setup_test_env();
+        let first = journaled_write(&session, "rename", "First", |c| Ok(c.to_string())).unwrap();
+        let second = journaled_write(&session, "rename", "Second", |c| Ok(c.to_string())).unwrap();
+
+        let history = get_journal_history(Some(session.to_string_lossy().to_string()))
+            .await
+            .unwrap();
+        assert_eq!(history, vec![second, first]);
+        assert!(get_journal_history(Some("/other.jsonl".to_string()))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,431 @@
+//! Follow a running session as Claude Code writes it
+//!
+//! [`start_session_tail`] watches one session file and emits `session-tail`
+//! with the messages appended since the last read. Only the new bytes are
+//! parsed, and a half-written last line waits for the write that completes
+//! it. [`get_active_sessions`] lists the sessions written to in the last few
+//! minutes, which are the ones an agent is most likely still working in.
+
+use super::session::{complete_len, read_appended_messages};
+use super::trusted_symlinks::{check_claude_folder, check_session_path};
+use super::watcher::FileState;
+use crate::models::{ActiveSession, ClaudeMessage};
+use chrono::{DateTime, Utc};
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebouncedEvent, Debouncer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, State};
+use walkdir::WalkDir;
+
+/// Shorter than the project watcher's, so messages show up as they are written
+const TAIL_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Bytes parsed per event when catching up on a large append
+const TAIL_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Sessions written to within this many minutes count as active by default
+const DEFAULT_ACTIVE_MINUTES: u64 = 5;
+
+/// Session tails by the path they were started with
+#[derive(Default)]
+pub struct SessionTailState {
+    tails: Mutex<HashMap<String, Debouncer<RecommendedWatcher>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTailEvent {
+    pub session_path: String,
+    /// The file was truncated or replaced: reload it; the tail goes on from `end_offset`
+    pub reset: bool,
+    pub messages: Vec<ClaudeMessage>,
+    /// Bytes `start_offset..end_offset` of the file were read
+    pub start_offset: u64,
+    pub end_offset: u64,
+}
+
+/// Read position in one session file
+struct Tail {
+    path: PathBuf,
+    session_path: String,
+    /// Start of the first line not read yet
+    offset: u64,
+    /// The file as of the last poll, to tell appends from rewrites
+    seen: Option<FileState>,
+}
+
+/// Whether `offset` is the start of a line
+fn is_line_start(file: &mut File, offset: u64) -> bool {
+    if offset == 0 {
+        return true;
+    }
+    let mut byte = [0u8];
+    file.seek(SeekFrom::Start(offset - 1)).is_ok()
+        && file.read_exact(&mut byte).is_ok()
+        && byte[0] == b'\n'
+}
+
+impl Tail {
+    fn new(path: PathBuf, session_path: String, offset: u64) -> Self {
+        Self {
+            seen: FileState::read(&path),
+            path,
+            session_path,
+            offset,
+        }
+    }
+
+    fn event(&self, start_offset: u64, end_offset: u64, reset: bool) -> SessionTailEvent {
+        SessionTailEvent {
+            session_path: self.session_path.clone(),
+            reset,
+            messages: Vec::new(),
+            start_offset,
+            end_offset,
+        }
+    }
+
+    /// Messages appended since the last poll, at most one chunk per event
+    fn poll(&mut self) -> Result<Vec<SessionTailEvent>, String> {
+        let mut file =
+            File::open(&self.path).map_err(|e| format!("Failed to open session file: {e}"))?;
+        let size = file
+            .metadata()
+            .map_err(|e| format!("Failed to read file metadata: {e}"))?
+            .len();
+
+        // Same checks as the project watcher: replaced, or changed anywhere
+        // but past the end, is a rewrite
+        let current = FileState::read(&self.path);
+        let extended = match (&self.seen, &current) {
+            (Some(seen), Some(current)) => {
+                seen.is_unchanged(current) || seen.is_extended_by(&self.path, current)
+            }
+            _ => true,
+        };
+        self.seen = current;
+        if !extended || size < self.offset || !is_line_start(&mut file, self.offset) {
+            let end = match &self.seen {
+                Some(seen) => seen.offset(),
+                None => complete_len(&mut file, size)
+                    .map_err(|e| format!("Failed to read session file: {e}"))?,
+            };
+            self.offset = end;
+            return Ok(vec![self.event(0, end, true)]);
+        }
+
+        let mut events = Vec::new();
+        while self.offset < size {
+            let mut appended =
+                read_appended_messages(&self.path, self.offset, Some(TAIL_CHUNK_BYTES), true)?;
+            if appended.end_offset == self.offset {
+                if size - self.offset <= TAIL_CHUNK_BYTES {
+                    // Only a partial line so far
+                    break;
+                }
+                // A single line longer than a chunk
+                appended = read_appended_messages(&self.path, self.offset, None, true)?;
+                if appended.end_offset == self.offset {
+                    break;
+                }
+            }
+            if !appended.messages.is_empty() {
+                let mut event = self.event(self.offset, appended.end_offset, false);
+                event.messages = appended.messages;
+                events.push(event);
+            }
+            self.offset = appended.end_offset;
+        }
+        Ok(events)
+    }
+}
+
+/// Where to start tailing: `from_offset`, which must be a line start, or else
+/// the end of the last complete line
+fn start_offset(path: &Path, from_offset: Option<u64>) -> Result<u64, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read file metadata: {e}"))?
+        .len();
+    match from_offset {
+        Some(offset) if offset <= size && is_line_start(&mut file, offset) => Ok(offset),
+        Some(offset) => Err(format!(
+            "Offset {offset} is not a line start in the session file"
+        )),
+        None => {
+            complete_len(&mut file, size).map_err(|e| format!("Failed to read session file: {e}"))
+        }
+    }
+}
+
+fn emit_tail_events(app_handle: &AppHandle, tail: &mut Tail) {
+    match tail.poll() {
+        Ok(events) => {
+            for event in events {
+                if let Err(e) = app_handle.emit("session-tail", &event) {
+                    log::error!("Failed to emit session tail event: {e}");
+                }
+            }
+        }
+        // Usually the file is being replaced; the next event finds the new one
+        Err(e) => log::warn!("Failed to read appended messages: {e}"),
+    }
+}
+
+/// Stream messages appended to one session file as `session-tail` events
+///
+/// Tailing starts at `from_offset` (the byte offset the caller has already
+/// loaded up to) or, without it, at the current end of the file. Anything
+/// written past that offset is emitted right away. Returns the start offset.
+#[tauri::command]
+pub async fn start_session_tail(
+    app_handle: AppHandle,
+    session_path: String,
+    from_offset: Option<u64>,
+    state: State<'_, SessionTailState>,
+) -> Result<u64, String> {
+    let path = PathBuf::from(&session_path);
+    check_session_path(&path)?;
+    if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") || !path.is_file() {
+        return Err(format!("Session file not found: {session_path}"));
+    }
+    let path =
+        fs::canonicalize(&path).map_err(|e| format!("Failed to resolve session path: {e}"))?;
+    let folder = path
+        .parent()
+        .ok_or_else(|| "Invalid session file path".to_string())?
+        .to_path_buf();
+
+    let offset = {
+        let path = path.clone();
+        tauri::async_runtime::spawn_blocking(move || start_offset(&path, from_offset))
+            .await
+            .map_err(|e| format!("Task join error: {e}"))??
+    };
+    let tail = Arc::new(Mutex::new(Tail::new(path, session_path.clone(), offset)));
+
+    let handle = app_handle.clone();
+    let watched = Arc::clone(&tail);
+    let mut debouncer = new_debouncer(
+        TAIL_DEBOUNCE,
+        move |result: Result<Vec<DebouncedEvent>, notify::Error>| match result {
+            Ok(events) => {
+                let Ok(mut tail) = watched.lock() else {
+                    return;
+                };
+                if events.iter().any(|event| event.path == tail.path) {
+                    emit_tail_events(&handle, &mut tail);
+                }
+            }
+            Err(error) => {
+                log::error!("Session tail watcher error: {error:?}");
+            }
+        },
+    )
+    .map_err(|e| format!("Failed to create session tail watcher: {e}"))?;
+
+    // Watch the folder so a replaced file is still followed
+    debouncer
+        .watcher()
+        .watch(&folder, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch session folder: {e}"))?;
+
+    // Catch up on whatever was written before the watcher was armed
+    {
+        let mut tail = tail
+            .lock()
+            .map_err(|e| format!("Failed to lock session tail: {e}"))?;
+        emit_tail_events(&app_handle, &mut tail);
+    }
+
+    state
+        .tails
+        .lock()
+        .map_err(|e| format!("Failed to lock session tails: {e}"))?
+        .insert(session_path, debouncer);
+    Ok(offset)
+}
+
+/// Stop a tail started with [`start_session_tail`]
+#[tauri::command]
+pub async fn stop_session_tail(
+    session_path: String,
+    state: State<'_, SessionTailState>,
+) -> Result<(), String> {
+    let removed = state
+        .tails
+        .lock()
+        .map_err(|e| format!("Failed to lock session tails: {e}"))?
+        .remove(&session_path);
+    match removed {
+        Some(_) => Ok(()),
+        None => Err(format!("No session tail for: {session_path}")),
+    }
+}
+
+/// Session files modified within `within` of `now`, most recent first
+fn active_sessions(projects_root: &Path, within: Duration, now: SystemTime) -> Vec<ActiveSession> {
+    let mut sessions: Vec<ActiveSession> = WalkDir::new(projects_root)
+        .min_depth(2)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter(|e| {
+            e.file_type().is_file()
+                && e.path().extension().and_then(|s| s.to_str()) == Some("jsonl")
+        })
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            let idle = now.duration_since(modified).unwrap_or_default();
+            if idle > within {
+                return None;
+            }
+            let project = entry
+                .path()
+                .strip_prefix(projects_root)
+                .ok()?
+                .components()
+                .next()?;
+            Some(ActiveSession {
+                file_path: entry.path().to_string_lossy().to_string(),
+                project_path: projects_root.join(project).to_string_lossy().to_string(),
+                last_modified: DateTime::<Utc>::from(modified).to_rfc3339(),
+                idle_seconds: idle.as_secs(),
+                size: metadata.len(),
+            })
+        })
+        .collect();
+    sessions.sort_by(|a, b| {
+        a.idle_seconds
+            .cmp(&b.idle_seconds)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    sessions
+}
+
+/// Sessions appended to within the last `within_minutes` (default 5)
+///
+/// Claude Code writes a line per message while it runs, so a recently
+/// modified file is a good sign the session is still going.
+#[tauri::command]
+pub async fn get_active_sessions(
+    claude_path: String,
+    within_minutes: Option<u64>,
+) -> Result<Vec<ActiveSession>, String> {
+    let within = Duration::from_secs(within_minutes.unwrap_or(DEFAULT_ACTIVE_MINUTES) * 60);
+    tauri::async_runtime::spawn_blocking(move || {
+        let projects_root = PathBuf::from(&claude_path).join("projects");
+        if !projects_root.is_dir() {
+            return Err(format!(
+                "Projects directory not found: {}",
+                projects_root.display()
+            ));
+        }
+        check_claude_folder(Path::new(&claude_path))?;
+        Ok(active_sessions(&projects_root, within, SystemTime::now()))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn line(uuid: &str) -> String {
+        json!({
+            "type": "user",
+            "uuid": uuid,
+            "sessionId": "s",
+            "timestamp": "2025-06-01T10:00:00Z",
+            "message": {"role": "user", "content": uuid},
+        })
+        .to_string()
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn uuids(events: &[SessionTailEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .flat_map(|event| event.messages.iter().map(|m| m.uuid.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_tail_streams_complete_lines_and_resets_on_rewrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("s.jsonl");
+        append(&path, &format!("{}\n", line("a")));
+        let loaded = fs::metadata(&path).unwrap().len();
+        assert!(start_offset(&path, Some(3)).is_err());
+
+        let mut tail = Tail::new(
+            path.clone(),
+            path.to_string_lossy().to_string(),
+            start_offset(&path, Some(loaded)).unwrap(),
+        );
+        assert!(tail.poll().unwrap().is_empty());
+
+        let partial = line("c");
+        let (head, rest) = partial.split_at(12);
+        append(&path, &format!("{}\n{head}", line("b")));
+        let events = tail.poll().unwrap();
+        assert_eq!(uuids(&events), ["b"]);
+        assert_eq!(events[0].start_offset, loaded);
+        assert_eq!(tail.offset, events[0].end_offset);
+        assert!(tail.poll().unwrap().is_empty());
+
+        append(&path, &format!("{rest}\n"));
+        assert_eq!(uuids(&tail.poll().unwrap()), ["c"]);
+        assert_eq!(tail.offset, fs::metadata(&path).unwrap().len());
+
+        fs::write(&path, format!("{}\n", line("d"))).unwrap();
+        let events = tail.poll().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].reset);
+        assert_eq!(tail.offset, fs::metadata(&path).unwrap().len());
+
+        // Edited before the end and grown, still split where the tail stopped
+        fs::write(&path, format!("{}\n{}\n", line("e"), line("f"))).unwrap();
+        let events = tail.poll().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].reset);
+        assert!(events[0].messages.is_empty());
+        assert_eq!(tail.offset, fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn test_active_sessions_by_modification_time() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("-Users-me-app");
+        fs::create_dir_all(&project).unwrap();
+        append(&project.join("s.jsonl"), "{}\n");
+        append(&temp_dir.path().join("stray.jsonl"), "{}\n");
+
+        let now = SystemTime::now();
+        let active = active_sessions(temp_dir.path(), Duration::from_secs(300), now);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].project_path, project.to_string_lossy());
+
+        let later = now + Duration::from_secs(600);
+        assert!(active_sessions(temp_dir.path(), Duration::from_secs(300), later).is_empty());
+    }
+}
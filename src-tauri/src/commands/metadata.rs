@@ -2,13 +2,23 @@
 //!
 //! This module provides commands for loading, saving, and updating
 //! user metadata stored in ~/.claude-history-viewer/user-data.json
-
-use crate::models::{ProjectMetadata, SessionMetadata, UserMetadata, UserSettings};
+//!
+//! Several app windows (or a sync tool) may write the file. Writes take an
+//! advisory lock, bump a revision counter and merge field by field with
+//! whatever was saved since the writer last loaded.
+
+use super::metadata_migration::{load_metadata_file, parse_metadata};
+use super::watcher::watch_user_metadata;
+use crate::models::{AutoTagRule, ProjectMetadata, SessionMetadata, UserMetadata, UserSettings};
+use chrono::Utc;
+use fs4::fs_std::FileExt;
+use notify::RecommendedWatcher;
+use notify_debouncer_mini::Debouncer;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use tauri::State;
+use tauri::{AppHandle, State};
 
 /// Validate that a project path is absolute
 fn validate_absolute_path(project_path: &str) -> Result<(), String> {
@@ -25,29 +35,40 @@ fn validate_absolute_path(project_path: &str) -> Result<(), String> {
 pub struct MetadataState {
     /// Cached metadata with mutex for thread-safe access
     pub metadata: Mutex<Option<UserMetadata>>,
+    /// Watches user-data.json for changes made outside this instance
+    pub watcher: Mutex<Option<Debouncer<RecommendedWatcher>>>,
 }
 
 impl Default for MetadataState {
     fn default() -> Self {
         Self {
             metadata: Mutex::new(None),
+            watcher: Mutex::new(None),
         }
     }
 }
 
+/// File name of the user data file inside the metadata folder
+pub(crate) const USER_DATA_FILE: &str = "user-data.json";
+
 /// Get the metadata folder path (~/.claude-history-viewer)
-fn get_metadata_folder() -> Result<PathBuf, String> {
+pub(crate) fn get_metadata_folder() -> Result<PathBuf, String> {
     let home = dirs::home_dir().ok_or("Could not find home directory")?;
     Ok(home.join(".claude-history-viewer"))
 }
 
 /// Get the user data file path (~/.claude-history-viewer/user-data.json)
 fn get_user_data_path() -> Result<PathBuf, String> {
-    Ok(get_metadata_folder()?.join("user-data.json"))
+    Ok(get_metadata_folder()?.join(USER_DATA_FILE))
+}
+
+/// Get the lock file path shared by every instance writing user-data.json
+fn get_lock_path() -> Result<PathBuf, String> {
+    Ok(get_metadata_folder()?.join("user-data.lock"))
 }
 
 /// Ensure the metadata folder exists
-fn ensure_metadata_folder() -> Result<PathBuf, String> {
+pub(crate) fn ensure_metadata_folder() -> Result<PathBuf, String> {
     let folder = get_metadata_folder()?;
     if !folder.exists() {
         fs::create_dir_all(&folder)
@@ -67,37 +88,207 @@ pub async fn get_metadata_folder_path() -> Result<String, String> {
     .map_err(|e| format!("Task join error: {e}"))?
 }
 
+/// Internal helper to read metadata from disk (blocking)
+/// Returns default metadata if the file doesn't exist or can't be read,
+/// and writes back files migrated from an older schema
+pub(crate) fn read_metadata_from_disk() -> Result<UserMetadata, String> {
+    let _lock = lock_metadata_file()?;
+    let loaded = load_metadata_file(&get_user_data_path()?)?;
+    if let Some(quarantined) = &loaded.quarantined {
+        log::warn!(
+            "Unreadable metadata was moved to {} and reset",
+            quarantined.display()
+        );
+    }
+    if loaded.migrated_from.is_some() {
+        save_metadata_to_disk(&loaded.metadata)?;
+    }
+    Ok(loaded.metadata)
+}
+
+/// Auto-tag rules from the saved settings
+///
+/// Reads without locking or migrating; an unreadable file means no rules.
+pub(crate) fn read_auto_tag_rules() -> Vec<AutoTagRule> {
+    get_user_data_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| parse_metadata(&content).ok())
+        .map(|(metadata, _)| metadata.settings.auto_tag_rules)
+        .unwrap_or_default()
+}
+
+/// The cached metadata, or the file's contents if nothing was loaded yet
+pub(crate) async fn current_metadata(state: &MetadataState) -> Result<UserMetadata, String> {
+    let cached = state
+        .metadata
+        .lock()
+        .map_err(|e| format!("Failed to lock metadata: {e}"))?
+        .clone();
+    match cached {
+        Some(metadata) => Ok(metadata),
+        None => tauri::async_runtime::spawn_blocking(read_metadata_from_disk)
+            .await
+            .map_err(|e| format!("Task join error: {e}"))?,
+    }
+}
+
 /// Load user metadata from disk
 /// Creates default metadata if file doesn't exist
 #[tauri::command]
-pub async fn load_user_metadata(state: State<'_, MetadataState>) -> Result<UserMetadata, String> {
-    let path = get_user_data_path()?;
-
+pub async fn load_user_metadata(
+    app_handle: AppHandle,
+    state: State<'_, MetadataState>,
+) -> Result<UserMetadata, String> {
     // Perform blocking file I/O off the async runtime
-    let metadata = tauri::async_runtime::spawn_blocking(move || {
-        if path.exists() {
-            let content = fs::read_to_string(&path)
-                .map_err(|e| format!("Failed to read metadata file: {e}"))?;
-            serde_json::from_str(&content).map_err(|e| format!("Failed to parse metadata: {e}"))
-        } else {
-            Ok(UserMetadata::new())
-        }
-    })
-    .await
-    .map_err(|e| format!("Task join error: {e}"))??;
+    let metadata = tauri::async_runtime::spawn_blocking(read_metadata_from_disk)
+        .await
+        .map_err(|e| format!("Task join error: {e}"))??;
 
     // Cache the metadata (lock is quick, no need to spawn_blocking)
+    {
+        let mut cached = state
+            .metadata
+            .lock()
+            .map_err(|e| format!("Failed to lock metadata: {e}"))?;
+        *cached = Some(metadata.clone());
+    }
+
+    // Pick up edits from other windows or sync tools; the app works without it
+    let mut watcher = state
+        .watcher
+        .lock()
+        .map_err(|e| format!("Failed to lock metadata watcher: {e}"))?;
+    if watcher.is_none() {
+        match ensure_metadata_folder().and_then(|folder| watch_user_metadata(app_handle, &folder)) {
+            Ok(debouncer) => *watcher = Some(debouncer),
+            Err(e) => log::warn!("Metadata watcher not started: {e}"),
+        }
+    }
+
+    Ok(metadata)
+}
+
+/// Reload the cache after user-data.json changed on disk
+///
+/// Returns the new metadata if it differs from the cache. A file that doesn't
+/// parse (e.g. caught mid-write by a non-atomic writer) is skipped until the
+/// next change.
+pub(crate) fn reload_if_changed(state: &MetadataState) -> Result<Option<UserMetadata>, String> {
+    let Ok(content) = fs::read_to_string(get_user_data_path()?) else {
+        return Ok(None);
+    };
+    let Ok((disk, _)) = parse_metadata(&content) else {
+        return Ok(None);
+    };
+
     let mut cached = state
         .metadata
         .lock()
         .map_err(|e| format!("Failed to lock metadata: {e}"))?;
-    *cached = Some(metadata.clone());
+    match cached.as_ref() {
+        Some(current) if *current != disk => {
+            *cached = Some(disk.clone());
+            Ok(Some(disk))
+        }
+        _ => Ok(None),
+    }
+}
 
-    Ok(metadata)
+/// Take the advisory lock on user-data.json, released when the file is dropped
+fn lock_metadata_file() -> Result<fs::File, String> {
+    ensure_metadata_folder()?;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(get_lock_path()?)
+        .map_err(|e| format!("Failed to open metadata lock: {e}"))?;
+    FileExt::lock_exclusive(&file).map_err(|e| format!("Failed to lock metadata file: {e}"))?;
+    Ok(file)
+}
+
+/// Write `ours`, an edited copy of `base`, without losing concurrent edits
+///
+/// Edited fields get the current time as their change time first.
+/// Under the file lock the current file is compared with `base`. If another
+/// writer saved in between, the two edits are merged field by field (see
+/// [`UserMetadata::merge_concurrent`]). The written metadata gets the next
+/// revision and is returned.
+pub(crate) fn commit_metadata(
+    base: &UserMetadata,
+    mut ours: UserMetadata,
+) -> Result<UserMetadata, String> {
+    ours.stamp_changes(base, &Utc::now().to_rfc3339());
+    let _lock = lock_metadata_file()?;
+    let loaded = load_metadata_file(&get_user_data_path()?)?;
+    let theirs = loaded.metadata;
+
+    // A quarantined file says nothing about what others changed; merging with
+    // its empty stand-in would delete everything in `base`
+    let mut merged = if theirs == *base || loaded.quarantined.is_some() {
+        ours
+    } else {
+        UserMetadata::merge_concurrent(base, &ours, &theirs)
+    };
+    merged.revision = theirs.revision.max(base.revision) + 1;
+    save_metadata_to_disk(&merged)?;
+    Ok(merged)
+}
+
+/// Apply `edit` to the cached metadata and commit it
+///
+/// Loads from disk first if nothing is cached yet. The cache only moves
+/// forward, so a slower commit can't replace a newer revision.
+pub(crate) async fn update_metadata<F>(
+    state: &MetadataState,
+    edit: F,
+) -> Result<UserMetadata, String>
+where
+    F: FnOnce(&mut UserMetadata),
+{
+    update_metadata_from(state, None, edit).await
+}
+
+/// [`update_metadata`] for an edit made on a copy at `base_revision`
+///
+/// Fails if the cached metadata has moved past that revision, since the
+/// edit would then undo the newer changes.
+async fn update_metadata_from<F>(
+    state: &MetadataState,
+    base_revision: Option<u64>,
+    edit: F,
+) -> Result<UserMetadata, String>
+where
+    F: FnOnce(&mut UserMetadata),
+{
+    let base = current_metadata(state).await?;
+    if let Some(revision) = base_revision.filter(|&r| r != base.revision) {
+        return Err(format!(
+            "Metadata changed since it was loaded (revision {revision}, now {}). Reload it before saving.",
+            base.revision
+        ));
+    }
+    let mut ours = base.clone();
+    edit(&mut ours);
+
+    // Perform blocking file I/O off the async runtime
+    let written = tauri::async_runtime::spawn_blocking(move || commit_metadata(&base, ours))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))??;
+
+    let mut cached = state
+        .metadata
+        .lock()
+        .map_err(|e| format!("Failed to lock metadata: {e}"))?;
+    if !matches!(cached.as_ref(), Some(current) if current.revision > written.revision) {
+        *cached = Some(written.clone());
+    }
+    Ok(written)
 }
 
 /// Internal helper to save metadata to disk (blocking)
-fn save_metadata_to_disk(metadata: &UserMetadata) -> Result<(), String> {
+pub(crate) fn save_metadata_to_disk(metadata: &UserMetadata) -> Result<(), String> {
     ensure_metadata_folder()?;
     let path = get_user_data_path()?;
 
@@ -120,25 +311,20 @@ fn save_metadata_to_disk(metadata: &UserMetadata) -> Result<(), String> {
 }
 
 /// Save user metadata to disk with atomic write
+///
+/// `metadata.revision` is the revision the copy was edited from. Changes
+/// saved by another instance since then are merged in; a copy older than the
+/// cached metadata is refused, as every stale field in it would count as an
+/// edit.
 #[tauri::command]
 pub async fn save_user_metadata(
     metadata: UserMetadata,
     state: State<'_, MetadataState>,
 ) -> Result<(), String> {
-    let metadata_clone = metadata.clone();
-
-    // Perform blocking file I/O off the async runtime
-    tauri::async_runtime::spawn_blocking(move || save_metadata_to_disk(&metadata_clone))
-        .await
-        .map_err(|e| format!("Task join error: {e}"))??;
-
-    // Update cache
-    let mut cached = state
-        .metadata
-        .lock()
-        .map_err(|e| format!("Failed to lock metadata: {e}"))?;
-    *cached = Some(metadata);
-
+    update_metadata_from(&state, Some(metadata.revision), |current| {
+        *current = metadata;
+    })
+    .await?;
     Ok(())
 }
 
@@ -149,32 +335,15 @@ pub async fn update_session_metadata(
     update: SessionMetadata,
     state: State<'_, MetadataState>,
 ) -> Result<UserMetadata, String> {
-    // Perform quick in-memory mutation while holding lock, then release
-    let metadata_to_save = {
-        let mut cached = state
-            .metadata
-            .lock()
-            .map_err(|e| format!("Failed to lock metadata: {e}"))?;
-
-        let metadata = cached.get_or_insert_with(UserMetadata::new);
-
+    update_metadata(&state, |metadata| {
         // Update or insert session metadata
         if update.is_empty() {
             metadata.sessions.remove(&session_id);
         } else {
             metadata.sessions.insert(session_id, update);
         }
-
-        metadata.clone()
-    }; // Lock released here
-
-    // Perform blocking file I/O off the async runtime
-    let metadata_clone = metadata_to_save.clone();
-    tauri::async_runtime::spawn_blocking(move || save_metadata_to_disk(&metadata_clone))
-        .await
-        .map_err(|e| format!("Task join error: {e}"))??;
-
-    Ok(metadata_to_save)
+    })
+    .await
 }
 
 /// Update metadata for a specific project
@@ -187,32 +356,15 @@ pub async fn update_project_metadata(
     // Validate that project path is absolute
     validate_absolute_path(&project_path)?;
 
-    // Perform quick in-memory mutation while holding lock, then release
-    let metadata_to_save = {
-        let mut cached = state
-            .metadata
-            .lock()
-            .map_err(|e| format!("Failed to lock metadata: {e}"))?;
-
-        let metadata = cached.get_or_insert_with(UserMetadata::new);
-
+    update_metadata(&state, |metadata| {
         // Update or insert project metadata
         if update.is_empty() {
             metadata.projects.remove(&project_path);
         } else {
             metadata.projects.insert(project_path, update);
         }
-
-        metadata.clone()
-    }; // Lock released here
-
-    // Perform blocking file I/O off the async runtime
-    let metadata_clone = metadata_to_save.clone();
-    tauri::async_runtime::spawn_blocking(move || save_metadata_to_disk(&metadata_clone))
-        .await
-        .map_err(|e| format!("Task join error: {e}"))??;
-
-    Ok(metadata_to_save)
+    })
+    .await
 }
 
 /// Update global user settings
@@ -221,26 +373,7 @@ pub async fn update_user_settings(
     settings: UserSettings,
     state: State<'_, MetadataState>,
 ) -> Result<UserMetadata, String> {
-    // Perform quick in-memory mutation while holding lock, then release
-    let metadata_to_save = {
-        let mut cached = state
-            .metadata
-            .lock()
-            .map_err(|e| format!("Failed to lock metadata: {e}"))?;
-
-        let metadata = cached.get_or_insert_with(UserMetadata::new);
-        metadata.settings = settings;
-
-        metadata.clone()
-    }; // Lock released here
-
-    // Perform blocking file I/O off the async runtime
-    let metadata_clone = metadata_to_save.clone();
-    tauri::async_runtime::spawn_blocking(move || save_metadata_to_disk(&metadata_clone))
-        .await
-        .map_err(|e| format!("Task join error: {e}"))??;
-
-    Ok(metadata_to_save)
+    update_metadata(&state, |metadata| metadata.settings = settings).await
 }
 
 /// Check if a project should be hidden based on metadata
@@ -351,4 +484,87 @@ mod tests {
 
         drop(temp);
     }
+
+    #[test]
+    fn test_commit_merges_concurrent_writers() {
+        let (_guard, _temp) = setup_test_env();
+
+        let mut initial = UserMetadata::new();
+        initial.get_session_mut("a").starred = Some(true);
+        let base = commit_metadata(&UserMetadata::new(), initial).unwrap();
+        assert_eq!(base.revision, 1);
+
+        // Two instances edit the same loaded copy
+        let mut first = base.clone();
+        first.get_session_mut("b").tags = vec!["work".to_string()];
+        let first = commit_metadata(&base, first).unwrap();
+        assert_eq!(first.revision, 2);
+
+        let mut second = base.clone();
+        second.get_session_mut("a").notes = Some("check later".to_string());
+        let second = commit_metadata(&base, second).unwrap();
+        assert_eq!(second.revision, 3);
+
+        let on_disk = read_metadata_from_disk().unwrap();
+        assert_eq!(on_disk, second);
+        assert_eq!(on_disk.get_session("b").unwrap().tags, ["work"]);
+        let a = on_disk.get_session("a").unwrap();
+        assert_eq!(a.starred, Some(true));
+        assert_eq!(a.notes.as_deref(), Some("check later"));
+    }
+
+    #[test]
+    fn test_reload_if_changed_detects_external_write() {
+        let (_guard, _temp) = setup_test_env();
+        let state = MetadataState::default();
+        let written = commit_metadata(&UserMetadata::new(), UserMetadata::new()).unwrap();
+        *state.metadata.lock().unwrap() = Some(written.clone());
+        assert!(reload_if_changed(&state).unwrap().is_none());
+
+        let mut external = written.clone();
+        external.get_session_mut("s").starred = Some(true);
+        commit_metadata(&written, external).unwrap();
+        let reloaded = reload_if_changed(&state).unwrap().unwrap();
+        assert_eq!(reloaded.get_session("s").unwrap().starred, Some(true));
+        assert_eq!(state.metadata.lock().unwrap().as_ref(), Some(&reloaded));
+    }
+
+    #[test]
+    fn test_commit_over_corrupt_file_keeps_base() {
+        let (_guard, _temp) = setup_test_env();
+        let mut initial = UserMetadata::new();
+        initial.get_session_mut("a").starred = Some(true);
+        let base = commit_metadata(&UserMetadata::new(), initial).unwrap();
+
+        fs::write(get_user_data_path().unwrap(), "{ not json").unwrap();
+        let mut ours = base.clone();
+        ours.get_session_mut("b").starred = Some(true);
+        let written = commit_metadata(&base, ours).unwrap();
+
+        assert_eq!(written.get_session("a").unwrap().starred, Some(true));
+        assert_eq!(written.get_session("b").unwrap().starred, Some(true));
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)] // The guard serializes HOME for the whole test
+    async fn test_save_refuses_stale_copy() {
+        let (_guard, _temp) = setup_test_env();
+        let state = MetadataState::default();
+        let loaded = update_metadata(&state, |_| {}).await.unwrap();
+
+        // Another edit lands after the frontend copy was loaded
+        update_metadata(&state, |m| m.get_session_mut("a").starred = Some(true))
+            .await
+            .unwrap();
+
+        let mut stale = loaded.clone();
+        stale.get_session_mut("b").starred = Some(true);
+        let err = update_metadata_from(&state, Some(stale.revision), |m| *m = stale)
+            .await
+            .unwrap_err();
+        assert!(err.contains("changed since it was loaded"));
+        let current = read_metadata_from_disk().unwrap();
+        assert_eq!(current.get_session("a").unwrap().starred, Some(true));
+        assert!(current.get_session("b").is_none());
+    }
 }
@@ -0,0 +1,415 @@
+//! Reconcile user metadata with the sessions and projects on disk
+//!
+//! Claude Code deletes old sessions on its own and projects folders come and
+//! go, but `UserMetadata` keeps their entries forever. [`find_metadata_orphans`]
+//! reports entries with nothing behind them and sessions whose file moved to
+//! another project folder, found by file name. [`clean_up_metadata`] applies
+//! only the re-links and prunes only the orphans the user confirmed. A pruned
+//! entry keeps the times its fields were cleared for a while (see
+//! [`UserMetadata::stamp_changes`]), so an older import doesn't bring it back.
+
+use super::archive::parked_sessions;
+use super::metadata::{current_metadata, update_metadata, MetadataState};
+use super::relocate::encode_project_path;
+use super::trusted_symlinks::check_claude_folder;
+use crate::models::{
+    MetadataCleanupResult, MetadataOrphanReport, OrphanedSession, SessionRelink, UserMetadata,
+};
+use crate::utils::decode_project_path;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+use walkdir::WalkDir;
+
+/// Sessions and projects currently on disk
+#[derive(Default)]
+pub(super) struct Inventory {
+    /// The scanned `projects/` folder; session keys elsewhere belong to
+    /// another Claude folder
+    root: PathBuf,
+    /// Session keys, as `load_project_sessions` produces them
+    sessions: HashSet<String>,
+    /// Session keys by file stem (the session id)
+    by_stem: HashMap<String, Vec<String>>,
+    /// Project folder names under `projects/`
+    folders: HashSet<String>,
+    /// Decoded project paths
+    projects: HashSet<String>,
+    /// `(project folder, session id)` held in the trash or an archive
+    parked: HashSet<(String, String)>,
+}
+
+fn file_stem(path: &Path) -> Option<String> {
+    path.file_stem().map(|s| s.to_string_lossy().to_string())
+}
+
+impl Inventory {
+    /// The only session file named like `key`'s, if it isn't `key` itself
+    ///
+    /// Session ids are UUIDs, so a single file with the same name is the same
+    /// session; several copies are left for the user to sort out.
+    fn moved_session(&self, key: &str) -> Option<&String> {
+        if self.sessions.contains(key) {
+            return None;
+        }
+        let stem = file_stem(Path::new(key))?;
+        match self.by_stem.get(&stem).map(Vec::as_slice) {
+            Some([target]) => Some(target),
+            _ => None,
+        }
+    }
+
+    /// `key`, or where its session file is now
+    pub(super) fn resolve_session_key(&self, key: &str) -> String {
+        self.moved_session(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+
+/// Everything under `projects_root`
+///
+/// Fails if the folder can't be read, since every entry would then look
+/// orphaned.
+pub(super) fn scan_inventory(
+    projects_root: &Path,
+    parked: HashSet<(String, String)>,
+) -> Result<Inventory, String> {
+    let mut inventory = Inventory {
+        root: projects_root.to_path_buf(),
+        parked,
+        ..Default::default()
+    };
+    let entries = fs::read_dir(projects_root)
+        .map_err(|e| format!("Failed to read {}: {e}", projects_root.display()))?;
+    for entry in entries.flatten() {
+        let folder = entry.path();
+        if !folder.is_dir() {
+            continue;
+        }
+        inventory
+            .folders
+            .insert(entry.file_name().to_string_lossy().to_string());
+        inventory
+            .projects
+            .insert(decode_project_path(&folder.to_string_lossy()));
+
+        for file in WalkDir::new(&folder)
+            .into_iter()
+            .filter_map(std::result::Result::ok)
+            .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        {
+            let key = file.path().to_string_lossy().to_string();
+            if let Some(stem) = file_stem(file.path()) {
+                inventory.by_stem.entry(stem).or_default().push(key.clone());
+            }
+            inventory.sessions.insert(key);
+        }
+    }
+    Ok(inventory)
+}
+
+fn find_orphans(inventory: &Inventory, metadata: &UserMetadata) -> MetadataOrphanReport {
+    let mut report = MetadataOrphanReport::default();
+
+    let mut session_keys: Vec<&String> = metadata.sessions.keys().collect();
+    session_keys.sort();
+    for key in session_keys {
+        if inventory.sessions.contains(key)
+            || !Path::new(key).starts_with(&inventory.root)
+            || !metadata.sessions[key].has_values()
+        {
+            continue;
+        }
+        let path = Path::new(key);
+        let stem = file_stem(path).unwrap_or_default();
+        let folder = path
+            .parent()
+            .and_then(Path::file_name)
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if inventory.parked.contains(&(folder, stem)) {
+            report.parked_sessions += 1;
+            continue;
+        }
+        match inventory.moved_session(key) {
+            Some(target) => report.relinks.push(SessionRelink {
+                from: key.clone(),
+                to: target.clone(),
+            }),
+            None => report.orphaned_sessions.push(OrphanedSession {
+                session_id: key.clone(),
+                metadata: metadata.sessions[key].clone(),
+            }),
+        }
+    }
+
+    let is_live = |path: &str| {
+        inventory.projects.contains(path) || inventory.folders.contains(&encode_project_path(path))
+    };
+    // Project entries aren't tied to a Claude folder; only those with session
+    // entries in a folder of the scanned one are known to belong to it
+    let known_folders: HashSet<String> = metadata
+        .sessions
+        .keys()
+        .filter_map(|key| Path::new(key).parent())
+        .filter(|folder| folder.parent() == Some(inventory.root.as_path()))
+        .filter_map(Path::file_name)
+        .map(|name| name.to_string_lossy().to_string())
+        .chain(inventory.parked.iter().map(|(folder, _)| folder.clone()))
+        .collect();
+    // A missing project still grouping one that exists is kept
+    let parents: HashSet<&str> = metadata
+        .projects
+        .iter()
+        .filter(|(path, _)| is_live(path))
+        .filter_map(|(_, project)| project.parent_project.as_deref())
+        .collect();
+    report.orphaned_projects = metadata
+        .projects
+        .iter()
+        .filter(|(path, project)| {
+            project.has_values()
+                && known_folders.contains(&encode_project_path(path))
+                && !is_live(path)
+                && !parents.contains(path.as_str())
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    report.orphaned_projects.sort();
+
+    report
+}
+
+/// Re-link the confirmed moved sessions, then prune the confirmed orphans,
+/// as far as they still apply
+fn apply_cleanup(
+    metadata: &mut UserMetadata,
+    inventory: &Inventory,
+    relink_sessions: &HashSet<String>,
+    prune_sessions: &HashSet<String>,
+    prune_projects: &HashSet<String>,
+) -> MetadataCleanupResult {
+    let report = find_orphans(inventory, metadata);
+    let relinks: HashMap<String, String> = report
+        .relinks
+        .into_iter()
+        .filter(|relink| relink_sessions.contains(&relink.from))
+        .map(|relink| (relink.from, relink.to))
+        .collect();
+    let mut result = MetadataCleanupResult {
+        relinked_sessions: metadata.rename_session_keys(&relinks),
+        ..Default::default()
+    };
+    for orphan in report.orphaned_sessions {
+        if prune_sessions.contains(&orphan.session_id) {
+            metadata.sessions.remove(&orphan.session_id);
+            result.pruned_sessions += 1;
+        }
+    }
+    for path in report.orphaned_projects {
+        if prune_projects.contains(&path) {
+            metadata.projects.remove(&path);
+            result.pruned_projects += 1;
+        }
+    }
+    result
+}
+
+pub(super) async fn load_inventory(claude_path: String) -> Result<Inventory, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let claude_folder = PathBuf::from(claude_path);
+        check_claude_folder(&claude_folder)?;
+        let projects_root = claude_folder.join("projects");
+        scan_inventory(&projects_root, parked_sessions()?)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Report metadata entries with no session or project behind them
+///
+/// Only sessions under `<claude_path>/projects`, and projects with sessions
+/// there, are considered. Nothing is changed; pass the re-links and orphans
+/// the user confirms to [`clean_up_metadata`].
+#[tauri::command]
+pub async fn find_metadata_orphans(
+    claude_path: String,
+    state: State<'_, MetadataState>,
+) -> Result<MetadataOrphanReport, String> {
+    let metadata = current_metadata(&state).await?;
+    let inventory = load_inventory(claude_path).await?;
+    tauri::async_runtime::spawn_blocking(move || Ok(find_orphans(&inventory, &metadata)))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Re-link the confirmed moved sessions and prune the confirmed orphans
+///
+/// `relink_sessions` holds the `from` keys of re-links to apply. Orphans are
+/// looked up again first, so an entry whose session reappeared since the
+/// report is kept even if it was confirmed.
+#[tauri::command]
+pub async fn clean_up_metadata(
+    claude_path: String,
+    relink_sessions: Vec<String>,
+    prune_sessions: Vec<String>,
+    prune_projects: Vec<String>,
+    state: State<'_, MetadataState>,
+) -> Result<MetadataCleanupResult, String> {
+    let inventory = load_inventory(claude_path).await?;
+    let relink_sessions: HashSet<String> = relink_sessions.into_iter().collect();
+    let prune_sessions: HashSet<String> = prune_sessions.into_iter().collect();
+    let prune_projects: HashSet<String> = prune_projects.into_iter().collect();
+    let mut result = MetadataCleanupResult::default();
+    update_metadata(&state, |metadata| {
+        result = apply_cleanup(
+            metadata,
+            &inventory,
+            &relink_sessions,
+            &prune_sessions,
+            &prune_projects,
+        );
+    })
+    .await?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touch(path: &Path) -> String {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "{}\n").unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn key(root: &Path, folder: &str, session: &str) -> String {
+        root.join(folder)
+            .join(format!("{session}.jsonl"))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn test_find_and_clean_up_orphans() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let live = touch(&root.join("-Users-me-app").join("live.jsonl"));
+        let moved_to = touch(&root.join("-Users-me-new").join("moved.jsonl"));
+        let declined_to = touch(&root.join("-Users-me-new").join("declined.jsonl"));
+        touch(&root.join("-Users-me-a").join("copied.jsonl"));
+        touch(&root.join("-Users-me-b").join("copied.jsonl"));
+
+        let mut metadata = UserMetadata::new();
+        metadata.get_session_mut(&live).starred = Some(true);
+        let moved = key(root, "-Users-me-old", "moved");
+        metadata.get_session_mut(&moved).tags = vec!["keep".to_string()];
+        let copied = key(root, "-Users-me-gone", "copied");
+        metadata.get_session_mut(&copied).starred = Some(true);
+        let trashed = key(root, "-Users-me-app", "trashed");
+        metadata.get_session_mut(&trashed).starred = Some(true);
+        let deleted = key(root, "-Users-me-app", "deleted");
+        metadata.get_session_mut(&deleted).custom_name = Some("Old".to_string());
+        let declined = key(root, "-Users-me-old", "declined");
+        metadata.get_session_mut(&declined).starred = Some(true);
+        // Belongs to another Claude folder, which wasn't scanned
+        let elsewhere = temp_dir
+            .path()
+            .with_extension("other")
+            .join("-Users-me-app")
+            .join("elsewhere.jsonl")
+            .to_string_lossy()
+            .to_string();
+        metadata.get_session_mut(&elsewhere).starred = Some(true);
+
+        metadata
+            .projects
+            .entry("/Users/me/app".to_string())
+            .or_default()
+            .alias = Some("App".to_string());
+        metadata
+            .projects
+            .entry("/Users/me/gone".to_string())
+            .or_default()
+            .hidden = Some(true);
+        metadata
+            .projects
+            .entry("/Users/me/parent".to_string())
+            .or_default()
+            .alias = Some("Parent".to_string());
+        // No sessions of it were seen here: it may be another Claude folder's
+        metadata
+            .projects
+            .entry("/Users/me/unknown".to_string())
+            .or_default()
+            .hidden = Some(true);
+        metadata
+            .projects
+            .entry("/Users/me/new".to_string())
+            .or_default()
+            .parent_project = Some("/Users/me/parent".to_string());
+
+        let parked = HashSet::from([("-Users-me-app".to_string(), "trashed".to_string())]);
+        let inventory = scan_inventory(root, parked).unwrap();
+        let report = find_orphans(&inventory, &metadata);
+
+        assert_eq!(
+            report.relinks,
+            vec![
+                SessionRelink {
+                    from: declined.clone(),
+                    to: declined_to.clone()
+                },
+                SessionRelink {
+                    from: moved.clone(),
+                    to: moved_to.clone()
+                }
+            ]
+        );
+        let orphans: Vec<&str> = report
+            .orphaned_sessions
+            .iter()
+            .map(|o| o.session_id.as_str())
+            .collect();
+        assert_eq!(orphans, [deleted.as_str(), copied.as_str()]);
+        assert_eq!(report.orphaned_projects, ["/Users/me/gone"]);
+        assert_eq!(report.parked_sessions, 1);
+
+        // Only the confirmed re-links and orphans apply; the copied session
+        // stays unconfirmed
+        let result = apply_cleanup(
+            &mut metadata,
+            &inventory,
+            &HashSet::from([moved.clone()]),
+            &HashSet::from([deleted.clone(), live.clone(), elsewhere.clone()]),
+            &HashSet::from(["/Users/me/gone".to_string(), "/Users/me/app".to_string()]),
+        );
+        assert_eq!(
+            result,
+            MetadataCleanupResult {
+                relinked_sessions: 1,
+                pruned_sessions: 1,
+                pruned_projects: 1,
+            }
+        );
+        assert_eq!(metadata.sessions[&moved_to].tags, ["keep"]);
+        assert!(!metadata.sessions.contains_key(&moved));
+        assert!(!metadata.sessions.contains_key(&deleted));
+        assert!(metadata.sessions.contains_key(&live));
+        assert!(metadata.sessions.contains_key(&copied));
+        assert!(metadata.sessions.contains_key(&trashed));
+        assert!(metadata.sessions.contains_key(&declined));
+        assert!(!metadata.sessions.contains_key(&declined_to));
+        assert!(metadata.sessions.contains_key(&elsewhere));
+        assert!(metadata.projects.contains_key("/Users/me/app"));
+        assert!(metadata.projects.contains_key("/Users/me/parent"));
+        assert!(!metadata.projects.contains_key("/Users/me/gone"));
+
+        // An unreadable folder would make everything look orphaned
+        assert!(scan_inventory(&root.join("missing"), HashSet::new()).is_err());
+    }
+}
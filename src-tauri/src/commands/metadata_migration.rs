@@ -0,0 +1,403 @@
+//! Schema migrations for user-data.json
+//!
+//! Migrations run on the raw JSON before it is deserialized, one version at a
+//! time, so each step only needs to know the schema it upgrades from. The file
+//! is backed up before a migrated copy is written, and a file that can't be
+//! read at all is moved aside instead of failing the app.
+
+use crate::glob::within_limits;
+use crate::models::{UserMetadata, METADATA_SCHEMA_VERSION};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+type Migration = fn(&mut Value);
+
+/// `MIGRATIONS[i]` upgrades schema version `i + 1` to `i + 2`
+const MIGRATIONS: [Migration; (METADATA_SCHEMA_VERSION - 1) as usize] =
+    [migrate_v1_to_v2, migrate_v2_to_v3];
+
+/// v2: `groupingMode` replaces the boolean `worktreeGrouping`
+///
+/// The legacy flag is kept so older builds still read the same setting.
+fn migrate_v1_to_v2(metadata: &mut Value) {
+    let Some(settings) = metadata.get_mut("settings").and_then(Value::as_object_mut) else {
+        return;
+    };
+    if settings.get("groupingMode").is_some_and(|m| !m.is_null()) {
+        return;
+    }
+    if let Some(grouping) = settings.get("worktreeGrouping").and_then(Value::as_bool) {
+        let mode = if grouping { "worktree" } else { "none" };
+        settings.insert("groupingMode".to_string(), Value::from(mode));
+    }
+}
+
+/// v3: stored globs use the `crate::glob` syntax
+///
+/// Hidden-project and `fileTouched` auto-tag patterns used to be matched
+/// against the whole path, with `*` and `?` crossing `/` and every other
+/// character literal. Each pattern is rewritten so it matches what it did;
+/// one whose rewrite would exceed the glob limits (and so be ignored) is kept
+/// as it was, with a warning, since it then still matches most of what it did.
+fn migrate_v2_to_v3(metadata: &mut Value) {
+    let Some(settings) = metadata.get_mut("settings").and_then(Value::as_object_mut) else {
+        return;
+    };
+    let rewrite = |pattern: &mut Value| {
+        let Some(legacy) = pattern.as_str() else {
+            return;
+        };
+        let rewritten = rewrite_legacy_glob(legacy);
+        if within_limits(&rewritten) {
+            *pattern = Value::from(rewritten);
+        } else {
+            log::warn!("Pattern '{legacy}' kept as it was: its rewrite '{rewritten}' is too long");
+        }
+    };
+    if let Some(hidden) = settings
+        .get_mut("hiddenPatterns")
+        .and_then(Value::as_array_mut)
+    {
+        hidden.iter_mut().for_each(rewrite);
+    }
+    if let Some(rules) = settings
+        .get_mut("autoTagRules")
+        .and_then(Value::as_array_mut)
+    {
+        rules
+            .iter_mut()
+            .filter_map(|rule| rule.get_mut("condition"))
+            .filter(|condition| condition["type"] == "fileTouched")
+            .filter_map(|condition| condition.get_mut("pattern"))
+            .for_each(rewrite);
+    }
+}
+
+/// Spell a v2 pattern in the current glob syntax
+///
+/// `*` becomes `**` and `?` becomes `{?,/}`; `[`, `{` and a leading `!` are
+/// quoted. A pattern without `/` would now match only the last path
+/// component, so one is added that matches nothing extra: `**{/,}` after a
+/// wildcard, or a `/` in the last character's class for a plain name, which
+/// could only ever match a path equal to it.
+pub(super) fn rewrite_legacy_glob(legacy: &str) -> String {
+    let quote = |c: char| match c {
+        '[' => "[[]".to_string(),
+        '{' => "[{]".to_string(),
+        _ => c.to_string(),
+    };
+    let needs_slash = !legacy.contains(['/', '?']);
+
+    if needs_slash && !legacy.contains('*') {
+        // A plain name: put the `/` in a class with its last character
+        let mut chars = legacy.chars();
+        let Some(last) = chars.next_back() else {
+            return String::new();
+        };
+        let mut out: String = chars.map(quote).collect();
+        if out.starts_with('!') {
+            out.replace_range(..1, "{!}");
+        }
+        if last == ']' {
+            out.push_str("[]/]");
+        } else {
+            out.push_str(&format!("[/{last}]"));
+        }
+        return out;
+    }
+
+    let mut out = String::new();
+    let mut chars = legacy.chars().peekable();
+    if legacy.starts_with('!') {
+        chars.next();
+        out.push_str("{!}");
+    }
+    let mut slash_added = !needs_slash;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                while chars.peek() == Some(&'*') {
+                    chars.next();
+                }
+                out.push_str("**");
+                if chars.peek() == Some(&'/') {
+                    // `**/` would also match no directory at all
+                    chars.next();
+                    out.push_str("{/}");
+                } else if !slash_added {
+                    out.push_str("{/,}");
+                    slash_added = true;
+                }
+            }
+            '?' => out.push_str("{?,/}"),
+            _ => out.push_str(&quote(c)),
+        }
+    }
+    out
+}
+
+/// Version a metadata document was written with
+///
+/// Files from before versioning have no `version` and use the v1 layout.
+fn schema_version(metadata: &Value) -> u32 {
+    metadata
+        .get("version")
+        .and_then(Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Upgrade a metadata document to the current schema in place
+///
+/// Steps skip parts that don't have the shape they expect. Returns the
+/// version it was written with. Documents from a newer version are left
+/// untouched.
+pub(crate) fn migrate(metadata: &mut Value) -> Result<u32, String> {
+    if !metadata.is_object() {
+        return Err("Metadata root is not an object".to_string());
+    }
+    let from = schema_version(metadata);
+    for version in from..METADATA_SCHEMA_VERSION {
+        MIGRATIONS[(version - 1) as usize](metadata);
+        metadata["version"] = Value::from(version + 1);
+    }
+    Ok(from)
+}
+
+/// Outcome of reading user-data.json
+#[derive(Debug)]
+pub(crate) struct LoadedMetadata {
+    pub metadata: UserMetadata,
+    /// Set when the file was upgraded and should be written back
+    pub migrated_from: Option<u32>,
+    /// Where an unreadable file was moved to
+    pub quarantined: Option<PathBuf>,
+}
+
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    path.with_extension(format!("json.v{version}.bak"))
+}
+
+/// Move an unreadable metadata file aside so a fresh one can be started
+fn quarantine(path: &Path) -> Result<PathBuf, String> {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%3f");
+    let target = path.with_extension(format!("json.corrupt-{stamp}"));
+    fs::rename(path, &target).map_err(|e| format!("Failed to quarantine metadata file: {e}"))?;
+    Ok(target)
+}
+
+/// Parse and migrate metadata file content, returning it with its stored version
+pub(crate) fn parse_metadata(content: &str) -> Result<(UserMetadata, u32), String> {
+    let mut value: Value =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse metadata: {e}"))?;
+    let from = migrate(&mut value)?;
+    let metadata =
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse metadata: {e}"))?;
+    Ok((metadata, from))
+}
+
+/// Read, migrate and deserialize a metadata file
+///
+/// A missing file yields default metadata. Before an older file is migrated it
+/// is copied to `user-data.json.v<N>.bak`; a file that can't be parsed or
+/// migrated is renamed to `user-data.json.corrupt-<timestamp>` and default
+/// metadata is returned.
+pub(crate) fn load_metadata_file(path: &Path) -> Result<LoadedMetadata, String> {
+    if !path.exists() {
+        return Ok(LoadedMetadata {
+            metadata: UserMetadata::new(),
+            migrated_from: None,
+            quarantined: None,
+        });
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read metadata file: {e}"))?;
+
+    match parse_metadata(&content) {
+        Ok((metadata, from)) if from < METADATA_SCHEMA_VERSION => {
+            fs::copy(path, backup_path(path, from))
+                .map_err(|e| format!("Failed to back up metadata file: {e}"))?;
+            Ok(LoadedMetadata {
+                metadata,
+                migrated_from: Some(from),
+                quarantined: None,
+            })
+        }
+        Ok((metadata, from)) => {
+            if from > METADATA_SCHEMA_VERSION {
+                // Saving drops fields this version doesn't know; keep the original
+                let backup = backup_path(path, from);
+                if !backup.exists() {
+                    fs::copy(path, &backup)
+                        .map_err(|e| format!("Failed to back up metadata file: {e}"))?;
+                }
+                log::warn!(
+                    "Metadata was written by a newer version (v{from}); a copy was kept at {}",
+                    backup.display()
+                );
+            }
+            Ok(LoadedMetadata {
+                metadata,
+                migrated_from: None,
+                quarantined: None,
+            })
+        }
+        Err(e) => {
+            let target = quarantine(path)?;
+            log::warn!("{e}");
+            Ok(LoadedMetadata {
+                metadata: UserMetadata::new(),
+                migrated_from: None,
+                quarantined: Some(target),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_migrate_v1_to_v2_sets_grouping_mode() {
+        let mut enabled = json!({"settings": {"worktreeGrouping": true}});
+        migrate_v1_to_v2(&mut enabled);
+        assert_eq!(enabled["settings"]["groupingMode"], "worktree");
+        assert_eq!(enabled["settings"]["worktreeGrouping"], true);
+
+        let mut disabled = json!({"settings": {"worktreeGrouping": false}});
+        migrate_v1_to_v2(&mut disabled);
+        assert_eq!(disabled["settings"]["groupingMode"], "none");
+
+        let mut explicit =
+            json!({"settings": {"worktreeGrouping": true, "groupingMode": "directory"}});
+        migrate_v1_to_v2(&mut explicit);
+        assert_eq!(explicit["settings"]["groupingMode"], "directory");
+
+        let mut unset = json!({"sessions": {}});
+        migrate_v1_to_v2(&mut unset);
+        assert_eq!(unset, json!({"sessions": {}}));
+    }
+
+    #[test]
+    fn test_migrate_v2_to_v3_keeps_pattern_meaning() {
+        let mut metadata = json!({"settings": {
+            "hiddenPatterns": ["*folders-dg-*", "/tmp/*/scratch", "?tmp", "[x]", "!old"],
+            "autoTagRules": [
+                {"tag": "sql", "condition": {"type": "fileTouched", "pattern": "*.sql"}},
+                {"tag": "opus", "condition": {"type": "model", "contains": "*"}}
+            ]
+        }});
+        migrate_v2_to_v3(&mut metadata);
+        let settings = &metadata["settings"];
+        assert_eq!(
+            settings["hiddenPatterns"],
+            json!([
+                "**{/,}folders-dg-**",
+                "/tmp/**{/}scratch",
+                "{?,/}tmp",
+                "[[]x[]/]",
+                "{!}ol[/d]"
+            ])
+        );
+        assert_eq!(
+            settings["autoTagRules"][0]["condition"]["pattern"],
+            "**{/,}.sql"
+        );
+        assert_eq!(settings["autoTagRules"][1]["condition"]["contains"], "*");
+
+        let rules = crate::glob::GlobRules::new(&["**{/,}folders-dg-**"]);
+        assert!(rules.matches_path("/Users/me/folders-dg-abc/app"));
+        // Used to be a literal path, not a negation or a folder name
+        let plain = crate::glob::GlobRules::new(&["{!}ol[/d]"]);
+        assert!(plain.matches_path("!old"));
+        assert!(!plain.matches_path("/Users/me/!old"));
+
+        // A rewrite past the length limit would be skipped; the pattern stays
+        let long = format!("/{}/?????", "a".repeat(230));
+        let mut too_long = json!({"settings": {"hiddenPatterns": [long.clone(), "*tmp"]}});
+        migrate_v2_to_v3(&mut too_long);
+        assert_eq!(
+            too_long["settings"]["hiddenPatterns"],
+            json!([long, "**{/,}tmp"])
+        );
+
+        let mut unset = json!({"sessions": {}});
+        migrate_v2_to_v3(&mut unset);
+        assert_eq!(unset, json!({"sessions": {}}));
+    }
+
+    #[test]
+    fn test_migrate_runs_chain_from_stored_version() {
+        // No version field: written before versioning, treated as v1
+        let mut unversioned = json!({"settings": {"worktreeGrouping": true}});
+        assert_eq!(migrate(&mut unversioned).unwrap(), 1);
+        assert_eq!(unversioned["version"], METADATA_SCHEMA_VERSION);
+        assert_eq!(unversioned["settings"]["groupingMode"], "worktree");
+
+        let mut current =
+            json!({"version": METADATA_SCHEMA_VERSION, "settings": {"worktreeGrouping": true}});
+        assert_eq!(migrate(&mut current).unwrap(), METADATA_SCHEMA_VERSION);
+        assert!(current["settings"].get("groupingMode").is_none());
+
+        let mut newer = json!({"version": METADATA_SCHEMA_VERSION + 1, "future": true});
+        let before = newer.clone();
+        assert_eq!(migrate(&mut newer).unwrap(), METADATA_SCHEMA_VERSION + 1);
+        assert_eq!(newer, before);
+
+        assert!(migrate(&mut json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_load_backs_up_before_migrating() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("user-data.json");
+        let original = r#"{"version":1,"sessions":{"s":{"starred":true}},"settings":{"worktreeGrouping":true}}"#;
+        fs::write(&path, original).unwrap();
+
+        let loaded = load_metadata_file(&path).unwrap();
+        assert_eq!(loaded.migrated_from, Some(1));
+        assert_eq!(loaded.metadata.version, METADATA_SCHEMA_VERSION);
+        assert_eq!(
+            loaded.metadata.settings.grouping_mode.as_deref(),
+            Some("worktree")
+        );
+        assert_eq!(loaded.metadata.sessions["s"].starred, Some(true));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("user-data.json.v1.bak")).unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn test_load_quarantines_corrupt_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("user-data.json");
+        fs::write(&path, "{\"version\": 2, \"sessions\": ").unwrap();
+
+        let loaded = load_metadata_file(&path).unwrap();
+        assert_eq!(loaded.metadata, UserMetadata::new());
+        let quarantined = loaded.quarantined.unwrap();
+        assert!(!path.exists());
+        assert!(quarantined
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("user-data.json.corrupt-"));
+        assert_eq!(
+            fs::read_to_string(quarantined).unwrap(),
+            "{\"version\": 2, \"sessions\": "
+        );
+
+        // Wrong types are as unreadable as broken JSON
+        fs::write(&path, r#"{"version": 2, "sessions": []}"#).unwrap();
+        assert!(load_metadata_file(&path).unwrap().quarantined.is_some());
+
+        let missing = load_metadata_file(&temp_dir.path().join("none.json")).unwrap();
+        assert!(missing.quarantined.is_none() && missing.migrated_from.is_none());
+    }
+}
@@ -0,0 +1,391 @@
+//! Carry user metadata between machines
+//!
+//! [`export_user_metadata`] produces a portable copy of `user-data.json`:
+//! paths inside the home folder are written as `~/...`, so a machine with a
+//! different user name maps them onto its own home. [`import_user_metadata`]
+//! merges such a copy into the local metadata (see
+//! [`UserMetadata::merge_import`]) and re-keys sessions whose file lives in a
+//! different project folder here. Session files are never read or written.
+
+use super::metadata::{current_metadata, update_metadata, MetadataState};
+use super::metadata_gc::{load_inventory, Inventory};
+use super::metadata_migration::migrate;
+use crate::models::{MetadataImportReport, SessionMetadata, UserMetadata};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Identifies an export file
+const EXPORT_FORMAT: &str = "claude-history-viewer/user-metadata";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetadataExport {
+    format: String,
+    exported_at: String,
+    metadata: Value,
+}
+
+fn home_dir() -> Result<PathBuf, String> {
+    dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())
+}
+
+/// `~/...` for paths inside `home`, with `/` separators
+fn to_portable(path: &str, home: &Path) -> String {
+    match Path::new(path).strip_prefix(home) {
+        Ok(rest) if !rest.as_os_str().is_empty() => {
+            let parts: Vec<String> = rest
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            format!("~/{}", parts.join("/"))
+        }
+        _ => path.to_string(),
+    }
+}
+
+fn from_portable(path: &str, home: &Path) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => rest
+            .split('/')
+            .fold(home.to_path_buf(), |path, part| path.join(part))
+            .to_string_lossy()
+            .to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Rewrite session keys; sessions landing on the same key merge
+fn map_session_keys(metadata: &mut UserMetadata, map: impl Fn(&str) -> String) {
+    let mut sessions: HashMap<String, SessionMetadata> = HashMap::new();
+    for (key, session) in std::mem::take(&mut metadata.sessions) {
+        sessions
+            .entry(map(&key))
+            .or_default()
+            .merge_missing(session);
+    }
+    metadata.sessions = sessions;
+}
+
+/// Rewrite session keys and project paths
+fn map_paths(metadata: &mut UserMetadata, map: impl Fn(&str) -> String) {
+    map_session_keys(metadata, &map);
+
+    let projects = std::mem::take(&mut metadata.projects);
+    metadata.projects = projects
+        .into_iter()
+        .map(|(path, mut project)| {
+            project.parent_project = project.parent_project.as_deref().map(&map);
+            (map(&path), project)
+        })
+        .collect();
+}
+
+fn export_metadata(metadata: &UserMetadata, home: &Path, now: &str) -> Result<String, String> {
+    let mut portable = metadata.clone();
+    map_paths(&mut portable, |path| to_portable(path, home));
+    let export = MetadataExport {
+        format: EXPORT_FORMAT.to_string(),
+        exported_at: now.to_string(),
+        metadata: serde_json::to_value(&portable)
+            .map_err(|e| format!("Failed to serialize metadata: {e}"))?,
+    };
+    serde_json::to_string_pretty(&export).map_err(|e| format!("Failed to serialize metadata: {e}"))
+}
+
+/// Parse an export (or a plain `user-data.json`) into local paths and keys
+fn parse_import(content: &str, home: &Path, inventory: &Inventory) -> Result<UserMetadata, String> {
+    let value: Value =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse metadata: {e}"))?;
+    let mut value = match value.get("format").and_then(Value::as_str) {
+        Some(EXPORT_FORMAT) => {
+            serde_json::from_value::<MetadataExport>(value)
+                .map_err(|e| format!("Failed to parse metadata export: {e}"))?
+                .metadata
+        }
+        Some(other) => return Err(format!("Unsupported metadata export format: {other}")),
+        None => value,
+    };
+    migrate(&mut value)?;
+    let mut metadata: UserMetadata =
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse metadata: {e}"))?;
+    map_paths(&mut metadata, |path| from_portable(path, home));
+    map_session_keys(&mut metadata, |key| inventory.resolve_session_key(key));
+    Ok(metadata)
+}
+
+/// Portable copy of the user metadata, to be written to a file by the caller
+#[tauri::command]
+pub async fn export_user_metadata(state: State<'_, MetadataState>) -> Result<String, String> {
+    let metadata = current_metadata(&state).await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        export_metadata(&metadata, &home_dir()?, &Utc::now().to_rfc3339())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Merge exported metadata from another machine into the local metadata
+///
+/// With `dry_run` nothing is saved, so the report can be reviewed first.
+#[tauri::command]
+pub async fn import_user_metadata(
+    claude_path: String,
+    content: String,
+    dry_run: Option<bool>,
+    state: State<'_, MetadataState>,
+) -> Result<MetadataImportReport, String> {
+    // Before the first session there is nothing to re-key
+    let inventory = if Path::new(&claude_path).join("projects").exists() {
+        load_inventory(claude_path).await?
+    } else {
+        Inventory::default()
+    };
+    let incoming = parse_import(&content, &home_dir()?, &inventory)?;
+
+    if dry_run.unwrap_or(false) {
+        let mut metadata = current_metadata(&state).await?;
+        return tauri::async_runtime::spawn_blocking(move || Ok(metadata.merge_import(&incoming)))
+            .await
+            .map_err(|e| format!("Task join error: {e}"))?;
+    }
+
+    let mut report = MetadataImportReport::default();
+    update_metadata(&state, |metadata| {
+        report = metadata.merge_import(&incoming);
+    })
+    .await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::metadata_gc::scan_inventory;
+    use crate::models::MessageAnnotation;
+    use std::collections::HashSet;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn session(name: Option<&str>, name_time: Option<&str>, tags: &[&str]) -> SessionMetadata {
+        let mut session = SessionMetadata {
+            custom_name: name.map(str::to_string),
+            tags: tags.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        };
+        if let Some(time) = name_time {
+            session
+                .updated
+                .insert("customName".to_string(), time.to_string());
+        }
+        session
+    }
+
+    #[test]
+    fn test_merge_import_last_writer_wins_per_field() {
+        let mut local = UserMetadata::new();
+        local.sessions.insert(
+            "a".to_string(),
+            session(Some("Local"), Some("2025-01-02T00:00:00Z"), &["work"]),
+        );
+        local.sessions.insert(
+            "b".to_string(),
+            session(Some("Local b"), Some("2025-01-05T00:00:00Z"), &[]),
+        );
+        local.get_session_mut("c").starred = Some(true);
+
+        let mut incoming = UserMetadata::new();
+        incoming.sessions.insert(
+            "a".to_string(),
+            session(
+                Some("Remote"),
+                Some("2025-01-03T00:00:00Z"),
+                &["work", "home"],
+            ),
+        );
+        incoming.sessions.insert(
+            "b".to_string(),
+            session(Some("Remote b"), Some("2025-01-04T00:00:00Z"), &[]),
+        );
+        incoming
+            .sessions
+            .insert("c".to_string(), session(Some("Named elsewhere"), None, &[]));
+        incoming
+            .sessions
+            .insert("d".to_string(), session(Some("New"), None, &[]));
+        incoming.get_session_mut("a").annotations = vec![MessageAnnotation {
+            id: "n1".to_string(),
+            message_uuid: "m1".to_string(),
+            bookmarked: true,
+            ..Default::default()
+        }];
+
+        let report = local.merge_import(&incoming);
+
+        let a = &local.sessions["a"];
+        assert_eq!(a.custom_name.as_deref(), Some("Remote"));
+        assert_eq!(a.updated["customName"], "2025-01-03T00:00:00Z");
+        assert_eq!(a.tags, ["work", "home"]);
+        assert_eq!(a.annotations.len(), 1);
+        assert_eq!(local.sessions["b"].custom_name.as_deref(), Some("Local b"));
+        let c = &local.sessions["c"];
+        assert_eq!(c.custom_name.as_deref(), Some("Named elsewhere"));
+        assert_eq!(c.starred, Some(true));
+        assert_eq!(local.sessions["d"].custom_name.as_deref(), Some("New"));
+
+        assert_eq!(report.sessions_added, 1);
+        assert_eq!(report.sessions_updated, 2);
+        let conflicts: Vec<(&str, bool)> = report
+            .conflicts
+            .iter()
+            .map(|c| (c.key.as_str(), c.incoming_won))
+            .collect();
+        assert_eq!(conflicts, [("a", true), ("b", false)]);
+    }
+
+    #[test]
+    fn test_merge_import_inserts_rules_in_place() {
+        let rules = |list: &[&str]| list.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let mut local = UserMetadata::new();
+        local.settings.hidden_patterns = rules(&["*tmp*", "*scratch*"]);
+        let mut incoming = UserMetadata::new();
+        incoming.settings.hidden_patterns = rules(&["*tmp*", "!*tmp-keep*", "*scratch*"]);
+
+        let report = local.merge_import(&incoming);
+        assert_eq!(
+            local.settings.hidden_patterns,
+            ["*tmp*", "!*tmp-keep*", "*scratch*"]
+        );
+        assert!(report.settings_updated);
+        assert!(report.conflicts.is_empty());
+
+        // A reorder elsewhere is reported, keeping the local order
+        incoming.settings.hidden_patterns = rules(&["!*tmp-keep*", "*tmp*", "*scratch*"]);
+        let report = local.merge_import(&incoming);
+        assert_eq!(
+            local.settings.hidden_patterns,
+            ["*tmp*", "!*tmp-keep*", "*scratch*"]
+        );
+        assert!(!report.settings_updated);
+        let conflict = &report.conflicts[0];
+        assert_eq!(
+            (conflict.scope.as_str(), conflict.field.as_str()),
+            ("settings", "hiddenPatterns")
+        );
+        assert!(!conflict.incoming_won);
+    }
+
+    #[test]
+    fn test_merge_import_keeps_local_clears() {
+        let mut base = UserMetadata::new();
+        base.sessions.insert(
+            "a".to_string(),
+            session(Some("Old"), Some("2025-01-01T00:00:00Z"), &[]),
+        );
+        base.get_project_mut("/Users/me/app").hidden = Some(true);
+        let exported = base.clone();
+
+        // Cleared here afterwards: the entries are removed, as the commands do
+        let mut local = base.clone();
+        local.sessions.remove("a");
+        local.projects.remove("/Users/me/app");
+        local.stamp_changes(&base, "2025-02-01T00:00:00Z");
+        assert!(!local.sessions["a"].is_empty());
+        assert_eq!(
+            local.sessions["a"].updated["customName"],
+            "2025-02-01T00:00:00Z"
+        );
+        assert_eq!(
+            local.projects["/Users/me/app"].updated["hidden"],
+            "2025-02-01T00:00:00Z"
+        );
+
+        let report = local.merge_import(&exported);
+        assert_eq!(local.sessions["a"].custom_name, None);
+        assert_eq!(local.projects["/Users/me/app"].hidden, None);
+        assert_eq!(report.sessions_added, 0);
+        assert_eq!(report.projects_added, 0);
+
+        // A value set elsewhere after the clear still wins
+        let mut renamed = exported.clone();
+        renamed.sessions.insert(
+            "a".to_string(),
+            session(Some("New"), Some("2025-03-01T00:00:00Z"), &[]),
+        );
+        local.merge_import(&renamed);
+        assert_eq!(local.sessions["a"].custom_name.as_deref(), Some("New"));
+
+        // The cleared project is dropped once its times are old enough
+        let unchanged = local.clone();
+        local.stamp_changes(&unchanged, "2025-03-01T00:00:00Z");
+        assert!(local.projects.contains_key("/Users/me/app"));
+        local.stamp_changes(&unchanged, "2025-09-01T00:00:00Z");
+        assert!(!local.projects.contains_key("/Users/me/app"));
+        assert!(local.sessions.contains_key("a"));
+    }
+
+    #[test]
+    fn test_stamp_changes_keeps_untouched_times() {
+        let mut base = UserMetadata::new();
+        base.sessions.insert(
+            "a".to_string(),
+            session(Some("Name"), Some("2025-01-01T00:00:00Z"), &[]),
+        );
+        let mut edited = base.clone();
+        let entry = edited.get_session_mut("a");
+        entry.updated.clear();
+        entry.starred = Some(true);
+
+        edited.stamp_changes(&base, "2025-02-01T00:00:00Z");
+        let times = &edited.sessions["a"].updated;
+        assert_eq!(times["customName"], "2025-01-01T00:00:00Z");
+        assert_eq!(times["starred"], "2025-02-01T00:00:00Z");
+        assert!(!times.contains_key("notes"));
+    }
+
+    #[test]
+    fn test_export_round_trip_maps_home_and_moved_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects = temp_dir.path().join("projects");
+        let moved = projects.join("-home-bob-code-app").join("s1.jsonl");
+        fs::create_dir_all(moved.parent().unwrap()).unwrap();
+        fs::write(&moved, "{}\n").unwrap();
+
+        let old_home = Path::new("/Users/alice");
+        let mut metadata = UserMetadata::new();
+        metadata
+            .get_session_mut("/Users/alice/.claude/projects/-Users-alice-code-app/s1.jsonl")
+            .starred = Some(true);
+        metadata.get_project_mut("/Users/alice/code/app").alias = Some("App".to_string());
+        metadata
+            .get_project_mut("/Users/alice/code/app-wt")
+            .parent_project = Some("/Users/alice/code/app".to_string());
+        metadata.get_project_mut("/opt/shared").hidden = Some(true);
+
+        let exported = export_metadata(&metadata, old_home, "2025-01-01T00:00:00Z").unwrap();
+        assert!(exported.contains("\"~/code/app\""));
+        assert!(!exported.contains("/Users/alice"));
+
+        let new_home = Path::new("/home/bob");
+        let inventory = scan_inventory(&projects, HashSet::new()).unwrap();
+        let imported = parse_import(&exported, new_home, &inventory).unwrap();
+        let moved_key = moved.to_string_lossy().to_string();
+        assert_eq!(imported.sessions[&moved_key].starred, Some(true));
+        assert_eq!(
+            imported.projects["/home/bob/code/app"].alias.as_deref(),
+            Some("App")
+        );
+        assert_eq!(
+            imported.projects["/home/bob/code/app-wt"]
+                .parent_project
+                .as_deref(),
+            Some("/home/bob/code/app")
+        );
+        assert!(imported.projects.contains_key("/opt/shared"));
+
+        assert!(parse_import(r#"{"format": "other"}"#, new_home, &inventory).is_err());
+    }
+}
@@ -1,12 +1,24 @@
+pub mod archive;
+pub mod bookmarks;
 pub mod claude_settings;
+pub mod collections;
 pub mod feedback;
 pub mod fs_utils;
+pub mod journal;
+pub mod live_tail;
 pub mod mcp_presets;
 pub mod metadata;
+pub mod metadata_gc;
+pub mod metadata_migration;
+pub mod metadata_sync;
 pub mod project;
+pub mod redaction;
+pub mod relocate;
+pub mod secret_scan;
 pub mod session;
 pub mod settings;
 pub mod stats;
+pub mod trusted_symlinks;
 pub mod unified_presets;
 pub mod watcher;
 
@@ -1,10 +1,11 @@
+use super::trusted_symlinks::check_claude_folder;
 use crate::models::{ClaudeProject, GitCommit};
 use crate::utils::{
     detect_git_worktree_info, estimate_message_count_from_size, extract_project_name,
 };
 use chrono::{DateTime, Utc};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use walkdir::WalkDir;
 
@@ -113,6 +114,7 @@ pub async fn scan_projects(claude_path: String) -> Result<Vec<ClaudeProject>, St
     if !projects_path.exists() {
         return Ok(vec![]);
     }
+    check_claude_folder(Path::new(&claude_path))?;
 
     let mut projects = Vec::new();
 
@@ -194,6 +194,137 @@ mod path_security_props {
     }
 }
 
+/// Property tests for the glob engine
+mod glob_props {
+    use super::*;
+    use crate::glob::{GlobPattern, GlobRules};
+
+    /// Straightforward backtracking matcher for `*`, `?` and literals
+    fn reference_match(text: &[char], pattern: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) => (0..=text.len())
+                .take_while(|&n| n == 0 || text[n - 1] != '/')
+                .any(|n| reference_match(&text[n..], rest)),
+            Some(('?', rest)) => {
+                matches!(text.first(), Some(&c) if c != '/') && reference_match(&text[1..], rest)
+            }
+            Some((&c, rest)) => text.first() == Some(&c) && reference_match(&text[1..], rest),
+        }
+    }
+
+    fn is_match(text: &str, pattern: &str) -> bool {
+        GlobPattern::new(pattern).unwrap().is_match(text)
+    }
+
+    /// The v2 matcher: whole text, `*` any run and `?` any one character
+    fn legacy_match(text: &[char], pattern: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) => (0..=text.len()).any(|n| legacy_match(&text[n..], rest)),
+            Some(('?', rest)) => !text.is_empty() && legacy_match(&text[1..], rest),
+            Some((&c, rest)) => text.first() == Some(&c) && legacy_match(&text[1..], rest),
+        }
+    }
+
+    proptest! {
+        /// Property: single wildcards agree with a reference matcher
+        #[test]
+        fn prop_wildcards_match_reference(
+            pattern in "[ab/*?]{0,8}",
+            text in "[ab/]{0,10}"
+        ) {
+            prop_assume!(!pattern.contains("**"));
+            let chars = |s: &str| s.chars().collect::<Vec<_>>();
+            prop_assert_eq!(
+                is_match(&text, &pattern),
+                reference_match(&chars(&text), &chars(&pattern))
+            );
+        }
+
+        /// Property: text without glob syntax matches only itself
+        #[test]
+        fn prop_literal_matches_itself(
+            text in "[a-zA-Z0-9 ._+()$^|\\\\/-]{0,30}",
+            other in "[a-z]{0,5}"
+        ) {
+            prop_assert!(is_match(&text, &text));
+            prop_assert_eq!(is_match(&other, &text), other == text);
+        }
+
+        /// Property: `{x,y}` matches exactly what `x` or `y` matches
+        #[test]
+        fn prop_braces_are_alternation(
+            x in "[ab*?]{0,4}",
+            y in "[ab*?]{0,4}",
+            text in "[ab]{0,6}"
+        ) {
+            prop_assert_eq!(
+                is_match(&text, &format!("{{{x},{y}}}")),
+                is_match(&text, &x) || is_match(&text, &y)
+            );
+        }
+
+        /// Property: `**` matches any path under a directory
+        #[test]
+        fn prop_double_star_matches_any_depth(
+            dirs in prop::collection::vec("[a-z]{1,5}", 0..5),
+            name in "[a-z]{1,8}"
+        ) {
+            let path = dirs.iter().chain(std::iter::once(&name)).cloned().collect::<Vec<_>>().join("/");
+            let under_root = format!("root/{path}");
+            let sql_file = format!("{path}.sql");
+            prop_assert!(is_match(&under_root, "root/**"));
+            prop_assert!(is_match(&sql_file, "**/*.sql"));
+        }
+
+        /// Property: a later negation overrides an earlier match
+        #[test]
+        fn prop_negation_overrides(pattern in "[ab*?]{0,6}", text in "[ab]{0,6}") {
+            let excluded = GlobRules::new(&[pattern.clone(), format!("!{pattern}")]);
+            prop_assert!(!excluded.matches_path(&text));
+            let included = GlobRules::new(&[format!("!{pattern}"), pattern.clone()]);
+            prop_assert_eq!(included.matches_path(&text), is_match(&text, &pattern));
+        }
+
+        /// Property: migrated v2 patterns hide the same paths they used to
+        #[test]
+        fn prop_legacy_patterns_keep_meaning(
+            pattern in "[ab/*?!,{}\\[\\]^-]{0,8}",
+            text in "[ab/!,{}\\[\\]^-]{0,10}"
+        ) {
+            // Project paths never end with a separator
+            prop_assume!(!text.ends_with('/'));
+            let chars = |s: &str| s.chars().collect::<Vec<_>>();
+            let rewritten = crate::commands::metadata_migration::rewrite_legacy_glob(&pattern);
+            prop_assert_eq!(
+                GlobRules::new(&[&rewritten]).matches_path(&text),
+                legacy_match(&chars(&text), &chars(&pattern)),
+                "rewritten as {}", rewritten
+            );
+        }
+
+        /// Property: compiling never panics, whatever the pattern
+        #[test]
+        fn prop_glob_compile_never_panics(pattern in ".{0,40}", text in ".{0,40}") {
+            if let Ok(glob) = GlobPattern::new(&pattern) {
+                let _ = glob.matches_path(&text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_many_wildcards_stay_fast() {
+        // Exponential for a backtracking matcher
+        let pattern = format!("{}b", "*a".repeat(50));
+        let text = "a".repeat(10_000);
+        let start = std::time::Instant::now();
+        assert!(!is_match(&text, &pattern));
+        assert!(is_match(&format!("{text}b"), &pattern));
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    }
+}
+
 /// Regression tests for edge cases found by property testing
 mod regression_tests {
     #[allow(unused_imports)]
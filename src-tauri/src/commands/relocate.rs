@@ -0,0 +1,480 @@
+//! Move sessions from one project folder into another
+//!
+//! Claude Code stores history under a folder named after the project path, so
+//! moving or renaming a repository leaves its history behind. [`relocate_project`]
+//! moves the sessions (with their subagent folders) into the folder of the new
+//! path, optionally rewrites the `cwd` recorded in each message, carries the
+//! session cache along and re-keys the user's metadata.
+
+use super::archive::projects_dir;
+use super::journal::journaled_write;
+use super::metadata::{update_metadata, MetadataState};
+use super::session::merge_session_cache;
+use crate::models::ProjectRelocationResult;
+use crate::utils::decode_project_path;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+use walkdir::WalkDir;
+
+const CACHE_FILE: &str = ".session_cache.json";
+const INDEX_FILE: &str = "sessions-index.json";
+
+/// Folder name Claude Code uses for a project path
+pub(super) fn encode_project_path(project_path: &str) -> String {
+    project_path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+fn folder_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Path of the project a session folder belongs to, if it can be pinned down
+///
+/// The decoded folder name is only trusted when it encodes back to the same
+/// name; otherwise the `cwd` of the sessions is searched for one that does
+/// (the project directory itself may no longer exist after a move).
+fn find_project_path(project_dir: &Path) -> Option<String> {
+    let name = folder_name(project_dir);
+    let decoded = decode_project_path(&project_dir.to_string_lossy());
+    if encode_project_path(&decoded) == name {
+        return Some(decoded);
+    }
+
+    for entry in fs::read_dir(project_dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let found = content
+            .lines()
+            .filter(|line| line.contains("\"cwd\""))
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .filter_map(|entry| entry.get("cwd")?.as_str().map(str::to_string))
+            .find(|cwd| encode_project_path(cwd) == name);
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+/// `cwd` with the `from` prefix replaced, if it lies inside `from`
+fn relocated_cwd(cwd: &str, from: &str, to: &str) -> Option<String> {
+    let rest = cwd.strip_prefix(from)?;
+    (rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\'))
+        .then(|| format!("{to}{rest}"))
+}
+
+/// Rewrite `cwd` fields under `from` to `to`, returning the new content and
+/// the number of lines changed
+///
+/// Lines are edited in place where the field can be found verbatim so the
+/// rest of each line stays byte-for-byte identical.
+fn rewrite_cwd_content(content: &str, from: &str, to: &str) -> (String, usize) {
+    let mut output = String::with_capacity(content.len());
+    let mut changed = 0;
+    for raw in content.split_inclusive('\n') {
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let rewritten = if line.contains("\"cwd\"") {
+            serde_json::from_str::<Value>(line)
+                .ok()
+                .and_then(|mut entry| {
+                    let old = entry.get("cwd")?.as_str()?.to_string();
+                    let new = relocated_cwd(&old, from, to)?;
+                    let needle = format!("\"cwd\":{}", serde_json::to_string(&old).ok()?);
+                    if line.matches(&needle).count() == 1 {
+                        let replacement = format!("\"cwd\":{}", serde_json::to_string(&new).ok()?);
+                        return Some(line.replacen(&needle, &replacement, 1));
+                    }
+                    entry["cwd"] = Value::from(new);
+                    serde_json::to_string(&entry).ok()
+                })
+        } else {
+            None
+        };
+
+        match rewritten {
+            Some(new_line) => {
+                output.push_str(&new_line);
+                output.push_str(&raw[line.len()..]);
+                changed += 1;
+            }
+            None => output.push_str(raw),
+        }
+    }
+    (output, changed)
+}
+
+/// Session and subagent transcripts below a moved entry
+fn transcripts_in(path: &Path) -> Vec<PathBuf> {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .map(walkdir::DirEntry::into_path)
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("jsonl"))
+        .collect()
+}
+
+fn is_transcript(name: &str) -> bool {
+    Path::new(name).extension().and_then(|e| e.to_str()) == Some("jsonl")
+}
+
+fn same_content(a: &Path, b: &Path) -> bool {
+    match (fs::read(a), fs::read(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Carry Claude Code's session index over, pointing it at the new folder
+///
+/// When the target already has an index the source one is dropped; Claude
+/// Code rebuilds missing entries from the session files.
+fn move_session_index(source: &Path, target: &Path, new_project_path: &str) -> Result<(), String> {
+    let source_index = source.join(INDEX_FILE);
+    if !source_index.exists() {
+        return Ok(());
+    }
+    let target_index = target.join(INDEX_FILE);
+    if !target_index.exists() {
+        let content = fs::read_to_string(&source_index)
+            .map_err(|e| format!("Failed to read session index: {e}"))?;
+        if let Ok(mut index) = serde_json::from_str::<Value>(&content) {
+            if index.get("originalPath").is_some() {
+                index["originalPath"] = Value::from(new_project_path);
+            }
+            let source_prefix = source.to_string_lossy().to_string();
+            let target_prefix = target.to_string_lossy().to_string();
+            if let Some(entries) = index.get_mut("entries").and_then(Value::as_array_mut) {
+                for entry in entries {
+                    if let Some(Value::String(full_path)) = entry.get_mut("fullPath") {
+                        if let Some(rest) = full_path.strip_prefix(&source_prefix) {
+                            *full_path = format!("{target_prefix}{rest}");
+                        }
+                    }
+                }
+            }
+            let json = serde_json::to_string_pretty(&index)
+                .map_err(|e| format!("Failed to serialize session index: {e}"))?;
+            fs::write(&target_index, json)
+                .map_err(|e| format!("Failed to write session index: {e}"))?;
+        }
+    }
+    fs::remove_file(&source_index).map_err(|e| format!("Failed to remove session index: {e}"))
+}
+
+/// Outcome of moving the files, before metadata is updated
+#[derive(Debug)]
+struct RelocatedFiles {
+    result: ProjectRelocationResult,
+    /// Old session file path -> new session file path
+    session_paths: HashMap<String, String>,
+    old_project_path: String,
+}
+
+fn relocate_project_files(
+    source_project_path: &str,
+    new_project_path: &str,
+    rewrite_cwd: bool,
+) -> Result<RelocatedFiles, String> {
+    if !Path::new(new_project_path).is_absolute() {
+        return Err(format!(
+            "Project path must be absolute, got relative path: {new_project_path}"
+        ));
+    }
+    let projects = projects_dir()?;
+    let source = Path::new(source_project_path)
+        .canonicalize()
+        .map_err(|e| format!("Project folder not found: {e}"))?;
+    if source.parent() != Some(projects.as_path()) || !source.is_dir() {
+        return Err(format!(
+            "Not a project folder in ~/.claude/projects: {source_project_path}"
+        ));
+    }
+    let target = projects.join(encode_project_path(new_project_path));
+    if target == source {
+        return Err("The project is already stored under that path".to_string());
+    }
+
+    let old_project_path = find_project_path(&source);
+    if rewrite_cwd && old_project_path.is_none() {
+        return Err(format!(
+            "Could not determine the original project path of {}",
+            folder_name(&source)
+        ));
+    }
+    let old_project_path =
+        old_project_path.unwrap_or_else(|| decode_project_path(&source.to_string_lossy()));
+
+    // Check every name before touching anything so a conflict moves nothing
+    let merged = target.exists();
+    let mut to_move = Vec::new();
+    let mut duplicates = Vec::new();
+    let mut conflicts = Vec::new();
+    for entry in fs::read_dir(&source).map_err(|e| format!("Failed to read project folder: {e}"))? {
+        let entry = entry.map_err(|e| format!("Failed to read project folder: {e}"))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == CACHE_FILE || name == INDEX_FILE {
+            continue;
+        }
+        let destination = target.join(&name);
+        if !destination.exists() {
+            to_move.push(name);
+        } else if entry.path().is_file() && same_content(&entry.path(), &destination) {
+            duplicates.push(name);
+        } else {
+            conflicts.push(name);
+        }
+    }
+    if !conflicts.is_empty() {
+        conflicts.sort();
+        return Err(format!(
+            "Already present in the target project folder: {}",
+            conflicts.join(", ")
+        ));
+    }
+
+    fs::create_dir_all(&target).map_err(|e| format!("Failed to create project folder: {e}"))?;
+
+    let mut session_paths = HashMap::new();
+    let mut moved_sessions = 0;
+    let mut moved_paths = Vec::new();
+    for (moved, name) in to_move.iter().enumerate() {
+        let from = source.join(name);
+        let to = target.join(name);
+        if let Err(e) = fs::rename(&from, &to) {
+            // Put back what already moved so the metadata still matches the files
+            let stranded: Vec<&str> = to_move[..moved]
+                .iter()
+                .filter(|name| fs::rename(target.join(name), source.join(name)).is_err())
+                .map(String::as_str)
+                .collect();
+            if !merged {
+                let _ = fs::remove_dir(&target);
+            }
+            return Err(if stranded.is_empty() {
+                format!("Failed to move {name}: {e}")
+            } else {
+                format!(
+                    "Failed to move {name}: {e}; could not move back {}",
+                    stranded.join(", ")
+                )
+            });
+        }
+        if is_transcript(name) {
+            moved_sessions += 1;
+            session_paths.insert(
+                from.to_string_lossy().to_string(),
+                to.to_string_lossy().to_string(),
+            );
+        }
+        moved_paths.push(to);
+    }
+    for name in &duplicates {
+        let from = source.join(name);
+        fs::remove_file(&from).map_err(|e| format!("Failed to remove duplicate {name}: {e}"))?;
+        if is_transcript(name) {
+            session_paths.insert(
+                from.to_string_lossy().to_string(),
+                target.join(name).to_string_lossy().to_string(),
+            );
+        }
+    }
+
+    let mut rewritten_files = 0;
+    let mut rewritten_lines = 0;
+    if rewrite_cwd {
+        let description = format!("Relocate to {new_project_path}");
+        for file in moved_paths.iter().flat_map(|p| transcripts_in(p)) {
+            let content = fs::read_to_string(&file)
+                .map_err(|e| format!("Failed to read session file: {e}"))?;
+            if rewrite_cwd_content(&content, &old_project_path, new_project_path).1 == 0 {
+                continue;
+            }
+            let mut lines = 0;
+            journaled_write(&file, "relocate", &description, |content| {
+                let (output, changed) =
+                    rewrite_cwd_content(content, &old_project_path, new_project_path);
+                lines = changed;
+                Ok(output)
+            })?;
+            rewritten_files += 1;
+            rewritten_lines += lines;
+        }
+    }
+
+    let source_str = source.to_string_lossy().to_string();
+    let target_str = target.to_string_lossy().to_string();
+    merge_session_cache(&source_str, &target_str, &session_paths);
+    move_session_index(&source, &target, new_project_path)?;
+    let source_removed = fs::remove_dir(&source).is_ok();
+
+    Ok(RelocatedFiles {
+        result: ProjectRelocationResult {
+            source_project_path: source_str,
+            target_project_path: target_str,
+            merged,
+            moved_sessions,
+            duplicate_sessions: duplicates.iter().filter(|n| is_transcript(n)).count(),
+            rewritten_files,
+            rewritten_lines,
+            source_removed,
+        },
+        session_paths,
+        old_project_path,
+    })
+}
+
+/// Move a project's sessions into the folder for `new_project_path`
+///
+/// If that folder already exists the sessions are merged into it; a name
+/// present in both folders with different content aborts before anything is
+/// moved. With `rewrite_cwd`, `cwd` fields inside the old project path are
+/// rewritten to the new one (journaled, so each file can be reverted).
+/// Session and project metadata follow the move.
+#[tauri::command]
+pub async fn relocate_project(
+    source_project_path: String,
+    new_project_path: String,
+    rewrite_cwd: bool,
+    state: State<'_, MetadataState>,
+) -> Result<ProjectRelocationResult, String> {
+    let target_path = new_project_path.clone();
+    let relocated = tauri::async_runtime::spawn_blocking(move || {
+        relocate_project_files(&source_project_path, &new_project_path, rewrite_cwd)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))??;
+
+    let RelocatedFiles {
+        result,
+        session_paths,
+        old_project_path,
+    } = relocated;
+    update_metadata(&state, |metadata| {
+        metadata.rename_session_keys(&session_paths);
+        metadata.rename_project(&old_project_path, &target_path);
+    })
+    .await?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::env;
+    use tempfile::TempDir;
+
+    fn session(cwd: &str, session_id: &str) -> String {
+        [
+            json!({"type": "user", "uuid": "u1", "parentUuid": null, "sessionId": session_id,
+                   "cwd": cwd, "message": {"role": "user", "content": "hello"}}),
+            json!({"type": "assistant", "uuid": "a1", "parentUuid": "u1", "sessionId": session_id,
+                   "cwd": format!("{cwd}/src"),
+                   "message": {"role": "assistant", "content": [{"type": "text", "text": "hi"}]}}),
+            json!({"type": "user", "uuid": "u2", "parentUuid": "a1", "sessionId": session_id,
+                   "cwd": format!("{cwd}-other"), "message": {"role": "user", "content": "bye"}}),
+        ]
+        .iter()
+        .fold(String::new(), |mut content, entry| {
+            content.push_str(&entry.to_string());
+            content.push('\n');
+            content
+        })
+    }
+
+    fn setup() -> (TempDir, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let home = temp_dir.path().canonicalize().unwrap();
+        env::set_var("HOME", &home);
+        let projects = home.join(".claude/projects");
+        fs::create_dir_all(&projects).unwrap();
+        (temp_dir, projects)
+    }
+
+    #[test]
+    fn test_rewrite_cwd_content_keeps_other_paths() {
+        let content = session("/old/app", "s1");
+        let (output, changed) = rewrite_cwd_content(&content, "/old/app", "/new/app");
+        assert_eq!(changed, 2);
+        assert!(output.contains(r#""cwd":"/new/app""#));
+        assert!(output.contains(r#""cwd":"/new/app/src""#));
+        assert!(output.contains(r#""cwd":"/old/app-other""#));
+        assert_eq!(output.len(), content.len());
+        assert!(output.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_relocate_moves_sessions_and_rewrites_cwd() {
+        let (_temp, projects) = setup();
+        let source = projects.join("-old-app");
+        fs::create_dir_all(source.join("s1/subagents")).unwrap();
+        fs::write(source.join("s1.jsonl"), session("/old/app", "s1")).unwrap();
+        fs::write(
+            source.join("s1/subagents/agent-a1.jsonl"),
+            session("/old/app", "s1"),
+        )
+        .unwrap();
+
+        let relocated =
+            relocate_project_files(&source.to_string_lossy(), "/new/app", true).unwrap();
+        let result = relocated.result;
+        let target = projects.join("-new-app");
+        assert!(!result.merged);
+        assert!(result.source_removed);
+        assert_eq!(result.moved_sessions, 1);
+        assert_eq!(result.rewritten_files, 2);
+        assert_eq!(result.rewritten_lines, 4);
+        assert_eq!(relocated.old_project_path, "/old/app");
+        assert!(!source.exists());
+
+        let moved = fs::read_to_string(target.join("s1.jsonl")).unwrap();
+        assert!(moved.contains(r#""cwd":"/new/app/src""#));
+        assert_eq!(
+            relocated.session_paths[&source.join("s1.jsonl").to_string_lossy().to_string()],
+            target.join("s1.jsonl").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_relocate_merges_and_refuses_conflicts() {
+        let (_temp, projects) = setup();
+        let source = projects.join("-old-app");
+        let target = projects.join("-new-app");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&target).unwrap();
+        fs::write(source.join("s1.jsonl"), session("/old/app", "s1")).unwrap();
+        fs::write(source.join("s2.jsonl"), session("/old/app", "s2")).unwrap();
+        fs::write(target.join("s2.jsonl"), session("/new/app", "s2")).unwrap();
+
+        let err = relocate_project_files(&source.to_string_lossy(), "/new/app", false).unwrap_err();
+        assert!(err.contains("s2.jsonl"));
+        assert!(source.join("s1.jsonl").exists());
+
+        // Identical copies are treated as already moved
+        fs::write(target.join("s2.jsonl"), session("/old/app", "s2")).unwrap();
+        let result = relocate_project_files(&source.to_string_lossy(), "/new/app", false)
+            .unwrap()
+            .result;
+        assert!(result.merged);
+        assert_eq!(result.moved_sessions, 1);
+        assert_eq!(result.duplicate_sessions, 1);
+        assert_eq!(result.rewritten_files, 0);
+        assert!(target.join("s1.jsonl").exists());
+        assert!(!source.exists());
+    }
+}
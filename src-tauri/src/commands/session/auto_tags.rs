@@ -0,0 +1,384 @@
+//! Tags derived from session content by user-defined rules
+//!
+//! Rules live in `UserSettings.auto_tag_rules`. The tags they produce are set
+//! on `ClaudeSession.auto_tags` when sessions are loaded and never written to
+//! the user's own `SessionMetadata.tags`, so editing or removing a rule simply
+//! changes what is derived. Results are cached per file together with the byte
+//! offset they cover; lines appended later are evaluated on top of them.
+
+use super::file_activity::{tool_file_accesses, PathMatcher};
+use super::shell_ops::command_programs;
+use crate::models::{AutoTagCondition, AutoTagRule, RawLogEntry, TokenUsage};
+use crate::utils::find_line_ranges;
+use memchr::memmem;
+use memmap2::Mmap;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+/// Tools that count as touching a file when a rule doesn't name one
+const WRITE_TOOLS: [&str; 4] = ["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Per-million-token prices (input, output, cache write, cache read)
+///
+/// Mirrors `MODEL_PRICING` in the analytics dashboard; first match wins.
+const MODEL_PRICING: [(&str, [f64; 4]); 7] = [
+    ("claude-opus-4-5", [5.0, 25.0, 6.25, 0.50]),
+    ("claude-opus-4", [15.0, 75.0, 18.75, 1.50]),
+    ("claude-sonnet-4-5", [3.0, 15.0, 3.75, 0.30]),
+    ("claude-sonnet-4", [3.0, 15.0, 3.75, 0.30]),
+    ("claude-3-5-sonnet", [3.0, 15.0, 3.75, 0.30]),
+    ("claude-3-5-haiku", [1.0, 5.0, 1.25, 0.10]),
+    ("claude-3-haiku", [0.25, 1.25, 0.30, 0.03]),
+];
+const DEFAULT_PRICING: [f64; 4] = [3.0, 15.0, 3.75, 0.30];
+
+/// Cached evaluation of one session file
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+pub(super) struct AutoTagState {
+    /// [`rules_key`] of the rules this was evaluated with
+    pub rules_key: u64,
+    /// Bytes of the file evaluated so far (always ends after a newline)
+    pub byte_offset: u64,
+    pub tags: Vec<String>,
+    /// Running cost estimate in USD
+    pub cost_usd: f64,
+    /// Last assistant message counted; streamed messages repeat their usage on every line
+    pub last_message_id: Option<String>,
+}
+
+/// Fingerprint of a rule set, so cached results are dropped when rules change
+pub(super) fn rules_key(rules: &[AutoTagRule]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(rules)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Estimated cost of one assistant message's token usage
+fn estimate_cost(model: &str, usage: &TokenUsage) -> f64 {
+    let model = model.to_lowercase();
+    let prices = MODEL_PRICING
+        .iter()
+        .find(|(name, _)| model.contains(name))
+        .map_or(DEFAULT_PRICING, |(_, prices)| *prices);
+    let tokens = [
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_creation_input_tokens,
+        usage.cache_read_input_tokens,
+    ];
+    tokens
+        .iter()
+        .zip(prices)
+        .map(|(tokens, price)| f64::from(tokens.unwrap_or(0)) / 1_000_000.0 * price)
+        .sum()
+}
+
+/// A rule still to be matched, with its file pattern compiled
+struct PendingRule<'a> {
+    rule: &'a AutoTagRule,
+    matcher: Option<PathMatcher>,
+}
+
+/// Whether one log entry satisfies a rule (cost is checked separately)
+fn entry_matches(pending: &PendingRule, entry: &RawLogEntry) -> bool {
+    let Some(message) = &entry.message else {
+        return false;
+    };
+    match &pending.rule.condition {
+        AutoTagCondition::Model { contains } => {
+            !contains.is_empty()
+                && message
+                    .model
+                    .as_deref()
+                    .is_some_and(|model| model.to_lowercase().contains(&contains.to_lowercase()))
+        }
+        AutoTagCondition::FileTouched { tool, .. } => {
+            let Some(matcher) = &pending.matcher else {
+                return false;
+            };
+            let cwd = entry.cwd.as_deref();
+            tool_uses(&message.content).any(|(name, input)| {
+                let counts = match tool {
+                    Some(tool) => name == tool,
+                    None => WRITE_TOOLS.contains(&name),
+                };
+                counts
+                    && tool_file_accesses(name, input, cwd)
+                        .iter()
+                        .any(|(_, path)| matcher.matches(path, cwd))
+            })
+        }
+        AutoTagCondition::BashCommand { program } => tool_uses(&message.content)
+            .filter(|(name, _)| *name == "Bash")
+            .filter_map(|(_, input)| input.get("command").and_then(Value::as_str))
+            .any(|command| command_programs(command).iter().any(|p| p == program)),
+        AutoTagCondition::CostAbove { .. } => false,
+    }
+}
+
+/// `(name, input)` of each `tool_use` block in message content
+fn tool_uses(content: &Value) -> impl Iterator<Item = (&str, &Value)> {
+    content
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|item| item.get("type").and_then(Value::as_str) == Some("tool_use"))
+        .filter_map(|item| {
+            let name = item.get("name").and_then(Value::as_str)?;
+            Some((name, item.get("input").unwrap_or(&Value::Null)))
+        })
+}
+
+/// Evaluate rules against a session file, continuing from `previous` when it
+/// was computed with the same rules
+///
+/// A trailing line without a newline may still be being written and is left
+/// for the next evaluation.
+#[allow(unsafe_code)] // Required for mmap performance optimization
+pub(super) fn evaluate_auto_tags(
+    file_path: &Path,
+    rules: &[AutoTagRule],
+    previous: Option<&AutoTagState>,
+) -> AutoTagState {
+    let key = rules_key(rules);
+    let mut state = match previous {
+        Some(previous) if previous.rules_key == key => previous.clone(),
+        _ => AutoTagState {
+            rules_key: key,
+            ..AutoTagState::default()
+        },
+    };
+
+    let Ok(file) = fs::File::open(file_path) else {
+        return state;
+    };
+    // SAFETY: We're only reading the file, and the file handle is kept open
+    // for the duration of the mmap's lifetime. Session files are append-only.
+    let Ok(mmap) = (unsafe { Mmap::map(&file) }) else {
+        return state;
+    };
+    if state.byte_offset > mmap.len() as u64 {
+        // Shorter than what was evaluated: the file was rewritten
+        state = AutoTagState {
+            rules_key: key,
+            ..AutoTagState::default()
+        };
+    }
+
+    let start = usize::try_from(state.byte_offset).unwrap_or(mmap.len());
+    let end = memchr::memrchr(b'\n', &mmap[start..]).map_or(start, |pos| start + pos + 1);
+    state.byte_offset = end as u64;
+
+    let mut pending: Vec<PendingRule> = rules
+        .iter()
+        .filter(|rule| rule.enabled && !rule.tag.trim().is_empty())
+        .filter(|rule| !state.tags.contains(&rule.tag))
+        .map(|rule| PendingRule {
+            rule,
+            matcher: match &rule.condition {
+                AutoTagCondition::FileTouched { pattern, .. } => Some(PathMatcher::new(pattern)),
+                _ => None,
+            },
+        })
+        .collect();
+    let assistant = memmem::Finder::new(b"\"assistant\"");
+
+    for (line_start, line_end) in find_line_ranges(&mmap[start..end]) {
+        if pending.is_empty() {
+            break;
+        }
+        let line = &mmap[start + line_start..start + line_end];
+        // Models, tool calls and usage all come from assistant messages
+        if assistant.find(line).is_none() {
+            continue;
+        }
+        // simd-json requires mutable slice
+        let mut line_bytes = line.to_vec();
+        let Ok(entry) = simd_json::serde::from_slice::<RawLogEntry>(&mut line_bytes) else {
+            continue;
+        };
+        if entry.message_type != "assistant" {
+            continue;
+        }
+
+        if let Some(cost) = entry.cost_usd {
+            state.cost_usd += cost;
+        } else if let Some(message) = &entry.message {
+            let repeated = message.id.is_some() && message.id == state.last_message_id;
+            if let (Some(usage), false) = (&message.usage, repeated) {
+                state.cost_usd += estimate_cost(message.model.as_deref().unwrap_or(""), usage);
+            }
+            state.last_message_id.clone_from(&message.id);
+        }
+
+        pending.retain(|pending| {
+            let tag = &pending.rule.tag;
+            let matched = match &pending.rule.condition {
+                AutoTagCondition::CostAbove { usd } => state.cost_usd > *usd,
+                _ => entry_matches(pending, &entry),
+            };
+            if matched && !state.tags.contains(tag) {
+                state.tags.push(tag.clone());
+            }
+            !matched && !state.tags.contains(tag)
+        });
+    }
+
+    state
+}
+
+/// Whether an assistant message in the session used a model whose name
+/// contains `contains` (case-insensitive)
+pub(crate) fn session_uses_model(file_path: &Path, contains: &str) -> bool {
+    let rule = AutoTagRule {
+        tag: "model".to_string(),
+        condition: AutoTagCondition::Model {
+            contains: contains.to_string(),
+        },
+        enabled: true,
+    };
+    !evaluate_auto_tags(file_path, std::slice::from_ref(&rule), None)
+        .tags
+        .is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn rule(tag: &str, condition: AutoTagCondition) -> AutoTagRule {
+        AutoTagRule {
+            tag: tag.to_string(),
+            condition,
+            enabled: true,
+        }
+    }
+
+    fn assistant_line(id: &str, model: &str, tools: &Value, output_tokens: u32) -> String {
+        let mut content = vec![json!({"type": "text", "text": "working on it"})];
+        content.extend(tools.as_array().cloned().unwrap_or_default());
+        let line = json!({
+            "type": "assistant", "uuid": id, "sessionId": "s1", "cwd": "/repo",
+            "timestamp": "2025-01-01T00:00:00Z",
+            "message": {"role": "assistant", "id": id, "model": model, "content": content,
+                        "usage": {"input_tokens": 0, "output_tokens": output_tokens}}
+        });
+        format!("{line}\n")
+    }
+
+    fn rules() -> Vec<AutoTagRule> {
+        vec![
+            rule(
+                "db",
+                AutoTagCondition::FileTouched {
+                    pattern: "migrations/**".to_string(),
+                    tool: None,
+                },
+            ),
+            rule(
+                "opus",
+                AutoTagCondition::Model {
+                    contains: "OPUS".to_string(),
+                },
+            ),
+            rule(
+                "infra",
+                AutoTagCondition::BashCommand {
+                    program: "terraform".to_string(),
+                },
+            ),
+            rule("expensive", AutoTagCondition::CostAbove { usd: 5.0 }),
+        ]
+    }
+
+    #[test]
+    fn test_rules_match_session_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("s1.jsonl");
+        let content = [
+            json!({"type": "user", "uuid": "u1", "sessionId": "s1", "cwd": "/repo",
+                   "message": {"role": "user", "content": "run terraform and edit migrations/001.sql"}})
+            .to_string()
+                + "\n",
+            assistant_line(
+                "a1",
+                "claude-sonnet-4-5",
+                &json!([{"type": "tool_use", "id": "t1", "name": "Read",
+                         "input": {"file_path": "/repo/migrations/001.sql"}},
+                        {"type": "tool_use", "id": "t2", "name": "Bash",
+                         "input": {"command": "cd infra && terraform plan"}}]),
+                100,
+            ),
+            assistant_line(
+                "a2",
+                "claude-sonnet-4-5",
+                &json!([{"type": "tool_use", "id": "t3", "name": "Edit",
+                         "input": {"file_path": "migrations/001.sql", "old_string": "a", "new_string": "b"}}]),
+                100,
+            ),
+        ]
+        .concat();
+        fs::write(&path, &content).unwrap();
+
+        let state = evaluate_auto_tags(&path, &rules(), None);
+        // Reading a migration doesn't count, editing one does; user text never counts
+        assert_eq!(state.tags, vec!["infra", "db"]);
+        assert_eq!(state.byte_offset, content.len() as u64);
+
+        // 400k opus output tokens: $30, streamed over two lines of the same message
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        let opus = assistant_line("a3", "claude-opus-4-1", &json!([]), 400_000);
+        file.write_all(opus.as_bytes()).unwrap();
+        file.write_all(opus.as_bytes()).unwrap();
+        // A partial last line is left for later
+        file.write_all(br#"{"type":"assistant","message":"#)
+            .unwrap();
+
+        let appended = evaluate_auto_tags(&path, &rules(), Some(&state));
+        assert_eq!(appended.tags, vec!["infra", "db", "opus", "expensive"]);
+        assert!((appended.cost_usd - 30.003).abs() < 1e-9);
+        assert_eq!(
+            appended.byte_offset,
+            (content.len() + 2 * opus.len()) as u64
+        );
+
+        // Different rules start over
+        let only_cost = vec![rule("pricey", AutoTagCondition::CostAbove { usd: 100.0 })];
+        let recomputed = evaluate_auto_tags(&path, &only_cost, Some(&appended));
+        assert!(recomputed.tags.is_empty());
+        assert_eq!(recomputed.rules_key, rules_key(&only_cost));
+
+        let mut disabled = rules();
+        for rule in &mut disabled {
+            rule.enabled = false;
+        }
+        assert!(evaluate_auto_tags(&path, &disabled, None).tags.is_empty());
+    }
+
+    #[test]
+    fn test_rule_serialization() {
+        let rules: Vec<AutoTagRule> = serde_json::from_value(json!([
+            {"tag": "db", "condition": {"type": "fileTouched", "pattern": "migrations/**"}},
+            {"tag": "x", "enabled": false, "condition": {"type": "costAbove", "usd": 5}}
+        ]))
+        .unwrap();
+        assert!(rules[0].enabled);
+        assert_eq!(
+            rules[0].condition,
+            AutoTagCondition::FileTouched {
+                pattern: "migrations/**".to_string(),
+                tool: None
+            }
+        );
+        assert_eq!(rules[1].condition, AutoTagCondition::CostAbove { usd: 5.0 });
+        assert!(!rules[1].enabled);
+    }
+}
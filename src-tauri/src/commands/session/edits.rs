@@ -1,23 +1,392 @@
 //! File edit and restore functions
 
-use crate::models::{RawLogEntry, RecentFileEdit};
+use super::file_history::{
+    file_history_dir_for_project, read_backup_content, session_id_from_file, SessionFileHistory,
+};
+use super::shell_ops::analyze_shell_command;
+use crate::models::{FileEditOrigin, RawLogEntry, RecentFileEdit, ShellFileOperation};
 use crate::utils::find_line_ranges;
 use memmap2::Mmap;
 use rayon::prelude::*;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 /// Intermediate result from processing a single session file (for parallel processing)
 struct SessionEditsResult {
     edits: Vec<RecentFileEdit>,
+    shell_operations: Vec<ShellFileOperation>,
     cwd_counts: HashMap<String, usize>,
 }
 
+/// Name and input of a `tool_use` block, keyed by its id
+struct ToolUseInput {
+    name: String,
+    input: serde_json::Value,
+    /// UUID of the assistant message containing the block
+    message_uuid: Option<String>,
+}
+
+/// File change reconstructed from a single tool result
+struct ReconstructedEdit {
+    file_path: String,
+    operation_type: &'static str,
+    content_after_change: String,
+    original_content: Option<String>,
+    lines_added: usize,
+    lines_removed: usize,
+}
+
+/// Collect `tool_use` blocks from assistant message content
+fn collect_tool_use_inputs(
+    content: &Value,
+    message_uuid: Option<&String>,
+    tool_inputs: &mut HashMap<String, ToolUseInput>,
+) {
+    let Some(items) = content.as_array() else {
+        return;
+    };
+    for item in items {
+        if item.get("type").and_then(Value::as_str) != Some("tool_use") {
+            continue;
+        }
+        if let (Some(id), Some(name)) = (
+            item.get("id").and_then(Value::as_str),
+            item.get("name").and_then(Value::as_str),
+        ) {
+            tool_inputs.insert(
+                id.to_string(),
+                ToolUseInput {
+                    name: name.to_string(),
+                    input: item.get("input").cloned().unwrap_or(Value::Null),
+                    message_uuid: message_uuid.cloned(),
+                },
+            );
+        }
+    }
+}
+
+/// Get the `tool_use_id` of the `tool_result` block in a user entry
+fn tool_result_id(log_entry: &RawLogEntry) -> Option<String> {
+    log_entry
+        .message
+        .as_ref()?
+        .content
+        .as_array()?
+        .iter()
+        .find(|item| item.get("type").and_then(Value::as_str) == Some("tool_result"))
+        .and_then(|item| item.get("tool_use_id"))
+        .and_then(Value::as_str)
+        .map(std::string::ToString::to_string)
+}
+
+/// Reconstruct the file change made by a Write, Edit, `MultiEdit` or `NotebookEdit` result
+fn reconstruct_tool_result(
+    result: &Value,
+    tool_use: Option<&ToolUseInput>,
+    tool_use_id: Option<&str>,
+) -> Option<ReconstructedEdit> {
+    let input = tool_use.map(|t| &t.input);
+
+    if tool_use.is_some_and(|t| t.name == "NotebookEdit") || result.get("notebook_path").is_some() {
+        return reconstruct_notebook_edit(result, input, tool_use_id);
+    }
+
+    // Write results: "create" for new files, "update" when overwriting
+    if let Some("create" | "update") = result.get("type").and_then(Value::as_str) {
+        let file_path = result.get("filePath").and_then(Value::as_str)?;
+        let content = result.get("content").and_then(Value::as_str)?;
+        let original = original_file(result);
+        return Some(ReconstructedEdit {
+            file_path: file_path.to_string(),
+            operation_type: "write",
+            content_after_change: content.to_string(),
+            original_content: original.map(std::string::ToString::to_string),
+            lines_added: content.lines().count(),
+            lines_removed: original.map_or(0, |o| o.lines().count()),
+        });
+    }
+
+    let file_path = result.get("filePath").and_then(Value::as_str)?;
+    let original = original_file(result)?;
+
+    if let Some(edits_arr) = result.get("edits").and_then(Value::as_array) {
+        // MultiEdit: edits are applied in order, each on the result of the previous one
+        let mut content = original.to_string();
+        let mut lines_added = 0usize;
+        let mut lines_removed = 0usize;
+
+        for edit in edits_arr {
+            if let (Some(old_str), Some(new_str)) = (
+                edit.get("old_string").and_then(Value::as_str),
+                edit.get("new_string").and_then(Value::as_str),
+            ) {
+                let replace_all = edit
+                    .get("replace_all")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                let (updated, replacements) =
+                    apply_string_edit(&content, old_str, new_str, replace_all);
+                content = updated;
+                lines_removed += old_str.lines().count() * replacements;
+                lines_added += new_str.lines().count() * replacements;
+            }
+        }
+
+        return Some(ReconstructedEdit {
+            file_path: file_path.to_string(),
+            operation_type: "edit",
+            content_after_change: content,
+            original_content: Some(original.to_string()),
+            lines_added,
+            lines_removed,
+        });
+    }
+
+    // Single Edit
+    let old_str = result.get("oldString").and_then(Value::as_str)?;
+    let new_str = result.get("newString").and_then(Value::as_str)?;
+    let replace_all = result
+        .get("replaceAll")
+        .or_else(|| input.and_then(|i| i.get("replace_all")))
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let (content, replacements) = apply_string_edit(original, old_str, new_str, replace_all);
+
+    Some(ReconstructedEdit {
+        file_path: file_path.to_string(),
+        operation_type: "edit",
+        content_after_change: content,
+        original_content: Some(original.to_string()),
+        lines_added: new_str.lines().count() * replacements,
+        lines_removed: old_str.lines().count() * replacements,
+    })
+}
+
+/// Original file content of an Edit/Write result (`MultiEdit` uses `originalFileContents`)
+fn original_file(result: &Value) -> Option<&str> {
+    result
+        .get("originalFile")
+        .or_else(|| result.get("originalFileContents"))
+        .and_then(Value::as_str)
+}
+
+/// Apply a string replacement the way the Edit tool does.
+/// Returns the new content and the number of replacements counted for line stats
+/// (at least 1, since a successful tool result means the edit was applied).
+fn apply_string_edit(content: &str, old: &str, new: &str, replace_all: bool) -> (String, usize) {
+    if replace_all && !old.is_empty() {
+        let occurrences = content.matches(old).count();
+        (content.replace(old, new), occurrences.max(1))
+    } else {
+        (content.replacen(old, new, 1), 1)
+    }
+}
+
+/// Reconstruct a `NotebookEdit` cell operation on `.ipynb` JSON
+///
+/// Uses `updated_file` from the result when Claude Code recorded it, otherwise
+/// replays the insert/replace/delete operation on `original_file`.
+fn reconstruct_notebook_edit(
+    result: &Value,
+    input: Option<&Value>,
+    tool_use_id: Option<&str>,
+) -> Option<ReconstructedEdit> {
+    // Failed edits carry an error message and leave the notebook untouched
+    if result
+        .get("error")
+        .and_then(Value::as_str)
+        .is_some_and(|e| !e.is_empty())
+    {
+        return None;
+    }
+
+    let field = |key: &str| {
+        result
+            .get(key)
+            .or_else(|| input.and_then(|i| i.get(key)))
+            .and_then(Value::as_str)
+    };
+
+    let file_path = field("notebook_path")?;
+    let new_source = field("new_source").unwrap_or_default();
+    let edit_mode = field("edit_mode").unwrap_or("replace");
+    // The input names the cell to insert after; the result may name the new one
+    let cell_id = input
+        .and_then(|i| i.get("cell_id"))
+        .and_then(Value::as_str)
+        .or_else(|| result.get("cell_id").and_then(Value::as_str));
+    let cell_type = field("cell_type");
+    let new_cell_id = result
+        .get("cell_id")
+        .and_then(Value::as_str)
+        .filter(|&id| Some(id) != cell_id)
+        .map_or_else(
+            || {
+                let seed = tool_use_id.map_or_else(
+                    || format!("{}\n{new_source}", cell_id.unwrap_or_default()),
+                    str::to_string,
+                );
+                inserted_cell_id(&seed)
+            },
+            str::to_string,
+        );
+
+    let original = result.get("original_file").and_then(Value::as_str);
+    let replayed = original.and_then(|o| {
+        apply_notebook_edit(o, edit_mode, cell_id, cell_type, new_source, &new_cell_id)
+    });
+
+    let (content_after_change, old_source) =
+        match (result.get("updated_file").and_then(Value::as_str), replayed) {
+            (Some(updated), replayed) => (
+                updated.to_string(),
+                replayed.map(|(_, old)| old).unwrap_or_default(),
+            ),
+            (None, Some(replayed)) => replayed,
+            (None, None) => return None,
+        };
+
+    let lines_added = if edit_mode == "delete" {
+        0
+    } else {
+        new_source.lines().count()
+    };
+
+    Some(ReconstructedEdit {
+        file_path: file_path.to_string(),
+        operation_type: "edit",
+        content_after_change,
+        original_content: original.map(std::string::ToString::to_string),
+        lines_added,
+        lines_removed: old_source.lines().count(),
+    })
+}
+
+/// Id for a cell inserted by `NotebookEdit` when the result doesn't record it
+///
+/// Derived from the tool call, so replaying a session always gives the same
+/// notebook.
+fn inserted_cell_id(seed: &str) -> String {
+    format!("{:08x}", crc32fast::hash(seed.as_bytes()))
+}
+
+/// Apply a notebook cell operation and return the new notebook JSON plus the
+/// source of the replaced or deleted cell
+fn apply_notebook_edit(
+    original: &str,
+    edit_mode: &str,
+    cell_id: Option<&str>,
+    cell_type: Option<&str>,
+    new_source: &str,
+    new_cell_id: &str,
+) -> Option<(String, String)> {
+    let mut notebook: Value = serde_json::from_str(original).ok()?;
+    let nbformat_minor = notebook
+        .get("nbformat_minor")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    let cells = notebook.get_mut("cells")?.as_array_mut()?;
+    let index = cell_id.and_then(|id| find_cell_index(cells, id));
+
+    let old_source = match edit_mode {
+        "insert" => {
+            let cell_type = cell_type.unwrap_or("code");
+            let mut cell = serde_json::json!({
+                "cell_type": cell_type,
+                "metadata": {},
+                "source": notebook_source(new_source),
+            });
+            if cell_type == "code" {
+                cell["execution_count"] = Value::Null;
+                cell["outputs"] = serde_json::json!([]);
+            }
+            // Cell ids are required from nbformat 4.5
+            if nbformat_minor >= 5 {
+                cell["id"] = Value::String(new_cell_id.to_string());
+            }
+            // New cells go after the referenced cell, or at the top without one
+            let position = match (cell_id, index) {
+                (None, _) => 0,
+                (Some(_), Some(i)) => i + 1,
+                (Some(_), None) => return None,
+            };
+            cells.insert(position, cell);
+            String::new()
+        }
+        "delete" => {
+            let removed = cells.remove(index?);
+            cell_source_text(&removed)
+        }
+        _ => {
+            let cell = cells.get_mut(index?)?;
+            let old = cell_source_text(cell);
+            cell["source"] = notebook_source(new_source);
+            if let Some(cell_type) = cell_type {
+                cell["cell_type"] = Value::String(cell_type.to_string());
+            }
+            if cell.get("cell_type").and_then(Value::as_str) == Some("code") {
+                cell["execution_count"] = Value::Null;
+                cell["outputs"] = serde_json::json!([]);
+            }
+            old
+        }
+    };
+
+    // Jupyter writes notebooks with a one-space indent
+    let mut buffer = Vec::with_capacity(original.len());
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+    serde::Serialize::serialize(&notebook, &mut serializer).ok()?;
+    let mut content = String::from_utf8(buffer).ok()?;
+    if original.ends_with('\n') {
+        content.push('\n');
+    }
+
+    Some((content, old_source))
+}
+
+/// Find a cell by its `id`, falling back to a positional `cell-N` / `N` reference
+fn find_cell_index(cells: &[Value], cell_id: &str) -> Option<usize> {
+    cells
+        .iter()
+        .position(|cell| cell.get("id").and_then(Value::as_str) == Some(cell_id))
+        .or_else(|| {
+            cell_id
+                .strip_prefix("cell-")
+                .unwrap_or(cell_id)
+                .parse::<usize>()
+                .ok()
+                .filter(|&i| i < cells.len())
+        })
+}
+
+/// Convert cell text into the line-array form used in `.ipynb` files
+fn notebook_source(text: &str) -> Value {
+    Value::Array(
+        text.split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    )
+}
+
+/// Get the text of a cell whose `source` is either a string or a line array
+fn cell_source_text(cell: &Value) -> String {
+    match cell.get("source") {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
 /// Process a single session file and extract edit information
 #[allow(unsafe_code)] // Required for mmap performance optimization
-fn process_session_file_for_edits(file_path: &PathBuf) -> Option<SessionEditsResult> {
+fn process_session_file_for_edits(
+    file_path: &PathBuf,
+    file_history_dir: Option<&Path>,
+) -> Option<SessionEditsResult> {
     let file = fs::File::open(file_path).ok()?;
 
     // SAFETY: We're only reading the file, and the file handle is kept open
@@ -25,8 +394,16 @@ fn process_session_file_for_edits(file_path: &PathBuf) -> Option<SessionEditsRes
     let mmap = unsafe { Mmap::map(&file) }.ok()?;
 
     let mut edits: Vec<RecentFileEdit> = Vec::with_capacity(16);
+    let mut shell_operations: Vec<ShellFileOperation> = Vec::new();
     let mut cwd_counts: HashMap<String, usize> = HashMap::new();
 
+    // File-history snapshots are keyed by the user prompt that started the turn,
+    // so remember which prompt each edit belongs to
+    let file_session_id = session_id_from_file(file_path).unwrap_or_default();
+    let mut file_history = SessionFileHistory::default();
+    let mut current_prompt_uuid: Option<String> = None;
+    let mut tool_inputs: HashMap<String, ToolUseInput> = HashMap::new();
+
     // Use SIMD-accelerated line detection
     let line_ranges = find_line_ranges(&mmap);
 
@@ -52,90 +429,77 @@ fn process_session_file_for_edits(file_path: &PathBuf) -> Option<SessionEditsRes
             *cwd_counts.entry(cwd_path.clone()).or_insert(0) += 1;
         }
 
-        // Process tool use results for Edit and Write operations
+        // Record file-history snapshot backups
+        file_history.record(
+            &log_entry,
+            log_entry.session_id.as_deref().unwrap_or(&file_session_id),
+            cwd.as_deref(),
+            file_history_dir,
+        );
+
+        // A user entry without a tool result is a prompt that starts a new turn
+        if log_entry.message_type == "user"
+            && log_entry.tool_use_result.is_none()
+            && !log_entry.is_meta.unwrap_or(false)
+            && !log_entry.is_sidechain.unwrap_or(false)
+        {
+            current_prompt_uuid.clone_from(&log_entry.uuid);
+        }
+
+        // Remember tool_use inputs so their results can be replayed with the same parameters
+        if let Some(message) = &log_entry.message {
+            collect_tool_use_inputs(&message.content, log_entry.uuid.as_ref(), &mut tool_inputs);
+        }
+
+        // Process tool use results for file-mutating tools
         if let Some(tool_use_result) = &log_entry.tool_use_result {
-            // Handle Write/Create tool results (type: "create")
-            if tool_use_result.get("type").and_then(|v| v.as_str()) == Some("create") {
-                if let (Some(file_path_str), Some(content)) = (
-                    tool_use_result.get("filePath").and_then(|v| v.as_str()),
-                    tool_use_result.get("content").and_then(|v| v.as_str()),
-                ) {
-                    edits.push(RecentFileEdit {
-                        file_path: file_path_str.to_string(),
+            let tool_use_id = tool_result_id(&log_entry);
+            let tool_use = tool_use_id.as_ref().and_then(|id| tool_inputs.get(id));
+            // The result entry's parent is the assistant message when the
+            // tool_use block itself wasn't seen
+            let message_uuid = tool_use
+                .and_then(|t| t.message_uuid.clone())
+                .or_else(|| log_entry.parent_uuid.clone());
+
+            if let Some(change) =
+                reconstruct_tool_result(tool_use_result, tool_use, tool_use_id.as_deref())
+            {
+                edits.push(RecentFileEdit {
+                    file_path: change.file_path,
+                    timestamp: timestamp.clone(),
+                    session_id: session_id.clone(),
+                    operation_type: change.operation_type.to_string(),
+                    content_after_change: change.content_after_change,
+                    original_content: change.original_content,
+                    lines_added: change.lines_added,
+                    lines_removed: change.lines_removed,
+                    cwd: cwd.clone(),
+                    snapshot_backup: None,
+                    message_uuid,
+                    tool_use_id,
+                    prompt_uuid: current_prompt_uuid.clone(),
+                });
+            } else if let Some(command) = tool_use
+                .filter(|t| t.name == "Bash")
+                .and_then(|t| t.input.get("command"))
+                .and_then(Value::as_str)
+            {
+                // Files changed through the shell have no structured result
+                for effect in analyze_shell_command(command, cwd.as_deref()) {
+                    shell_operations.push(ShellFileOperation {
+                        file_path: effect.file_path,
+                        operation: effect.operation.to_string(),
+                        destination: effect.destination,
+                        command: command.to_string(),
                         timestamp: timestamp.clone(),
                         session_id: session_id.clone(),
-                        operation_type: "write".to_string(),
-                        content_after_change: content.to_string(),
-                        original_content: None,
-                        lines_added: content.lines().count(),
-                        lines_removed: 0,
                         cwd: cwd.clone(),
+                        message_uuid: message_uuid.clone(),
+                        tool_use_id: tool_use_id.clone(),
+                        prompt_uuid: current_prompt_uuid.clone(),
                     });
                 }
             }
-
-            // Handle Edit tool results
-            if let Some(file_path_val) = tool_use_result.get("filePath") {
-                if let Some(file_path_str) = file_path_val.as_str() {
-                    if let Some(edits_arr_val) = tool_use_result.get("edits") {
-                        // Multi-edit format
-                        if let Some(original) =
-                            tool_use_result.get("originalFile").and_then(|v| v.as_str())
-                        {
-                            let mut content = original.to_string();
-                            let mut lines_added = 0usize;
-                            let mut lines_removed = 0usize;
-
-                            if let Some(edits_arr) = edits_arr_val.as_array() {
-                                for edit in edits_arr {
-                                    if let (Some(old_str), Some(new_str)) = (
-                                        edit.get("old_string").and_then(|v| v.as_str()),
-                                        edit.get("new_string").and_then(|v| v.as_str()),
-                                    ) {
-                                        content = content.replacen(old_str, new_str, 1);
-                                        lines_removed += old_str.lines().count();
-                                        lines_added += new_str.lines().count();
-                                    }
-                                }
-                            }
-
-                            edits.push(RecentFileEdit {
-                                file_path: file_path_str.to_string(),
-                                timestamp: timestamp.clone(),
-                                session_id: session_id.clone(),
-                                operation_type: "edit".to_string(),
-                                content_after_change: content,
-                                original_content: Some(original.to_string()),
-                                lines_added,
-                                lines_removed,
-                                cwd: cwd.clone(),
-                            });
-                        }
-                    } else if let (Some(old_str), Some(new_str)) = (
-                        tool_use_result.get("oldString").and_then(|v| v.as_str()),
-                        tool_use_result.get("newString").and_then(|v| v.as_str()),
-                    ) {
-                        // Single edit format
-                        if let Some(original) =
-                            tool_use_result.get("originalFile").and_then(|v| v.as_str())
-                        {
-                            let content = original.replacen(old_str, new_str, 1);
-
-                            edits.push(RecentFileEdit {
-                                file_path: file_path_str.to_string(),
-                                timestamp: timestamp.clone(),
-                                session_id: session_id.clone(),
-                                operation_type: "edit".to_string(),
-                                content_after_change: content,
-                                original_content: Some(original.to_string()),
-                                lines_added: new_str.lines().count(),
-                                lines_removed: old_str.lines().count(),
-                                cwd: cwd.clone(),
-                            });
-                        }
-                    }
-                }
-            }
         }
 
         // Also check tool_use for Write operations
@@ -157,6 +521,13 @@ fn process_session_file_for_edits(file_path: &PathBuf) -> Option<SessionEditsRes
                                 lines_added: content.lines().count(),
                                 lines_removed: 0,
                                 cwd: cwd.clone(),
+                                snapshot_backup: None,
+                                message_uuid: log_entry.uuid.clone(),
+                                tool_use_id: tool_use
+                                    .get("id")
+                                    .and_then(|v| v.as_str())
+                                    .map(std::string::ToString::to_string),
+                                prompt_uuid: current_prompt_uuid.clone(),
                             });
                         }
                     }
@@ -165,7 +536,54 @@ fn process_session_file_for_edits(file_path: &PathBuf) -> Option<SessionEditsRes
         }
     }
 
-    Some(SessionEditsResult { edits, cwd_counts })
+    // Link each edit to the backup taken before its turn
+    for edit in &mut edits {
+        if let Some(prompt_uuid) = &edit.prompt_uuid {
+            edit.snapshot_backup = file_history.find(prompt_uuid, &edit.file_path).cloned();
+        }
+    }
+
+    Some(SessionEditsResult {
+        edits,
+        shell_operations,
+        cwd_counts,
+    })
+}
+
+/// Collect all JSONL session files under a project directory
+fn collect_session_files(project_path: &str) -> Vec<PathBuf> {
+    WalkDir::new(project_path)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .map(|e| e.path().to_path_buf())
+        .collect()
+}
+
+/// Most frequent working directory, used as the project directory
+fn most_common_cwd(cwd_counts: HashMap<String, usize>) -> Option<String> {
+    cwd_counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(cwd, _)| cwd)
+}
+
+/// Prefix check on paths, case-insensitive on Windows
+fn path_starts_with(path: &str, prefix: &str) -> bool {
+    if cfg!(target_os = "windows") {
+        path.to_lowercase().starts_with(&prefix.to_lowercase())
+    } else {
+        path.starts_with(prefix)
+    }
+}
+
+/// Path equality, case-insensitive on Windows
+fn path_eq(a: &str, b: &str) -> bool {
+    if cfg!(target_os = "windows") {
+        a.to_lowercase() == b.to_lowercase()
+    } else {
+        a == b
+    }
 }
 
 /// Paginated response for recent edits
@@ -193,17 +611,13 @@ pub async fn get_recent_edits(
     let offset = offset.unwrap_or(0);
     let limit = limit.unwrap_or(20);
     // Phase 1: Collect all session files
-    let session_files: Vec<PathBuf> = WalkDir::new(&project_path)
-        .into_iter()
-        .filter_map(std::result::Result::ok)
-        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"))
-        .map(|e| e.path().to_path_buf())
-        .collect();
+    let session_files = collect_session_files(&project_path);
 
     // Phase 2: Process files in parallel
+    let file_history_dir = file_history_dir_for_project(&project_path);
     let file_results: Vec<SessionEditsResult> = session_files
         .par_iter()
-        .filter_map(process_session_file_for_edits)
+        .filter_map(|path| process_session_file_for_edits(path, file_history_dir.as_deref()))
         .collect();
 
     // Phase 3: Aggregate results with pre-allocated capacity
@@ -219,29 +633,13 @@ pub async fn get_recent_edits(
     }
 
     // Find the most common cwd (project directory)
-    let project_cwd = cwd_counts
-        .into_iter()
-        .max_by_key(|(_, count)| *count)
-        .map(|(cwd, _)| cwd);
+    let project_cwd = most_common_cwd(cwd_counts);
 
     // Filter edits to only include files within the project directory
-    // Use case-insensitive comparison on Windows for path matching
     let filtered_edits: Vec<RecentFileEdit> = if let Some(ref cwd) = project_cwd {
-        #[cfg(target_os = "windows")]
-        let cwd_normalized = cwd.to_lowercase();
-        #[cfg(not(target_os = "windows"))]
-        let cwd_normalized = cwd.clone();
-
         all_edits
             .into_iter()
-            .filter(|edit| {
-                #[cfg(target_os = "windows")]
-                let file_path_normalized = edit.file_path.to_lowercase();
-                #[cfg(not(target_os = "windows"))]
-                let file_path_normalized = edit.file_path.clone();
-
-                file_path_normalized.starts_with(&cwd_normalized)
-            })
+            .filter(|edit| path_starts_with(&edit.file_path, cwd))
             .collect()
     } else {
         all_edits
@@ -266,7 +664,16 @@ pub async fn get_recent_edits(
     files.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
 
     // Apply pagination
-    let paginated_files: Vec<RecentFileEdit> = files.into_iter().skip(offset).take(limit).collect();
+    let mut paginated_files: Vec<RecentFileEdit> =
+        files.into_iter().skip(offset).take(limit).collect();
+
+    // Fall back to Claude Code's file-history backup when the tool result has no
+    // "before" content (e.g. Write over an existing file)
+    for edit in &mut paginated_files {
+        if edit.original_content.is_none() {
+            edit.original_content = edit.snapshot_backup.as_ref().and_then(read_backup_content);
+        }
+    }
 
     let has_more = offset + paginated_files.len() < unique_files_count;
 
@@ -281,6 +688,87 @@ pub async fn get_recent_edits(
     })
 }
 
+/// Find the messages that edited a file, oldest first
+///
+/// Each origin carries the assistant message uuid, `tool_use` id and the user
+/// prompt uuid so the UI can jump from a change to the reasoning behind it.
+#[tauri::command]
+pub async fn get_file_edit_origins(
+    project_path: String,
+    file_path: String,
+) -> Result<Vec<FileEditOrigin>, String> {
+    let session_files = collect_session_files(&project_path);
+    let file_results: Vec<SessionEditsResult> = session_files
+        .par_iter()
+        .filter_map(|path| process_session_file_for_edits(path, None))
+        .collect();
+
+    let mut origins: Vec<FileEditOrigin> = Vec::new();
+    for result in file_results {
+        origins.extend(
+            result
+                .edits
+                .into_iter()
+                .filter(|edit| path_eq(&edit.file_path, &file_path))
+                .map(FileEditOrigin::from),
+        );
+        // Shell operations touch the file as source or destination of a move
+        origins.extend(
+            result
+                .shell_operations
+                .into_iter()
+                .filter(|op| {
+                    path_eq(&op.file_path, &file_path)
+                        || op
+                            .destination
+                            .as_deref()
+                            .is_some_and(|dest| path_eq(dest, &file_path))
+                })
+                .map(FileEditOrigin::from),
+        );
+    }
+
+    origins.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    Ok(origins)
+}
+
+/// Scan all JSONL files in a project for files deleted, moved or rewritten
+/// through Bash tool calls, newest first
+///
+/// Only includes operations on files within the project's working directory.
+#[tauri::command]
+pub async fn get_shell_file_operations(
+    project_path: String,
+) -> Result<Vec<ShellFileOperation>, String> {
+    let session_files = collect_session_files(&project_path);
+    let file_results: Vec<SessionEditsResult> = session_files
+        .par_iter()
+        .filter_map(|path| process_session_file_for_edits(path, None))
+        .collect();
+
+    let mut operations: Vec<ShellFileOperation> = Vec::new();
+    let mut cwd_counts: HashMap<String, usize> = HashMap::new();
+    for result in file_results {
+        operations.extend(result.shell_operations);
+        for (cwd, count) in result.cwd_counts {
+            *cwd_counts.entry(cwd).or_insert(0) += count;
+        }
+    }
+
+    if let Some(project_cwd) = most_common_cwd(cwd_counts) {
+        operations.retain(|op| {
+            path_starts_with(&op.file_path, &project_cwd)
+                || op
+                    .destination
+                    .as_deref()
+                    .is_some_and(|dest| path_starts_with(dest, &project_cwd))
+        });
+    }
+
+    operations.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    Ok(operations)
+}
+
 /// Restore a file by writing content to the specified path
 ///
 /// Uses atomic write pattern: writes to a temporary file first, then renames.
@@ -569,4 +1057,313 @@ mod tests {
         assert_eq!(edits_result.unique_files_count, 2);
         assert_eq!(edits_result.project_cwd, Some("/test/project".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_get_recent_edits_links_file_history_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join(".claude/projects/-test-project");
+        let backup_dir = temp_dir.path().join(".claude/file-history/session-1");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::create_dir_all(&backup_dir).unwrap();
+        fs::write(backup_dir.join("abc@v1"), "old main").unwrap();
+
+        // Snapshot for prompt uuid-1, then a Write over the existing file in that turn
+        let content = r#"{"type":"file-history-snapshot","messageId":"uuid-1","snapshot":{"messageId":"uuid-1","trackedFileBackups":{"/test/project/src/main.rs":{"backupFileName":"abc@v1","version":1,"backupTime":"2025-06-26T10:00:00Z"}}},"isSnapshotUpdate":false}
+{"uuid":"uuid-1","sessionId":"session-1","timestamp":"2025-06-26T10:00:00Z","type":"user","cwd":"/test/project","message":{"role":"user","content":"rewrite main"}}
+{"uuid":"uuid-2","parentUuid":"uuid-1","sessionId":"session-1","timestamp":"2025-06-26T10:00:05Z","type":"assistant","cwd":"/test/project","toolUse":{"name":"Write","input":{"file_path":"/test/project/src/main.rs","content":"fn main() {}"}}}"#;
+        fs::write(project_dir.join("session-1.jsonl"), content).unwrap();
+
+        let result = get_recent_edits(project_dir.to_string_lossy().to_string(), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.files.len(), 1);
+        let edit = &result.files[0];
+        let backup = edit.snapshot_backup.as_ref().unwrap();
+        assert_eq!(backup.message_id, "uuid-1");
+        assert_eq!(backup.backup_file_name.as_deref(), Some("abc@v1"));
+        // Write has no originalFile, so the backup provides the "before" content
+        assert_eq!(edit.original_content.as_deref(), Some("old main"));
+    }
+
+    #[tokio::test]
+    async fn test_get_recent_edits_keeps_tool_original_over_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join(".claude/projects/-test-project");
+        let backup_dir = temp_dir.path().join(".claude/file-history/session-1");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::create_dir_all(&backup_dir).unwrap();
+        fs::write(backup_dir.join("abc@v1"), "turn start").unwrap();
+
+        let content = r#"{"type":"file-history-snapshot","messageId":"uuid-1","snapshot":{"trackedFileBackups":{"/test/project/a.txt":{"backupFileName":"abc@v1","version":1}}}}
+{"uuid":"uuid-1","sessionId":"session-1","timestamp":"2025-06-26T10:00:00Z","type":"user","cwd":"/test/project","message":{"role":"user","content":"edit a"}}
+{"uuid":"uuid-2","sessionId":"session-1","timestamp":"2025-06-26T10:00:05Z","type":"user","cwd":"/test/project","toolUseResult":{"filePath":"/test/project/a.txt","oldString":"old","newString":"new","originalFile":"old"}}"#;
+        fs::write(project_dir.join("session-1.jsonl"), content).unwrap();
+
+        let result = get_recent_edits(project_dir.to_string_lossy().to_string(), None, None)
+            .await
+            .unwrap();
+
+        let edit = &result.files[0];
+        assert!(edit.snapshot_backup.is_some());
+        assert_eq!(edit.original_content.as_deref(), Some("old"));
+    }
+
+    fn fixture_edits(name: &str) -> Vec<RecentFileEdit> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/edits")
+            .join(name);
+        process_session_file_for_edits(&path, None).unwrap().edits
+    }
+
+    #[test]
+    fn test_edit_replace_all_from_transcript() {
+        let edits = fixture_edits("multi_edit_and_replace_all.jsonl");
+        let edit = &edits[0];
+
+        assert_eq!(edit.file_path, "/home/dev/app/src/counter.js");
+        assert_eq!(
+            edit.content_after_change,
+            "let total = 0;\nconsole.log(total);\ntotal += 1;\n"
+        );
+        assert_eq!(edit.lines_added, 3);
+        assert_eq!(edit.lines_removed, 3);
+    }
+
+    #[test]
+    fn test_multi_edit_from_transcript() {
+        let edits = fixture_edits("multi_edit_and_replace_all.jsonl");
+        let edit = &edits[1];
+
+        assert_eq!(edit.file_path, "/home/dev/app/src/index.js");
+        assert_eq!(edit.operation_type, "edit");
+        // First edit replaces every "a", second applies to the already edited text
+        assert_eq!(
+            edit.content_after_change,
+            "import { fetchData } from './fetchData';\nconst data = fetchData();\nexport { data };\n"
+        );
+        assert_eq!(
+            edit.original_content.as_deref(),
+            Some("import { load } from './load';\nconst data = load();\nexport default data;\n")
+        );
+    }
+
+    #[test]
+    fn test_write_update_keeps_original_from_transcript() {
+        let edits = fixture_edits("multi_edit_and_replace_all.jsonl");
+        let edit = &edits[2];
+
+        assert_eq!(edit.file_path, "/home/dev/app/README.md");
+        assert_eq!(edit.operation_type, "write");
+        assert_eq!(edit.original_content.as_deref(), Some("# App\n"));
+        assert_eq!(edit.lines_added, 3);
+        assert_eq!(edit.lines_removed, 1);
+    }
+
+    #[test]
+    fn test_notebook_edit_from_transcript() {
+        let edits = fixture_edits("notebook_edit.jsonl");
+
+        // The failed edit (cell not found) is skipped
+        assert_eq!(edits.len(), 3);
+        assert!(edits
+            .iter()
+            .all(|e| e.file_path == "/home/dev/app/analysis.ipynb"));
+
+        // Replace is replayed on original_file and matches Jupyter's serialization
+        let expected_replace = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/edits/expected_after_replace.ipynb"
+        ));
+        assert_eq!(edits[0].content_after_change, expected_replace);
+        assert_eq!(edits[0].lines_added, 2);
+        assert_eq!(edits[0].lines_removed, 2);
+
+        // Delete uses the recorded updated_file
+        let after_delete: Value = serde_json::from_str(&edits[1].content_after_change).unwrap();
+        let cells = after_delete["cells"].as_array().unwrap();
+        assert_eq!(cells.len(), 2);
+        assert!(cells.iter().all(|c| c["id"] != "c9d0e1f2"));
+        assert_eq!(edits[1].lines_removed, 1);
+
+        // Insert adds a markdown cell after the referenced cell
+        let after_insert: Value = serde_json::from_str(&edits[2].content_after_change).unwrap();
+        let cells = after_insert["cells"].as_array().unwrap();
+        assert_eq!(cells.len(), 3);
+        assert_eq!(cells[0]["id"], "a1b2c3d4");
+        assert_eq!(cells[1]["cell_type"], "markdown");
+        assert_eq!(cells[1]["source"], serde_json::json!(["## Setup"]));
+        // The new cell's id comes from the tool call, the same on every load
+        assert_eq!(cells[1]["id"], inserted_cell_id("toolu_06NotebookInsert"));
+        assert_eq!(
+            fixture_edits("notebook_edit.jsonl")[2].content_after_change,
+            edits[2].content_after_change
+        );
+        assert_eq!(edits[2].lines_added, 1);
+        assert_eq!(edits[2].lines_removed, 0);
+    }
+
+    #[test]
+    fn test_apply_string_edit_replace_all() {
+        assert_eq!(
+            apply_string_edit("a-a-a", "a", "b", true),
+            ("b-b-b".to_string(), 3)
+        );
+        assert_eq!(
+            apply_string_edit("a-a-a", "a", "b", false),
+            ("b-a-a".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn test_find_cell_index_falls_back_to_position() {
+        let cells = vec![serde_json::json!({"id": "x"}), serde_json::json!({})];
+        assert_eq!(find_cell_index(&cells, "x"), Some(0));
+        assert_eq!(find_cell_index(&cells, "cell-1"), Some(1));
+        assert_eq!(find_cell_index(&cells, "1"), Some(1));
+        assert_eq!(find_cell_index(&cells, "cell-2"), None);
+    }
+
+    #[test]
+    fn test_edits_link_to_originating_messages() {
+        let edits = fixture_edits("multi_edit_and_replace_all.jsonl");
+
+        let prompt = "00000000-0000-4000-8000-000000000001";
+        assert_eq!(
+            edits[0].message_uuid.as_deref(),
+            Some("00000000-0000-4000-8000-000000000002")
+        );
+        assert_eq!(
+            edits[0].tool_use_id.as_deref(),
+            Some("toolu_01EditReplaceAll")
+        );
+        assert_eq!(edits[1].tool_use_id.as_deref(), Some("toolu_02MultiEdit"));
+        assert_eq!(
+            edits[1].message_uuid.as_deref(),
+            Some("00000000-0000-4000-8000-000000000004")
+        );
+        assert!(edits
+            .iter()
+            .all(|e| e.prompt_uuid.as_deref() == Some(prompt)));
+    }
+
+    #[test]
+    fn test_edit_message_uuid_falls_back_to_parent() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // Tool result without the assistant tool_use entry in the file
+        let content = r#"{"uuid":"uuid-2","parentUuid":"assistant-1","sessionId":"session-1","timestamp":"2025-06-26T10:00:00Z","type":"user","cwd":"/test/project","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"ok"}]},"toolUseResult":{"filePath":"/test/project/a.txt","oldString":"a","newString":"b","originalFile":"a"}}"#;
+        let path = create_test_jsonl_file(&temp_dir, "session.jsonl", content);
+
+        let edits = process_session_file_for_edits(&path, None).unwrap().edits;
+        assert_eq!(edits[0].message_uuid.as_deref(), Some("assistant-1"));
+        assert_eq!(edits[0].tool_use_id.as_deref(), Some("toolu_1"));
+        assert_eq!(edits[0].prompt_uuid, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_file_edit_origins() {
+        let temp_dir = TempDir::new().unwrap();
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/edits/notebook_edit.jsonl");
+        fs::copy(&fixture, temp_dir.path().join("session.jsonl")).unwrap();
+        fs::copy(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/edits/multi_edit_and_replace_all.jsonl"),
+            temp_dir.path().join("other.jsonl"),
+        )
+        .unwrap();
+
+        let origins = get_file_edit_origins(
+            temp_dir.path().to_string_lossy().to_string(),
+            "/home/dev/app/analysis.ipynb".to_string(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(origins.len(), 3);
+        let tool_use_ids: Vec<_> = origins
+            .iter()
+            .map(|o| o.tool_use_id.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            tool_use_ids,
+            vec![
+                "toolu_04NotebookReplace",
+                "toolu_05NotebookDelete",
+                "toolu_06NotebookInsert"
+            ]
+        );
+        assert!(origins.iter().all(|o| o.prompt_uuid.is_some()));
+    }
+
+    #[test]
+    fn test_shell_operations_from_transcript() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/edits/bash_operations.jsonl");
+        let result = process_session_file_for_edits(&path, None).unwrap();
+
+        assert!(result.edits.is_empty());
+        let ops: Vec<(&str, &str, Option<&str>)> = result
+            .shell_operations
+            .iter()
+            .map(|op| {
+                (
+                    op.operation.as_str(),
+                    op.file_path.as_str(),
+                    op.destination.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                ("delete", "/home/dev/app/scripts/build-old.sh", None),
+                (
+                    "move",
+                    "/home/dev/app/config.json",
+                    Some("/home/dev/app/config.default.json")
+                ),
+                ("rewrite", "/home/dev/app/config.default.json", None),
+            ]
+        );
+
+        let sed = &result.shell_operations[2];
+        assert_eq!(
+            sed.command,
+            "sed -i 's/8080/3000/' config.default.json 2>&1"
+        );
+        assert_eq!(sed.tool_use_id.as_deref(), Some("toolu_12BashSed"));
+        assert_eq!(
+            sed.prompt_uuid.as_deref(),
+            Some("10000000-0000-4000-8000-000000000001")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_edit_origins_include_shell_operations() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::copy(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/edits/bash_operations.jsonl"),
+            temp_dir.path().join("session.jsonl"),
+        )
+        .unwrap();
+        let project_path = temp_dir.path().to_string_lossy().to_string();
+
+        let origins = get_file_edit_origins(
+            project_path.clone(),
+            "/home/dev/app/config.default.json".to_string(),
+        )
+        .await
+        .unwrap();
+        let kinds: Vec<&str> = origins.iter().map(|o| o.operation_type.as_str()).collect();
+        assert_eq!(kinds, vec!["move", "rewrite"]);
+        assert!(origins.iter().all(|o| o.command.is_some()));
+
+        let operations = get_shell_file_operations(project_path).await.unwrap();
+        assert_eq!(operations.len(), 3);
+        // Newest first
+        assert_eq!(operations[0].operation, "rewrite");
+    }
 }
@@ -0,0 +1,401 @@
+//! Cross-session file activity queries
+//!
+//! Answers "which sessions touched this file?" for a whole project by scanning
+//! `tool_use` inputs (Read, Edit, Write, Grep, Bash, ...) and message text.
+
+use super::shell_ops::analyze_shell_command;
+use crate::glob::GlobPattern;
+use crate::models::{FileActivityMessage, FileActivityResult, FileSessionActivity, RawLogEntry};
+use crate::utils::find_line_ranges;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Tool name used for file references in user/assistant message text
+const MENTION_TOOL: &str = "mention";
+
+/// Matches file paths against a literal path or glob
+///
+/// Relative patterns are matched against paths relative to the message's cwd.
+pub(super) struct PathMatcher {
+    pattern: String,
+    glob: Option<GlobPattern>,
+    is_absolute: bool,
+}
+
+impl PathMatcher {
+    pub(super) fn new(pattern: &str) -> Self {
+        let mut pattern = pattern.trim().trim_start_matches("./").to_string();
+        if cfg!(target_os = "windows") {
+            pattern = pattern.to_lowercase();
+        }
+        let glob = if pattern.contains(['*', '?', '[', '{']) {
+            GlobPattern::new(&Self::normalize(&pattern)).ok()
+        } else {
+            None
+        };
+        Self {
+            is_absolute: Path::new(&pattern).is_absolute(),
+            glob,
+            pattern,
+        }
+    }
+
+    /// Use `/` separators so `*` stops at Windows directory boundaries too
+    fn normalize(path: &str) -> String {
+        if cfg!(target_os = "windows") {
+            path.replace('\\', "/")
+        } else {
+            path.to_string()
+        }
+    }
+
+    pub(super) fn matches(&self, path: &str, cwd: Option<&str>) -> bool {
+        let candidate = if self.is_absolute {
+            path
+        } else {
+            match cwd.and_then(|cwd| path.strip_prefix(cwd)) {
+                Some(relative) if relative.starts_with('/') || relative.starts_with('\\') => {
+                    &relative[1..]
+                }
+                _ => return false,
+            }
+        };
+
+        let lowered;
+        let candidate = if cfg!(target_os = "windows") {
+            lowered = candidate.to_lowercase();
+            lowered.as_str()
+        } else {
+            candidate
+        };
+        match &self.glob {
+            Some(glob) => glob.matches_path(&Self::normalize(candidate)),
+            None => candidate == self.pattern,
+        }
+    }
+}
+
+/// Files accessed by a `tool_use` block, as (access kind, path) pairs
+pub(super) fn tool_file_accesses(
+    name: &str,
+    input: &Value,
+    cwd: Option<&str>,
+) -> Vec<(&'static str, String)> {
+    let field = |key: &str| {
+        input
+            .get(key)
+            .and_then(Value::as_str)
+            .map(|path| resolve_path(cwd, path))
+    };
+
+    let single = |access: &'static str, key: &str| field(key).map(|p| (access, p)).into_iter();
+
+    match name {
+        "Read" => single("read", "file_path").collect(),
+        "NotebookRead" => single("read", "notebook_path").collect(),
+        "Edit" | "MultiEdit" => single("edit", "file_path").collect(),
+        "NotebookEdit" => single("edit", "notebook_path").collect(),
+        "Write" => single("write", "file_path").collect(),
+        "Grep" => single("grep", "path").collect(),
+        "Bash" => input
+            .get("command")
+            .and_then(Value::as_str)
+            .map(|command| {
+                analyze_shell_command(command, cwd)
+                    .into_iter()
+                    .flat_map(|effect| std::iter::once(effect.file_path).chain(effect.destination))
+                    .map(|path| ("shell", path))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Resolve a possibly relative path against the working directory
+fn resolve_path(cwd: Option<&str>, path: &str) -> String {
+    match cwd {
+        Some(cwd) if Path::new(path).is_relative() => Path::new(cwd)
+            .join(path.trim_start_matches("./"))
+            .to_string_lossy()
+            .to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// Path-like words in message text, with quotes and `:line` suffixes stripped
+fn mentioned_paths(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| {
+        c.is_whitespace() || matches!(c, '`' | '"' | '\'' | '(' | ')' | ',' | '<' | '>')
+    })
+    .map(|word| {
+        let word = word.trim_end_matches(['.', ':', ';', '!', '?']);
+        // `src/lib.rs:42:7` -> `src/lib.rs`
+        let mut word = word;
+        while let Some((head, tail)) = word.rsplit_once(':') {
+            if tail.is_empty() || !tail.chars().all(|c| c.is_ascii_digit()) {
+                break;
+            }
+            word = head;
+        }
+        word
+    })
+    .filter(|word| word.contains('/') || word.contains('.'))
+}
+
+/// Text of user/assistant message content, skipping tool results
+fn message_texts(content: &Value) -> Vec<&str> {
+    match content {
+        Value::String(text) => vec![text.as_str()],
+        Value::Array(items) => items
+            .iter()
+            .filter(|item| item.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|item| item.get("text").and_then(Value::as_str))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Scan one session file for activity on matching files
+#[allow(unsafe_code)] // Required for mmap performance optimization
+fn scan_session_file(file_path: &PathBuf, matcher: &PathMatcher) -> Option<FileSessionActivity> {
+    let file = fs::File::open(file_path).ok()?;
+
+    // SAFETY: We're only reading the file, and the file handle is kept open
+    // for the duration of the mmap's lifetime. Session files are append-only.
+    let mmap = unsafe { Mmap::map(&file) }.ok()?;
+
+    let mut messages: Vec<FileActivityMessage> = Vec::new();
+    let mut session_id: Option<String> = None;
+
+    for (start, end) in find_line_ranges(&mmap) {
+        // simd-json requires mutable slice
+        let mut line_bytes = mmap[start..end].to_vec();
+        let Ok(entry) = simd_json::serde::from_slice::<RawLogEntry>(&mut line_bytes) else {
+            continue;
+        };
+        let Some(message) = &entry.message else {
+            continue;
+        };
+        if session_id.is_none() {
+            session_id.clone_from(&entry.session_id);
+        }
+        let cwd = entry.cwd.as_deref();
+
+        let mut push = |tool_name: &str, access: &str, path: String, tool_use_id: Option<&str>| {
+            messages.push(FileActivityMessage {
+                uuid: entry.uuid.clone(),
+                timestamp: entry.timestamp.clone(),
+                tool_name: tool_name.to_string(),
+                access: access.to_string(),
+                file_path: path,
+                tool_use_id: tool_use_id.map(std::string::ToString::to_string),
+            });
+        };
+
+        // Tool calls in assistant messages
+        if let Some(items) = message.content.as_array() {
+            for item in items {
+                if item.get("type").and_then(Value::as_str) != Some("tool_use") {
+                    continue;
+                }
+                let Some(name) = item.get("name").and_then(Value::as_str) else {
+                    continue;
+                };
+                let input = item.get("input").unwrap_or(&Value::Null);
+                let tool_use_id = item.get("id").and_then(Value::as_str);
+                for (access, path) in tool_file_accesses(name, input, cwd) {
+                    if matcher.matches(&path, cwd) {
+                        push(name, access, path, tool_use_id);
+                    }
+                }
+            }
+        }
+
+        // References in message text, once per file per message
+        let mut mentioned: Vec<String> = Vec::new();
+        for text in message_texts(&message.content) {
+            for word in mentioned_paths(text) {
+                let path = resolve_path(cwd, word);
+                if matcher.matches(&path, cwd) && !mentioned.contains(&path) {
+                    mentioned.push(path);
+                }
+            }
+        }
+        for path in mentioned {
+            push(MENTION_TOOL, MENTION_TOOL, path, None);
+        }
+    }
+
+    if messages.is_empty() {
+        return None;
+    }
+
+    let mut tool_counts: HashMap<String, usize> = HashMap::new();
+    for message in &messages {
+        *tool_counts.entry(message.tool_name.clone()).or_insert(0) += 1;
+    }
+    let first_timestamp = messages.iter().filter_map(|m| m.timestamp.clone()).min();
+    let last_timestamp = messages.iter().filter_map(|m| m.timestamp.clone()).max();
+
+    Some(FileSessionActivity {
+        session_id: session_id.unwrap_or_else(|| {
+            file_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        }),
+        session_path: file_path.to_string_lossy().to_string(),
+        tool_counts,
+        first_timestamp,
+        last_timestamp,
+        messages,
+    })
+}
+
+/// Find every session and message in a project that read, edited, wrote,
+/// grepped, ran shell commands on, or mentioned a file
+///
+/// `path_pattern` is an absolute path, a path relative to the session's working
+/// directory, or a glob (`*` and `?`).
+#[tauri::command]
+pub async fn query_file_activity(
+    project_path: String,
+    path_pattern: String,
+) -> Result<FileActivityResult, String> {
+    if path_pattern.trim().is_empty() {
+        return Err("Path pattern must not be empty".to_string());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let matcher = PathMatcher::new(&path_pattern);
+
+        let session_files: Vec<PathBuf> = WalkDir::new(&project_path)
+            .into_iter()
+            .filter_map(std::result::Result::ok)
+            .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"))
+            .map(|e| e.path().to_path_buf())
+            .collect();
+
+        let mut sessions: Vec<FileSessionActivity> = session_files
+            .par_iter()
+            .filter_map(|path| scan_session_file(path, &matcher))
+            .collect();
+        sessions.sort_by(|a, b| b.last_timestamp.cmp(&a.last_timestamp));
+
+        let mut tool_counts: HashMap<String, usize> = HashMap::new();
+        for session in &sessions {
+            for (tool, count) in &session.tool_counts {
+                *tool_counts.entry(tool.clone()).or_insert(0) += count;
+            }
+        }
+
+        FileActivityResult {
+            pattern: path_pattern,
+            total_matches: tool_counts.values().sum(),
+            tool_counts,
+            sessions,
+        }
+    })
+    .await
+    .map_err(|e| format!("Failed to query file activity: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SESSION: &str = r#"{"uuid":"u1","sessionId":"s1","timestamp":"2025-06-26T10:00:00Z","type":"user","cwd":"/repo","message":{"role":"user","content":"Please look at `src/lib.rs:42` and README.md."}}
+{"uuid":"a1","sessionId":"s1","timestamp":"2025-06-26T10:01:00Z","type":"assistant","cwd":"/repo","message":{"role":"assistant","content":[{"type":"text","text":"Reading it."},{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/repo/src/lib.rs"}},{"type":"tool_use","id":"t2","name":"Grep","input":{"pattern":"fn main","path":"src/main.rs"}}]}}
+{"uuid":"u2","sessionId":"s1","timestamp":"2025-06-26T10:02:00Z","type":"user","cwd":"/repo","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"src/lib.rs contents"}]}}
+{"uuid":"a2","sessionId":"s1","timestamp":"2025-06-26T10:03:00Z","type":"assistant","cwd":"/repo","message":{"role":"assistant","content":[{"type":"tool_use","id":"t3","name":"Edit","input":{"file_path":"/repo/src/lib.rs","old_string":"a","new_string":"b"}},{"type":"tool_use","id":"t4","name":"Bash","input":{"command":"rm src/old.rs"}}]}}
+"#;
+
+    fn write_project(dir: &TempDir) -> String {
+        fs::write(dir.path().join("s1.jsonl"), SESSION).unwrap();
+        fs::write(
+            dir.path().join("s2.jsonl"),
+            r#"{"uuid":"b1","sessionId":"s2","timestamp":"2025-06-27T10:00:00Z","type":"assistant","cwd":"/repo","message":{"role":"assistant","content":[{"type":"tool_use","id":"t9","name":"Write","input":{"file_path":"/repo/src/lib.rs","content":"x"}}]}}"#,
+        )
+        .unwrap();
+        dir.path().to_string_lossy().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_query_file_activity_exact_path() {
+        let dir = TempDir::new().unwrap();
+        let project = write_project(&dir);
+
+        let result = query_file_activity(project, "/repo/src/lib.rs".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(result.sessions.len(), 2);
+        // Most recent session first
+        assert_eq!(result.sessions[0].session_id, "s2");
+        assert_eq!(result.tool_counts.get("Read"), Some(&1));
+        assert_eq!(result.tool_counts.get("Edit"), Some(&1));
+        assert_eq!(result.tool_counts.get("Write"), Some(&1));
+        // Mentioned once in the prompt; tool_result text is not counted
+        assert_eq!(result.tool_counts.get(MENTION_TOOL), Some(&1));
+        assert_eq!(result.total_matches, 4);
+
+        let read = result.sessions[1]
+            .messages
+            .iter()
+            .find(|m| m.tool_name == "Read")
+            .unwrap();
+        assert_eq!(read.uuid.as_deref(), Some("a1"));
+        assert_eq!(read.tool_use_id.as_deref(), Some("t1"));
+        assert_eq!(read.access, "read");
+    }
+
+    #[tokio::test]
+    async fn test_query_file_activity_relative_glob() {
+        let dir = TempDir::new().unwrap();
+        let project = write_project(&dir);
+
+        let result = query_file_activity(project, "src/*.rs".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(result.tool_counts.get("Grep"), Some(&1));
+        assert_eq!(result.tool_counts.get("Bash"), Some(&1));
+        let bash = result.sessions[1]
+            .messages
+            .iter()
+            .find(|m| m.tool_name == "Bash")
+            .unwrap();
+        assert_eq!(bash.file_path, "/repo/src/old.rs");
+        assert_eq!(bash.access, "shell");
+    }
+
+    #[tokio::test]
+    async fn test_query_file_activity_no_matches() {
+        let dir = TempDir::new().unwrap();
+        let project = write_project(&dir);
+
+        let result = query_file_activity(project, "/elsewhere/file.txt".to_string())
+            .await
+            .unwrap();
+        assert!(result.sessions.is_empty());
+        assert_eq!(result.total_matches, 0);
+
+        assert!(query_file_activity(String::new(), "  ".to_string())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_mentioned_paths_strip_line_numbers() {
+        let paths: Vec<&str> =
+            mentioned_paths("See src/lib.rs:10:4, (docs/guide.md) and done.").collect();
+        assert_eq!(paths, vec!["src/lib.rs", "docs/guide.md"]);
+    }
+}
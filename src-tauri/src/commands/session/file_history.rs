@@ -0,0 +1,472 @@
+//! Claude Code file-history snapshot functions
+//!
+//! Claude Code records a `file-history-snapshot` entry for each user prompt and
+//! stores a backup of every tracked file under `~/.claude/file-history/{session_id}/`
+//! before the assistant modifies it. These backups are the authoritative "before"
+//! content of a turn, including for files changed through Bash where no Edit
+//! tool result exists.
+
+use crate::commands::trusted_symlinks::check_symlinks;
+use crate::models::{FileHistoryBackup, RawLogEntry};
+use crate::utils::find_line_ranges;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// `snapshot` payload of a `file-history-snapshot` entry
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotPayload {
+    message_id: Option<String>,
+    #[serde(default)]
+    tracked_file_backups: HashMap<String, TrackedFileBackup>,
+}
+
+/// Single entry of `snapshot.trackedFileBackups`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackedFileBackup {
+    backup_file_name: Option<String>,
+    #[serde(default)]
+    version: u32,
+    backup_time: Option<String>,
+}
+
+/// Backups recorded in one session, keyed by (message id, file path)
+#[derive(Default)]
+pub(crate) struct SessionFileHistory {
+    backups: HashMap<(String, String), FileHistoryBackup>,
+}
+
+impl SessionFileHistory {
+    /// Record the backups of a `file-history-snapshot` entry.
+    ///
+    /// Snapshot updates (`isSnapshotUpdate: true`) add files to the snapshot of an
+    /// earlier message, so entries are merged and the highest version wins.
+    pub(crate) fn record(
+        &mut self,
+        entry: &RawLogEntry,
+        session_id: &str,
+        cwd: Option<&str>,
+        file_history_dir: Option<&Path>,
+    ) {
+        for backup in parse_snapshot_entry(entry, session_id, cwd, file_history_dir) {
+            let key = (backup.message_id.clone(), backup.file_path.clone());
+            match self.backups.get(&key) {
+                Some(existing) if existing.version >= backup.version => {}
+                _ => {
+                    self.backups.insert(key, backup);
+                }
+            }
+        }
+    }
+
+    /// Find the backup taken for `file_path` in the snapshot of `message_id`
+    pub(crate) fn find(&self, message_id: &str, file_path: &str) -> Option<&FileHistoryBackup> {
+        self.backups
+            .get(&(message_id.to_string(), file_path.to_string()))
+    }
+
+    pub(crate) fn into_backups(self) -> Vec<FileHistoryBackup> {
+        self.backups.into_values().collect()
+    }
+}
+
+/// Get the `file-history` directory for a project storage path
+///
+/// `~/.claude/projects/{project}` → `~/.claude/file-history`
+pub(crate) fn file_history_dir_for_project(project_path: &str) -> Option<PathBuf> {
+    Path::new(project_path)
+        .parent()
+        .and_then(Path::parent)
+        .map(|claude_dir| claude_dir.join("file-history"))
+}
+
+/// Get the session ID for a session file (Claude Code names files `{session_id}.jsonl`)
+pub(crate) fn session_id_from_file(file_path: &Path) -> Option<String> {
+    file_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(std::string::ToString::to_string)
+}
+
+/// Extract file backups from a `file-history-snapshot` log entry.
+/// Returns an empty list for any other entry type.
+pub(crate) fn parse_snapshot_entry(
+    entry: &RawLogEntry,
+    session_id: &str,
+    cwd: Option<&str>,
+    file_history_dir: Option<&Path>,
+) -> Vec<FileHistoryBackup> {
+    if entry.message_type != "file-history-snapshot" {
+        return Vec::new();
+    }
+    let Some(snapshot) = entry.snapshot.clone() else {
+        return Vec::new();
+    };
+    let Ok(payload) = serde_json::from_value::<SnapshotPayload>(snapshot) else {
+        return Vec::new();
+    };
+    let Some(message_id) = entry.message_id.clone().or(payload.message_id) else {
+        return Vec::new();
+    };
+
+    payload
+        .tracked_file_backups
+        .into_iter()
+        .map(|(path, tracked)| {
+            // Tracked paths are usually absolute, but resolve relative ones against cwd
+            let file_path = match cwd {
+                Some(cwd) if !Path::new(&path).is_absolute() => {
+                    Path::new(cwd).join(&path).to_string_lossy().to_string()
+                }
+                _ => path,
+            };
+
+            let backup_path = match (file_history_dir, tracked.backup_file_name.as_deref()) {
+                (Some(dir), Some(name)) if is_safe_file_name(name) => Some(
+                    dir.join(session_id)
+                        .join(name)
+                        .to_string_lossy()
+                        .to_string(),
+                ),
+                _ => None,
+            };
+
+            FileHistoryBackup {
+                file_path,
+                session_id: session_id.to_string(),
+                message_id: message_id.clone(),
+                version: tracked.version,
+                backup_file_name: tracked.backup_file_name,
+                backup_time: tracked.backup_time,
+                backup_path,
+            }
+        })
+        .collect()
+}
+
+/// Check that a name is a single path segment (no separators or traversal)
+fn is_safe_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains('/')
+        && !name.contains('\\')
+        && !name.contains('\0')
+}
+
+/// Read the content of a backup, if it was stored on disk
+pub(crate) fn read_backup_content(backup: &FileHistoryBackup) -> Option<String> {
+    backup
+        .backup_path
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path).ok())
+}
+
+/// Collect all file-history backups recorded in a single session file
+#[allow(unsafe_code)] // Required for mmap performance optimization
+fn collect_backups_from_file(
+    file_path: &PathBuf,
+    file_history_dir: Option<&Path>,
+) -> Vec<FileHistoryBackup> {
+    let Ok(file) = fs::File::open(file_path) else {
+        return Vec::new();
+    };
+
+    // SAFETY: We're only reading the file, and the file handle is kept open
+    // for the duration of the mmap's lifetime. Session files are append-only.
+    let Ok(mmap) = (unsafe { Mmap::map(&file) }) else {
+        return Vec::new();
+    };
+
+    let file_session_id = session_id_from_file(file_path).unwrap_or_default();
+    let mut history = SessionFileHistory::default();
+    let mut cwd: Option<String> = None;
+
+    for (start, end) in find_line_ranges(&mmap) {
+        let mut line_bytes = mmap[start..end].to_vec();
+        let Ok(entry) = simd_json::serde::from_slice::<RawLogEntry>(&mut line_bytes) else {
+            continue;
+        };
+
+        if entry.cwd.is_some() {
+            cwd.clone_from(&entry.cwd);
+        }
+
+        let session_id = entry.session_id.as_deref().unwrap_or(&file_session_id);
+        history.record(&entry, session_id, cwd.as_deref(), file_history_dir);
+    }
+
+    history.into_backups()
+}
+
+/// List the file-history backups recorded across all sessions of a project
+///
+/// When `file_path` is given, only backups of that file are returned.
+/// Results are sorted by backup time, then version (oldest first).
+#[tauri::command]
+pub async fn get_file_history_snapshots(
+    project_path: String,
+    file_path: Option<String>,
+) -> Result<Vec<FileHistoryBackup>, String> {
+    let file_history_dir = file_history_dir_for_project(&project_path);
+
+    let session_files: Vec<PathBuf> = WalkDir::new(&project_path)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .map(|e| e.path().to_path_buf())
+        .collect();
+
+    let mut backups: Vec<FileHistoryBackup> = session_files
+        .par_iter()
+        .flat_map(|path| collect_backups_from_file(path, file_history_dir.as_deref()))
+        .filter(|backup| match &file_path {
+            Some(fp) => &backup.file_path == fp,
+            None => true,
+        })
+        .collect();
+
+    backups.sort_by(|a, b| {
+        a.backup_time
+            .cmp(&b.backup_time)
+            .then(a.version.cmp(&b.version))
+    });
+
+    Ok(backups)
+}
+
+/// Read the content of a file-history backup
+///
+/// Security: `session_id` and `backup_file_name` must be plain file names,
+/// so the read cannot escape `~/.claude/file-history/{session_id}/`.
+#[tauri::command]
+pub async fn read_file_history_backup(
+    project_path: String,
+    session_id: String,
+    backup_file_name: String,
+) -> Result<String, String> {
+    if !is_safe_file_name(&session_id) || !is_safe_file_name(&backup_file_name) {
+        return Err("Invalid backup reference: path separators not allowed".to_string());
+    }
+
+    // The project must sit in a Claude `projects` folder, or the backup path
+    // could be pointed at any other `file-history` folder
+    let project = Path::new(&project_path);
+    let in_projects_folder = project.is_absolute()
+        && !project
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+        && project
+            .parent()
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == "projects");
+    let claude_folder = project
+        .parent()
+        .and_then(Path::parent)
+        .filter(|_| in_projects_folder)
+        .ok_or_else(|| "Invalid project path: not in a Claude projects folder".to_string())?;
+    let backup_path = claude_folder
+        .join("file-history")
+        .join(&session_id)
+        .join(&backup_file_name);
+    check_symlinks(claude_folder, project)?;
+    check_symlinks(claude_folder, &backup_path)?;
+
+    fs::read_to_string(&backup_path).map_err(|e| {
+        format!(
+            "Failed to read file history backup {}: {e}",
+            backup_path.display()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Create `.claude/projects/{project}` plus a session file and return the project path
+    fn create_project_with_session(temp_dir: &TempDir, session_id: &str, content: &str) -> PathBuf {
+        let project_dir = temp_dir.path().join(".claude/projects/-test-project");
+        fs::create_dir_all(&project_dir).unwrap();
+        let mut file = File::create(project_dir.join(format!("{session_id}.jsonl"))).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        project_dir
+    }
+
+    fn write_backup(temp_dir: &TempDir, session_id: &str, name: &str, content: &str) {
+        let dir = temp_dir
+            .path()
+            .join(".claude/file-history")
+            .join(session_id);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(name), content).unwrap();
+    }
+
+    #[test]
+    fn test_parse_snapshot_entry() {
+        let entry: RawLogEntry = serde_json::from_str(
+            r#"{"type":"file-history-snapshot","messageId":"msg-1","snapshot":{"messageId":"msg-1","trackedFileBackups":{"/p/a.rs":{"backupFileName":"abc@v1","version":1,"backupTime":"2025-06-26T10:00:00Z"},"/p/new.rs":{"backupFileName":null,"version":1,"backupTime":"2025-06-26T10:00:00Z"}},"timestamp":"2025-06-26T10:00:00Z"},"isSnapshotUpdate":false}"#,
+        )
+        .unwrap();
+
+        let mut backups = parse_snapshot_entry(
+            &entry,
+            "session-1",
+            None,
+            Some(Path::new("/h/file-history")),
+        );
+        backups.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].file_path, "/p/a.rs");
+        assert_eq!(backups[0].message_id, "msg-1");
+        assert_eq!(
+            backups[0].backup_path.as_deref(),
+            Some("/h/file-history/session-1/abc@v1")
+        );
+        // File did not exist when the snapshot was taken
+        assert_eq!(backups[1].file_path, "/p/new.rs");
+        assert!(backups[1].backup_file_name.is_none());
+        assert!(backups[1].backup_path.is_none());
+    }
+
+    #[test]
+    fn test_parse_snapshot_entry_resolves_relative_paths() {
+        let entry: RawLogEntry = serde_json::from_str(
+            r#"{"type":"file-history-snapshot","messageId":"msg-1","snapshot":{"trackedFileBackups":{"src/a.rs":{"backupFileName":"abc@v1","version":1}}}}"#,
+        )
+        .unwrap();
+
+        let backups = parse_snapshot_entry(&entry, "s", Some("/work/repo"), None);
+        assert_eq!(backups.len(), 1);
+        assert_eq!(
+            Path::new(&backups[0].file_path),
+            Path::new("/work/repo/src/a.rs")
+        );
+    }
+
+    #[test]
+    fn test_parse_snapshot_entry_rejects_unsafe_backup_names() {
+        let entry: RawLogEntry = serde_json::from_str(
+            r#"{"type":"file-history-snapshot","messageId":"msg-1","snapshot":{"trackedFileBackups":{"/p/a.rs":{"backupFileName":"../../etc/passwd","version":1}}}}"#,
+        )
+        .unwrap();
+
+        let backups = parse_snapshot_entry(&entry, "s", None, Some(Path::new("/h/fh")));
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].backup_path.is_none());
+    }
+
+    #[test]
+    fn test_session_file_history_keeps_highest_version() {
+        let first: RawLogEntry = serde_json::from_str(
+            r#"{"type":"file-history-snapshot","messageId":"msg-1","snapshot":{"trackedFileBackups":{"/p/a.rs":{"backupFileName":"abc@v1","version":1}}},"isSnapshotUpdate":false}"#,
+        )
+        .unwrap();
+        let update: RawLogEntry = serde_json::from_str(
+            r#"{"type":"file-history-snapshot","messageId":"msg-1","snapshot":{"trackedFileBackups":{"/p/a.rs":{"backupFileName":"abc@v2","version":2}}},"isSnapshotUpdate":true}"#,
+        )
+        .unwrap();
+
+        let mut history = SessionFileHistory::default();
+        history.record(&update, "s", None, None);
+        history.record(&first, "s", None, None);
+
+        let backup = history.find("msg-1", "/p/a.rs").unwrap();
+        assert_eq!(backup.version, 2);
+        assert_eq!(backup.backup_file_name.as_deref(), Some("abc@v2"));
+    }
+
+    #[test]
+    fn test_file_history_dir_for_project() {
+        let dir = file_history_dir_for_project("/home/u/.claude/projects/-home-u-repo").unwrap();
+        assert_eq!(dir, PathBuf::from("/home/u/.claude/file-history"));
+    }
+
+    #[tokio::test]
+    async fn test_get_file_history_snapshots_filters_by_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let content = r#"{"type":"file-history-snapshot","messageId":"msg-1","snapshot":{"trackedFileBackups":{"/p/a.rs":{"backupFileName":"a@v1","version":1,"backupTime":"2025-06-26T10:00:00Z"},"/p/b.rs":{"backupFileName":"b@v1","version":1,"backupTime":"2025-06-26T10:00:00Z"}}}}
+{"type":"file-history-snapshot","messageId":"msg-2","snapshot":{"trackedFileBackups":{"/p/a.rs":{"backupFileName":"a@v2","version":2,"backupTime":"2025-06-26T10:05:00Z"}}}}"#;
+        let project_dir = create_project_with_session(&temp_dir, "session-1", content);
+
+        let backups = get_file_history_snapshots(
+            project_dir.to_string_lossy().to_string(),
+            Some("/p/a.rs".to_string()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].version, 1);
+        assert_eq!(backups[1].version, 2);
+        assert_eq!(backups[1].message_id, "msg-2");
+        assert_eq!(backups[1].session_id, "session-1");
+    }
+
+    #[tokio::test]
+    async fn test_read_file_history_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = create_project_with_session(&temp_dir, "session-1", "");
+        write_backup(&temp_dir, "session-1", "a@v1", "before content");
+
+        let content = read_file_history_backup(
+            project_dir.to_string_lossy().to_string(),
+            "session-1".to_string(),
+            "a@v1".to_string(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(content, "before content");
+    }
+
+    #[tokio::test]
+    async fn test_read_file_history_backup_rejects_traversal() {
+        let result = read_file_history_backup(
+            "/home/u/.claude/projects/p".to_string(),
+            "..".to_string(),
+            "passwd".to_string(),
+        )
+        .await;
+        assert!(result.is_err());
+
+        let result = read_file_history_backup(
+            "/home/u/.claude/projects/p".to_string(),
+            "session".to_string(),
+            "../../etc/passwd".to_string(),
+        )
+        .await;
+        assert!(result.unwrap_err().contains("path separators"));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_history_backup_requires_projects_folder() {
+        let temp_dir = TempDir::new().unwrap();
+        write_backup(&temp_dir, "session-1", "a@v1", "before content");
+
+        // `<grandparent>/file-history` of a folder outside `projects/`
+        let elsewhere = temp_dir.path().join(".claude/other/p");
+        for project_path in [
+            elsewhere.to_string_lossy().to_string(),
+            "relative/projects/p".to_string(),
+            format!("{}/../projects/p", elsewhere.display()),
+        ] {
+            let result =
+                read_file_history_backup(project_path, "session-1".to_string(), "a@v1".to_string())
+                    .await;
+            assert!(result
+                .unwrap_err()
+                .contains("not in a Claude projects folder"));
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Fork a session at a message into a new resumable session file
+//!
+//! The new file holds the parent chain from the root to the chosen message,
+//! with a fresh `sessionId` and fresh uuids (links remapped accordingly), so
+//! `claude --resume` lists it next to the original in the same project folder.
+
+use super::validate_claude_path;
+use crate::models::ForkSessionResult;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Top-level fields that reference another entry's uuid
+const UUID_LINK_FIELDS: [&str; 4] = [
+    "parentUuid",
+    "logicalParentUuid",
+    "leafUuid",
+    "sourceToolAssistantUUID",
+];
+
+fn str_field<'a>(entry: &'a Value, key: &str) -> Option<&'a str> {
+    entry.get(key).and_then(Value::as_str)
+}
+
+/// Uuid of the message a `file-history-snapshot` entry belongs to
+fn snapshot_message_id(entry: &Value) -> Option<&str> {
+    (str_field(entry, "type") == Some("file-history-snapshot"))
+        .then(|| str_field(entry, "messageId"))
+        .flatten()
+}
+
+fn has_tool_use(entry: &Value) -> bool {
+    entry
+        .pointer("/message/content")
+        .and_then(Value::as_array)
+        .is_some_and(|blocks| {
+            blocks
+                .iter()
+                .any(|b| b.get("type").and_then(Value::as_str) == Some("tool_use"))
+        })
+}
+
+/// Rewrite a session's content into the chain ending at `message_uuid`
+///
+/// Returns the new lines and whether the last message is waiting for tool results.
+fn fork_content(
+    content: &str,
+    message_uuid: &str,
+    new_session_id: &str,
+) -> Result<(Vec<String>, bool), String> {
+    let entries: Vec<Value> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+
+    let by_uuid: HashMap<&str, &Value> = entries
+        .iter()
+        .filter_map(|e| Some((str_field(e, "uuid")?, e)))
+        .collect();
+    let target = by_uuid
+        .get(message_uuid)
+        .ok_or_else(|| format!("Message not found in session: {message_uuid}"))?;
+
+    // Walk up the parent chain; a cycle would mean a corrupt file
+    let mut chain: HashSet<&str> = HashSet::new();
+    let mut current = Some(message_uuid);
+    while let Some(uuid) = current {
+        if !chain.insert(uuid) {
+            return Err(format!("Parent chain loops at message {uuid}"));
+        }
+        current = by_uuid
+            .get(uuid)
+            .and_then(|e| str_field(e, "parentUuid"))
+            .filter(|parent| by_uuid.contains_key(parent));
+    }
+
+    let remap: HashMap<&str, String> = chain
+        .iter()
+        .map(|uuid| (*uuid, Uuid::new_v4().to_string()))
+        .collect();
+
+    let mut lines = Vec::new();
+    // Duplicate lines (same uuid written twice on resume) are kept once
+    let mut written: HashSet<&str> = HashSet::new();
+    for entry in &entries {
+        let keep = if let Some(uuid) = str_field(entry, "uuid") {
+            chain.contains(uuid) && written.insert(uuid)
+        } else {
+            str_field(entry, "leafUuid")
+                .or_else(|| snapshot_message_id(entry))
+                .is_some_and(|uuid| chain.contains(uuid))
+        };
+        if !keep {
+            continue;
+        }
+
+        let mut entry = entry.clone();
+        let Some(object) = entry.as_object_mut() else {
+            continue;
+        };
+        if object.contains_key("sessionId") {
+            object.insert("sessionId".to_string(), Value::from(new_session_id));
+        }
+        for field in std::iter::once("uuid").chain(UUID_LINK_FIELDS) {
+            if let Some(Value::String(old)) = object.get(field) {
+                let mapped = remap
+                    .get(old.as_str())
+                    .map_or(Value::Null, |new| Value::from(new.as_str()));
+                object.insert(field.to_string(), mapped);
+            }
+        }
+        if object.get("type").and_then(Value::as_str) == Some("file-history-snapshot") {
+            for pointer in ["/messageId", "/snapshot/messageId"] {
+                if let Some(slot) = entry.pointer_mut(pointer) {
+                    if let Some(new) = slot.as_str().and_then(|old| remap.get(old)) {
+                        *slot = Value::from(new.as_str());
+                    }
+                }
+            }
+        }
+        lines.push(
+            serde_json::to_string(&entry).map_err(|e| format!("Failed to serialize entry: {e}"))?,
+        );
+    }
+
+    Ok((lines, has_tool_use(target)))
+}
+
+fn fork_session_file(file_path: &str, message_uuid: &str) -> Result<ForkSessionResult, String> {
+    if !Path::new(file_path).is_file() {
+        return Err(format!("Session file not found: {file_path}"));
+    }
+    validate_claude_path(file_path)?;
+    let source = Path::new(file_path);
+    let project_dir = source.parent().ok_or("Invalid session file path")?;
+    let source_session_id = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or("Invalid session file name")?
+        .to_string();
+
+    let content =
+        fs::read_to_string(source).map_err(|e| format!("Failed to read session file: {e}"))?;
+    let new_session_id = Uuid::new_v4().to_string();
+    let (lines, ends_with_pending_tool_use) =
+        fork_content(&content, message_uuid, &new_session_id)?;
+
+    let new_path = project_dir.join(format!("{new_session_id}.jsonl"));
+    let mut file = fs::File::options()
+        .write(true)
+        .create_new(true)
+        .open(&new_path)
+        .map_err(|e| format!("Failed to create session file: {e}"))?;
+    let mut output = lines.join("\n");
+    output.push('\n');
+    if let Err(e) = file
+        .write_all(output.as_bytes())
+        .and_then(|()| file.sync_all())
+    {
+        let _ = fs::remove_file(&new_path);
+        return Err(format!("Failed to write session file: {e}"));
+    }
+
+    Ok(ForkSessionResult {
+        source_session_id,
+        new_session_id,
+        file_path: new_path.to_string_lossy().to_string(),
+        entry_count: lines.len(),
+        ends_with_pending_tool_use,
+    })
+}
+
+/// Fork a session at `message_uuid` into a new session in the same project
+///
+/// The new session contains the conversation from its start up to and
+/// including that message, following the parent chain (other branches are
+/// dropped).
+#[tauri::command]
+pub async fn fork_session(
+    file_path: String,
+    message_uuid: String,
+) -> Result<ForkSessionResult, String> {
+    tauri::async_runtime::spawn_blocking(move || fork_session_file(&file_path, &message_uuid))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::env;
+    use tempfile::TempDir;
+
+    fn session_lines() -> String {
+        [
+            json!({"type": "summary", "summary": "Fix login", "leafUuid": "a2"}),
+            json!({"type": "user", "uuid": "u1", "parentUuid": null, "sessionId": "orig",
+                   "message": {"role": "user", "content": "fix the login bug"}}),
+            json!({"type": "file-history-snapshot", "messageId": "u1",
+                   "snapshot": {"messageId": "u1", "trackedFileBackups": {}}}),
+            json!({"type": "assistant", "uuid": "a1", "parentUuid": "u1", "sessionId": "orig",
+                   "message": {"role": "assistant", "content": [
+                       {"type": "tool_use", "id": "t1", "name": "Read", "input": {"file_path": "/src/login.ts"}}
+                   ]}}),
+            json!({"type": "user", "uuid": "r1", "parentUuid": "a1", "sessionId": "orig",
+                   "sourceToolAssistantUUID": "a1",
+                   "message": {"role": "user", "content": [
+                       {"type": "tool_result", "tool_use_id": "t1", "content": "..."}
+                   ]}}),
+            // Two branches from r1: the first answer was retried
+            json!({"type": "assistant", "uuid": "a2", "parentUuid": "r1", "sessionId": "orig",
+                   "message": {"role": "assistant", "content": [{"type": "text", "text": "Fixed."}]}}),
+            json!({"type": "assistant", "uuid": "b2", "parentUuid": "r1", "sessionId": "orig",
+                   "message": {"role": "assistant", "content": [{"type": "text", "text": "Rewrote it all."}]}}),
+            json!({"type": "user", "uuid": "b3", "parentUuid": "b2", "sessionId": "orig",
+                   "message": {"role": "user", "content": "no, revert that"}}),
+        ]
+        .iter()
+        .fold(String::new(), |mut content, entry| {
+            content.push_str(&entry.to_string());
+            content.push('\n');
+            content
+        })
+    }
+
+    #[test]
+    fn test_fork_keeps_only_the_parent_chain() {
+        let (lines, pending) = fork_content(&session_lines(), "a2", "new-session").unwrap();
+        assert!(!pending);
+        let entries: Vec<Value> = lines
+            .iter()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let types: Vec<_> = entries
+            .iter()
+            .map(|e| e["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "summary",
+                "user",
+                "file-history-snapshot",
+                "assistant",
+                "user",
+                "assistant"
+            ]
+        );
+
+        let messages: Vec<&Value> = entries.iter().filter(|e| e.get("uuid").is_some()).collect();
+        assert_eq!(messages[0]["parentUuid"], Value::Null);
+        for pair in messages.windows(2) {
+            assert_eq!(pair[1]["parentUuid"], pair[0]["uuid"]);
+        }
+        assert!(messages.iter().all(|m| m["sessionId"] == "new-session"));
+        assert!(messages
+            .iter()
+            .all(|m| !["u1", "a1", "r1", "a2"].contains(&m["uuid"].as_str().unwrap())));
+
+        // Links outside the message chain follow the new uuids
+        assert_eq!(entries[0]["leafUuid"], messages[3]["uuid"]);
+        assert_eq!(entries[2]["messageId"], messages[0]["uuid"]);
+        assert_eq!(entries[2]["snapshot"]["messageId"], messages[0]["uuid"]);
+        assert_eq!(messages[2]["sourceToolAssistantUUID"], messages[1]["uuid"]);
+    }
+
+    #[test]
+    fn test_fork_other_branch_and_pending_tool_use() {
+        let (lines, _) = fork_content(&session_lines(), "b3", "s").unwrap();
+        let texts: Vec<_> = lines.iter().filter(|l| l.contains("Fixed.")).collect();
+        assert!(texts.is_empty());
+        assert_eq!(lines.len(), 6); // no summary: its leaf is on the other branch
+
+        let (lines, pending) = fork_content(&session_lines(), "a1", "s").unwrap();
+        assert!(pending);
+        assert_eq!(lines.len(), 3);
+
+        assert!(fork_content(&session_lines(), "missing", "s")
+            .unwrap_err()
+            .contains("missing"));
+    }
+
+    #[test]
+    fn test_fork_session_file_writes_new_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let home = temp_dir.path().canonicalize().unwrap();
+        env::set_var("HOME", &home);
+        let project_dir = home.join(".claude/projects/-test-project");
+        fs::create_dir_all(&project_dir).unwrap();
+        let source = project_dir.join("orig.jsonl");
+        fs::write(&source, session_lines()).unwrap();
+
+        let result = fork_session_file(&source.to_string_lossy(), "a2").unwrap();
+        assert_eq!(result.source_session_id, "orig");
+        assert_eq!(result.entry_count, 6);
+        let new_path = Path::new(&result.file_path);
+        assert_eq!(new_path.parent(), Some(project_dir.as_path()));
+        assert_eq!(
+            new_path.file_stem().unwrap().to_string_lossy(),
+            result.new_session_id
+        );
+        let written = fs::read_to_string(new_path).unwrap();
+        assert!(written.ends_with('\n'));
+        assert!(written.contains(&result.new_session_id));
+        assert_eq!(fs::read_to_string(&source).unwrap(), session_lines());
+    }
+}
@@ -1,5 +1,8 @@
 //! Session loading functions
 
+use super::auto_tags::{evaluate_auto_tags, AutoTagState};
+use crate::commands::metadata::read_auto_tag_rules;
+use crate::commands::trusted_symlinks::check_session_path;
 use crate::models::{ClaudeMessage, ClaudeSession, MessagePage, RawLogEntry};
 use crate::utils::{extract_project_name, find_line_ranges, find_line_starts};
 use chrono::{DateTime, Utc};
@@ -8,7 +11,7 @@ use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs;
 use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use uuid::Uuid;
 use walkdir::WalkDir;
@@ -30,6 +33,9 @@ struct CachedSessionMetadata {
     has_tool_use: bool,
     /// Whether errors were detected (for incremental updates)
     has_errors: bool,
+    /// Auto-tag rule results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auto_tags: Option<AutoTagState>,
 }
 
 /// Session metadata cache file structure
@@ -69,6 +75,49 @@ fn save_cache(project_path: &str, cache: &SessionMetadataCache) {
     }
 }
 
+/// Carry cache entries of sessions moved between project folders
+///
+/// `moved` maps old file paths to new ones. Entries are re-keyed and their
+/// cached session points at the new file; entries already present in the
+/// target cache win. The source cache file is removed. Returns the number of
+/// entries carried over.
+pub(crate) fn merge_session_cache(
+    source_project: &str,
+    target_project: &str,
+    moved: &HashMap<String, String>,
+) -> usize {
+    let source = load_cache(source_project);
+    let mut target = load_cache(target_project);
+    let raw_project_name = PathBuf::from(target_project)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Unknown")
+        .to_string();
+    let project_name = extract_project_name(&raw_project_name);
+
+    let mut merged = 0;
+    for (old_path, mut entry) in source.entries {
+        let Some(new_path) = moved.get(&old_path) else {
+            continue;
+        };
+        if target.entries.contains_key(new_path) {
+            continue;
+        }
+        if let Some(session) = entry.session.as_mut() {
+            session.session_id.clone_from(new_path);
+            session.file_path.clone_from(new_path);
+            session.project_name.clone_from(&project_name);
+        }
+        target.entries.insert(new_path.clone(), entry);
+        merged += 1;
+    }
+
+    target.version = CACHE_VERSION;
+    save_cache(target_project, &target);
+    let _ = fs::remove_file(get_cache_path(source_project));
+    merged
+}
+
 /// Get file modification time as Unix timestamp
 fn get_modified_time(path: &PathBuf) -> Option<u64> {
     path.metadata()
@@ -449,6 +498,7 @@ fn extract_session_metadata_internal(
             has_tool_use,
             has_errors,
             summary: final_summary,
+            auto_tags: Vec::new(),
         },
         sidechain_count,
         final_byte_offset: file_size,
@@ -468,7 +518,7 @@ const SYSTEM_MESSAGE_TYPES: [&str; 4] = [
 
 /// Check if a message type is a system type (should be excluded)
 #[inline]
-fn is_system_message_type(message_type: &str) -> bool {
+pub(super) fn is_system_message_type(message_type: &str) -> bool {
     SYSTEM_MESSAGE_TYPES.contains(&message_type)
 }
 
@@ -584,16 +634,18 @@ pub async fn load_project_sessions(
     let start_time = std::time::Instant::now();
 
     let exclude = exclude_sidechain.unwrap_or(false);
+    check_session_path(Path::new(&project_path))?;
 
     // 1. Load existing cache
     let mut cache = load_cache(&project_path);
     let mut cache_updated = false;
 
-    // 2. Collect all JSONL file paths
+    // 2. Collect all JSONL file paths; links are only followed into trusted targets
     let file_paths: Vec<PathBuf> = WalkDir::new(&project_path)
         .into_iter()
         .filter_map(std::result::Result::ok)
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .filter(|e| !e.path_is_symlink() || check_session_path(e.path()).is_ok())
         .map(|e| e.path().to_path_buf())
         .collect();
 
@@ -693,6 +745,7 @@ pub async fn load_project_sessions(
     let mut sessions: Vec<ClaudeSession> = Vec::with_capacity(results.len());
 
     for (strategy, result_opt) in results {
+        let appended = matches!(strategy, FileParseStrategy::Incremental(..));
         match strategy {
             FileParseStrategy::UseCached(session, sidechain_count) => {
                 let mut session_clone = session;
@@ -721,6 +774,15 @@ pub async fn load_project_sessions(
                         ),
                         None => (None, 0, 0, false, false),
                     };
+                // Rule results stay valid for appended lines only
+                let auto_tags = if appended {
+                    cache
+                        .entries
+                        .get(&path_str)
+                        .and_then(|entry| entry.auto_tags.clone())
+                } else {
+                    None
+                };
 
                 cache.entries.insert(
                     path_str,
@@ -732,6 +794,7 @@ pub async fn load_project_sessions(
                         sidechain_count,
                         has_tool_use,
                         has_errors,
+                        auto_tags,
                     },
                 );
                 cache_updated = true;
@@ -778,7 +841,30 @@ pub async fn load_project_sessions(
         }
     }
 
-    // 9. Save updated cache
+    // 9. Derive tags from the user's auto-tag rules
+    let rules = read_auto_tag_rules();
+    if !rules.is_empty() {
+        let updates: Vec<(String, AutoTagState)> = sessions
+            .par_iter_mut()
+            .filter_map(|session| {
+                let previous = cache
+                    .entries
+                    .get(&session.file_path)
+                    .and_then(|entry| entry.auto_tags.as_ref());
+                let state = evaluate_auto_tags(Path::new(&session.file_path), &rules, previous);
+                session.auto_tags.clone_from(&state.tags);
+                (previous != Some(&state)).then(|| (session.file_path.clone(), state))
+            })
+            .collect();
+        for (path, state) in updates {
+            if let Some(entry) = cache.entries.get_mut(&path) {
+                entry.auto_tags = Some(state);
+                cache_updated = true;
+            }
+        }
+    }
+
+    // 10. Save updated cache
     if cache_updated {
         cache.version = CACHE_VERSION;
         save_cache(&project_path, &cache);
@@ -923,7 +1009,7 @@ fn parse_line_to_message(
 
 /// Parse a single line using simd-json for faster parsing
 /// Returns None if the line is empty or fails to parse
-fn parse_line_simd(
+pub(super) fn parse_line_simd(
     line_num: usize,
     line: &mut [u8],
     include_summary: bool,
@@ -1068,6 +1154,8 @@ pub async fn load_session_messages(session_path: String) -> Result<Vec<ClaudeMes
     #[cfg(debug_assertions)]
     let start_time = std::time::Instant::now();
 
+    check_session_path(Path::new(&session_path))?;
+
     // Use memory-mapped file for faster I/O
     let file =
         fs::File::open(&session_path).map_err(|e| format!("Failed to open session file: {e}"))?;
@@ -1146,6 +1234,52 @@ fn classify_line_fast(line: &[u8], exclude_sidechain: bool) -> bool {
     false
 }
 
+/// Paginated message count, and index and message for each uuid found
+pub(crate) type LocatedMessages = (usize, HashMap<String, (usize, ClaudeMessage)>);
+
+/// Find messages by uuid, numbered the way `load_session_messages_paginated` counts them
+///
+/// Indexes count from the oldest message.
+#[allow(unsafe_code)] // Required for mmap performance optimization
+pub(crate) fn locate_messages(
+    session_path: &str,
+    uuids: &[&str],
+    exclude_sidechain: bool,
+) -> Result<LocatedMessages, String> {
+    check_session_path(Path::new(session_path))?;
+    let file =
+        fs::File::open(session_path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    // SAFETY: read-only mapping, the file handle outlives the map
+    let mmap = unsafe { Mmap::map(&file) }
+        .map_err(|e| format!("Failed to memory-map session file: {e}"))?;
+
+    let mut total_count = 0;
+    let mut found = HashMap::new();
+    for (line_num, &(start, end)) in find_line_ranges(&mmap).iter().enumerate() {
+        let line = &mmap[start..end];
+        if !classify_line_fast(line, exclude_sidechain) {
+            continue;
+        }
+        let index = total_count;
+        total_count += 1;
+
+        // Cheap substring check before parsing the whole line
+        if !uuids
+            .iter()
+            .any(|uuid| memchr::memmem::find(line, uuid.as_bytes()).is_some())
+        {
+            continue;
+        }
+        let mut line_bytes = line.to_vec();
+        if let Some(message) = parse_line_simd(line_num, &mut line_bytes, false) {
+            if uuids.contains(&message.uuid.as_str()) {
+                found.insert(message.uuid.clone(), (index, message));
+            }
+        }
+    }
+    Ok((total_count, found))
+}
+
 #[tauri::command]
 #[allow(unsafe_code)] // Required for mmap performance optimization
 pub async fn load_session_messages_paginated(
@@ -1157,6 +1291,8 @@ pub async fn load_session_messages_paginated(
     #[cfg(debug_assertions)]
     let start_time = std::time::Instant::now();
 
+    check_session_path(Path::new(&session_path))?;
+
     // Use memory-mapped file for faster I/O
     let file =
         fs::File::open(&session_path).map_err(|e| format!("Failed to open session file: {e}"))?;
@@ -1247,6 +1383,8 @@ pub async fn get_session_message_count(
     session_path: String,
     exclude_sidechain: Option<bool>,
 ) -> Result<usize, String> {
+    check_session_path(Path::new(&session_path))?;
+
     // Use memory-mapped file for faster I/O
     let file =
         fs::File::open(&session_path).map_err(|e| format!("Failed to open session file: {e}"))?;
@@ -1793,4 +1931,34 @@ mod tests {
         assert_eq!(usage.cache_creation_input_tokens, Some(20));
         assert_eq!(usage.cache_read_input_tokens, Some(10));
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_loaders_skip_untrusted_symlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join(".claude/projects/-test-project");
+        let elsewhere = temp_dir.path().join("elsewhere");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(&elsewhere).unwrap();
+
+        let content = format!(
+            "{}\n",
+            create_sample_user_message("uuid-1", "session-1", "Hello")
+        );
+        std::fs::write(project.join("own.jsonl"), &content).unwrap();
+        std::fs::write(elsewhere.join("other.jsonl"), &content).unwrap();
+        let linked = project.join("linked.jsonl");
+        std::os::unix::fs::symlink(elsewhere.join("other.jsonl"), &linked).unwrap();
+
+        let sessions = load_project_sessions(project.to_string_lossy().to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions[0].file_path.ends_with("own.jsonl"));
+
+        let err = load_session_messages(linked.to_string_lossy().to_string())
+            .await
+            .unwrap_err();
+        assert!(err.contains("not a trusted symlink target"));
+    }
 }
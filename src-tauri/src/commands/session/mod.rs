@@ -2,17 +2,34 @@
 //!
 //! This module contains all session-related Tauri commands organized into submodules:
 //! - `load`: Session and message loading functions
+//! - `auto_tags`: Tags derived from session content by user-defined rules
 //! - `search`: Message search functions
+//! - `tail`: Messages appended to a session file since a byte offset
 //! - `edits`: File edit tracking and restore functions
+//! - `file_history`: Claude Code file-history snapshot backups
+//! - `file_activity`: Cross-session "which sessions touched this file" queries
+//! - `fork`: Fork a session at a message into a new session file
 //! - `rename`: Native session renaming functions
+//! - `shell_ops`: File operations detected in Bash tool commands
 
+mod auto_tags;
 mod edits;
+mod file_activity;
+mod file_history;
+mod fork;
 mod load;
 mod rename;
 mod search;
+mod shell_ops;
+mod tail;
 
 // Re-export all commands
+pub(crate) use auto_tags::session_uses_model;
 pub use edits::*;
+pub use file_activity::*;
+pub use file_history::*;
+pub use fork::*;
 pub use load::*;
 pub use rename::*;
 pub use search::*;
+pub(crate) use tail::{complete_len, read_appended_messages};
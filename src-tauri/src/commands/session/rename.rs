@@ -3,11 +3,13 @@
 //! Provides functionality to rename Claude Code sessions by modifying
 //! the first user message in the session JSONL file.
 
+use crate::commands::journal::journaled_write;
+use crate::commands::trusted_symlinks::{self, check_symlinks};
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Write};
+use std::fs;
+use std::ops::Range;
 use tauri::command;
 
 lazy_static! {
@@ -49,7 +51,7 @@ impl std::fmt::Display for RenameError {
                 write!(f, "No user message found in session")
             }
             RenameError::UnsupportedContentFormat => {
-                write!(f, "Message content format not supported (array content)")
+                write!(f, "Message content format not supported (no text block)")
             }
             RenameError::InvalidTitle(msg) => write!(f, "Invalid title: {msg}"),
         }
@@ -86,83 +88,103 @@ pub async fn rename_session_native(
         .to_string());
     }
 
-    // 4. Read all lines from JSONL file
-    let file =
-        File::open(&file_path).map_err(|e| RenameError::IoError(e.to_string()).to_string())?;
-    let reader = BufReader::new(file);
-    let mut lines: Vec<String> = reader
-        .lines()
-        .collect::<Result<_, _>>()
-        .map_err(|e| RenameError::IoError(e.to_string()).to_string())?;
+    // 4. Rewrite through the journal: the original is snapshotted for revert, and
+    //    the rewrite is retried if Claude Code appends to the session meanwhile
+    let mut titles: Option<(String, String)> = None;
+    let (operation, description) = if new_title.trim().is_empty() {
+        ("reset_name", "Reset session name".to_string())
+    } else {
+        (
+            "rename",
+            format!("Rename session to \"{}\"", new_title.trim()),
+        )
+    };
+    journaled_write(
+        std::path::Path::new(&file_path),
+        operation,
+        &description,
+        |file_content| {
+            let (new_content, previous, new) = rename_in_content(file_content, &new_title)?;
+            titles = Some((previous, new));
+            Ok(new_content)
+        },
+    )?;
+    let (current_message, new_message) = titles
+        .ok_or_else(|| RenameError::IoError("Rename was not applied".to_string()).to_string())?;
+
+    Ok(NativeRenameResult {
+        success: true,
+        previous_title: current_message,
+        new_title: new_message,
+        file_path,
+    })
+}
+
+/// Applies the title to the first user message of a session file's content.
+///
+/// Returns the new file content with only that message's content string
+/// changed, plus the previous and new message text.
+fn rename_in_content(
+    file_content: &str,
+    new_title: &str,
+) -> Result<(String, String, String), String> {
+    let lines: Vec<String> = file_content.lines().map(str::to_string).collect();
 
     if lines.is_empty() {
         return Err(RenameError::EmptySession.to_string());
     }
 
-    // 5. Find first user message (type: "user", not isMeta)
+    // Find first user message (type: "user", not isMeta)
     let user_message_index = find_first_user_message_index(&lines)?;
+    let line = &lines[user_message_index];
 
-    // 6. Parse the user message line as JSON
-    let mut user_message: serde_json::Value = serde_json::from_str(&lines[user_message_index])
+    // Parse the user message line as JSON
+    let mut user_message: serde_json::Value = serde_json::from_str(line)
         .map_err(|e| RenameError::InvalidJsonFormat(e.to_string()).to_string())?;
 
-    // 7. Extract current message content - handle nested structure
+    // Extract current message content - handle nested structure
     let current_message = extract_message_content(&user_message).ok_or_else(|| {
         RenameError::InvalidJsonFormat("No 'message' field found".to_string()).to_string()
     })?;
 
-    // 8. Strip existing bracket prefix if present
+    // Strip existing bracket prefix if present
     let base_message = strip_title_prefix(&current_message);
 
-    // 9. Construct new message with title prefix
+    // Construct new message with title prefix
     let new_message = if new_title.trim().is_empty() {
         base_message.clone()
     } else {
         format!("[{}] {}", new_title.trim(), base_message)
     };
 
-    // 10. Update JSON object - handle nested structure
+    // Rewrite only the content string within the raw line
+    let new_line = replace_message_content_in_line(line, &new_message)
+        .ok_or_else(|| RenameError::UnsupportedContentFormat.to_string())?;
+
+    // Verify the splice matches the structured update
     if !update_message_content(&mut user_message, &new_message) {
         return Err(RenameError::UnsupportedContentFormat.to_string());
     }
-
-    // 11. Serialize back to JSON string
-    lines[user_message_index] = serde_json::to_string(&user_message)
+    let spliced: serde_json::Value = serde_json::from_str(&new_line)
         .map_err(|e| RenameError::InvalidJsonFormat(e.to_string()).to_string())?;
-
-    // 12. Write atomically (write to temp, then rename)
-    let temp_path = format!("{file_path}.tmp");
-    {
-        let mut temp_file = File::create(&temp_path)
-            .map_err(|e| RenameError::IoError(e.to_string()).to_string())?;
-
-        for (i, line) in lines.iter().enumerate() {
-            if i > 0 {
-                writeln!(temp_file).map_err(|e| RenameError::IoError(e.to_string()).to_string())?;
-            }
-            write!(temp_file, "{line}")
-                .map_err(|e| RenameError::IoError(e.to_string()).to_string())?;
-        }
-    }
-
-    // 13. Atomic rename (Windows compatibility: remove existing file first)
-    #[cfg(target_os = "windows")]
-    {
-        if std::path::Path::new(&file_path).exists() {
-            fs::remove_file(&file_path)
-                .map_err(|e| RenameError::IoError(e.to_string()).to_string())?;
-        }
+    if spliced != user_message {
+        return Err(RenameError::InvalidJsonFormat(
+            "Rewritten message does not match expected content".to_string(),
+        )
+        .to_string());
     }
 
-    fs::rename(&temp_path, &file_path)
-        .map_err(|e| RenameError::IoError(e.to_string()).to_string())?;
-
-    Ok(NativeRenameResult {
-        success: true,
-        previous_title: current_message,
-        new_title: new_message,
-        file_path,
-    })
+    // Splice the line back in; everything else stays byte-identical
+    let line_range = line_byte_range(file_content, user_message_index)
+        .ok_or_else(|| RenameError::IoError("Message line not found".to_string()).to_string())?;
+    let new_content = format!(
+        "{}{}{}",
+        &file_content[..line_range.start],
+        new_line,
+        &file_content[line_range.end..]
+    );
+
+    Ok((new_content, current_message, new_message))
 }
 
 /// Validates that the file path is within the ~/.claude directory.
@@ -170,9 +192,10 @@ pub async fn rename_session_native(
 ///
 /// Security checks performed:
 /// 1. Path must be absolute
-/// 2. No symlinks allowed in any path component
+/// 2. No symlinks from ~/.claude down, except ones resolving into a trusted
+///    symlink target; the file itself can't be a symlink
 /// 3. Filename must match pattern ^[A-Za-z0-9_-]+$
-fn validate_claude_path(file_path: &str) -> Result<(), String> {
+pub(crate) fn validate_claude_path(file_path: &str) -> Result<(), String> {
     let file_path_buf = std::path::PathBuf::from(file_path);
 
     // 1. Require absolute path
@@ -182,23 +205,26 @@ fn validate_claude_path(file_path: &str) -> Result<(), String> {
         );
     }
 
-    // 2. Block symlinks in path components
-    // Check each ancestor for symlinks before canonicalizing
-    let mut current = file_path_buf.as_path();
-    while let Some(parent) = current.parent() {
-        if parent.as_os_str().is_empty() {
-            break;
-        }
-        // Use symlink_metadata to avoid following symlinks
-        if let Ok(metadata) = fs::symlink_metadata(parent) {
-            if metadata.file_type().is_symlink() {
-                return Err(RenameError::PermissionDenied(
-                    "Symlinks are not allowed in path".to_string(),
-                )
-                .to_string());
-            }
-        }
-        current = parent;
+    // Resolved the same way as the file, so a trusted ~/.claude symlink still matches
+    let claude_dir =
+        trusted_symlinks::claude_dir().map_err(|e| RenameError::PermissionDenied(e).to_string())?;
+
+    // 2. Block symlinks in path components below ~/.claude (as given or
+    // resolved), unless they resolve into a target the user trusts (e.g.
+    // ~/.claude linked into a synced folder)
+    let given_claude_dir = dirs::home_dir().map(|home| home.join(".claude"));
+    let base = given_claude_dir
+        .iter()
+        .chain(std::iter::once(&claude_dir))
+        .find(|base| file_path_buf.starts_with(base))
+        .ok_or_else(|| {
+            RenameError::PermissionDenied(
+                "File path must be within ~/.claude directory".to_string(),
+            )
+            .to_string()
+        })?;
+    if let Some(parent) = file_path_buf.parent() {
+        check_symlinks(base, parent).map_err(|e| RenameError::PermissionDenied(e).to_string())?;
     }
 
     // Also check the final file itself for symlinks
@@ -225,19 +251,11 @@ fn validate_claude_path(file_path: &str) -> Result<(), String> {
         return Err(RenameError::PermissionDenied("Invalid filename".to_string()).to_string());
     }
 
-    // Canonicalize to resolve .. components (symlinks already blocked above)
+    // Canonicalize to resolve .. components and trusted symlinks
     let canonical_path = file_path_buf
         .canonicalize()
         .map_err(|e| RenameError::IoError(e.to_string()).to_string())?;
 
-    // Get home directory
-    let home_dir = dirs::home_dir().ok_or_else(|| {
-        RenameError::IoError("Cannot determine home directory".to_string()).to_string()
-    })?;
-
-    // Build the allowed claude directory path
-    let claude_dir = home_dir.join(".claude");
-
     // Verify the file is within ~/.claude
     if !canonical_path.starts_with(&claude_dir) {
         return Err(RenameError::PermissionDenied(
@@ -324,6 +342,189 @@ fn update_message_content(json: &mut serde_json::Value, new_content: &str) -> bo
     false
 }
 
+/// Byte range of line `index` in `content`, excluding its line terminator.
+/// Line numbering matches `str::lines`.
+fn line_byte_range(content: &str, index: usize) -> Option<Range<usize>> {
+    let mut start = 0;
+    for (i, segment) in content.split_inclusive('\n').enumerate() {
+        if i == index {
+            let line = segment.strip_suffix('\n').unwrap_or(segment);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            return Some(start..start + line.len());
+        }
+        start += segment.len();
+    }
+    None
+}
+
+/// Replaces the message content string inside a raw JSONL line.
+///
+/// Locates the same string `extract_message_content` reads (`message`,
+/// `message.content`, or the `text` of the first text block in array content)
+/// and splices in the newly encoded string, leaving every other byte of the
+/// line untouched. Returns None if the line has no such string.
+fn replace_message_content_in_line(line: &str, new_content: &str) -> Option<String> {
+    let span = message_content_span(line)?;
+    let encoded = serde_json::to_string(new_content).ok()?;
+    Some(format!(
+        "{}{}{}",
+        &line[..span.start],
+        encoded,
+        &line[span.end..]
+    ))
+}
+
+/// Byte span of the message content JSON string within a raw line
+fn message_content_span(line: &str) -> Option<Range<usize>> {
+    let scanner = JsonSpanScanner::new(line);
+    let root = scanner.skip_whitespace(0);
+    let message = scanner.object_field(root, "message")?;
+
+    match line.as_bytes()[message.start] {
+        b'"' => Some(message),
+        b'{' => {
+            let content = scanner.object_field(message.start, "content")?;
+            match line.as_bytes()[content.start] {
+                b'"' => Some(content),
+                b'[' => scanner
+                    .array_elements(content.start)?
+                    .into_iter()
+                    .filter(|item| line.as_bytes()[item.start] == b'{')
+                    .find(|item| {
+                        scanner
+                            .object_field(item.start, "type")
+                            .and_then(|t| serde_json::from_str::<String>(&line[t]).ok())
+                            .is_some_and(|t| t == "text")
+                    })
+                    .and_then(|item| scanner.object_field(item.start, "text"))
+                    .filter(|text| line.as_bytes()[text.start] == b'"'),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Minimal JSON scanner that reports byte spans of values without re-serializing
+struct JsonSpanScanner<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+}
+
+impl<'a> JsonSpanScanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            bytes: text.as_bytes(),
+        }
+    }
+
+    fn skip_whitespace(&self, mut pos: usize) -> usize {
+        while pos < self.bytes.len() && matches!(self.bytes[pos], b' ' | b'\t' | b'\n' | b'\r') {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// End position (exclusive) of the value starting at `pos`
+    fn value_end(&self, pos: usize) -> Option<usize> {
+        match *self.bytes.get(pos)? {
+            b'"' => self.string_end(pos),
+            b'{' | b'[' => {
+                let mut depth = 0usize;
+                let mut i = pos;
+                while i < self.bytes.len() {
+                    match self.bytes[i] {
+                        b'"' => {
+                            i = self.string_end(i)?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return Some(i + 1);
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                None
+            }
+            _ => {
+                let mut i = pos;
+                while i < self.bytes.len()
+                    && !matches!(
+                        self.bytes[i],
+                        b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r'
+                    )
+                {
+                    i += 1;
+                }
+                Some(i)
+            }
+        }
+    }
+
+    /// End position (exclusive) of the string starting at `pos`
+    fn string_end(&self, pos: usize) -> Option<usize> {
+        let mut i = pos + 1;
+        while i < self.bytes.len() {
+            match self.bytes[i] {
+                b'\\' => i += 2,
+                b'"' => return Some(i + 1),
+                _ => i += 1,
+            }
+        }
+        None
+    }
+
+    /// Span of the value of `key` in the object starting at `pos`
+    fn object_field(&self, pos: usize, key: &str) -> Option<Range<usize>> {
+        if *self.bytes.get(pos)? != b'{' {
+            return None;
+        }
+        let mut i = self.skip_whitespace(pos + 1);
+        while *self.bytes.get(i)? == b'"' {
+            let key_end = self.string_end(i)?;
+            let name: String = serde_json::from_str(&self.text[i..key_end]).ok()?;
+            i = self.skip_whitespace(key_end);
+            if *self.bytes.get(i)? != b':' {
+                return None;
+            }
+            let value_start = self.skip_whitespace(i + 1);
+            let value_end = self.value_end(value_start)?;
+            if name == key {
+                return Some(value_start..value_end);
+            }
+            i = self.skip_whitespace(value_end);
+            if *self.bytes.get(i)? == b',' {
+                i = self.skip_whitespace(i + 1);
+            }
+        }
+        None
+    }
+
+    /// Spans of the elements of the array starting at `pos`
+    fn array_elements(&self, pos: usize) -> Option<Vec<Range<usize>>> {
+        if *self.bytes.get(pos)? != b'[' {
+            return None;
+        }
+        let mut elements = Vec::new();
+        let mut i = self.skip_whitespace(pos + 1);
+        while *self.bytes.get(i)? != b']' {
+            let end = self.value_end(i)?;
+            elements.push(i..end);
+            i = self.skip_whitespace(end);
+            if *self.bytes.get(i)? == b',' {
+                i = self.skip_whitespace(i + 1);
+            }
+        }
+        Some(elements)
+    }
+}
+
 /// Strips existing \[Title\] prefix from message content.
 ///
 /// This function removes a title prefix in the format `[Title] Message`.
@@ -762,4 +963,131 @@ mod tests {
         assert_eq!(result, "] Message");
         // This is why we reject titles with ']' in rename_session_native
     }
+
+    // --- byte-preserving rewrite tests ---
+
+    #[test]
+    fn test_replace_message_content_array_preserves_line() {
+        // Unusual key order, number formats and escapes must survive untouched
+        let line = r#"{"parentUuid":null,"cost":1.50,"message":{"role":"user","content":[{"type":"image","source":{"type":"base64","media_type":"image\/png","data":"iVBORw0KGgo="}},{"text":"Fix the café \"menu\"","type":"text"},{"type":"text","text":"second"}]},"type":"user","uuid":"u-1"}"#;
+        let updated =
+            replace_message_content_in_line(line, "[Title] Fix the café \"menu\"").unwrap();
+
+        let expected = line.replace(
+            r#""text":"Fix the café \"menu\"""#,
+            r#""text":"[Title] Fix the café \"menu\"""#,
+        );
+        assert_eq!(updated, expected);
+    }
+
+    #[test]
+    fn test_replace_message_content_string_forms() {
+        let line = r#"{"type":"user","message":{"content":"Hello","role":"user"},"n":1e3}"#;
+        assert_eq!(
+            replace_message_content_in_line(line, "[T] Hello").unwrap(),
+            r#"{"type":"user","message":{"content":"[T] Hello","role":"user"},"n":1e3}"#
+        );
+
+        let line = r#"{"type":"user", "message" : "Direct" }"#;
+        assert_eq!(
+            replace_message_content_in_line(line, "New").unwrap(),
+            r#"{"type":"user", "message" : "New" }"#
+        );
+    }
+
+    #[test]
+    fn test_replace_message_content_ignores_nested_lookalikes() {
+        // "message" and "text" keys nested elsewhere must not be matched
+        let line = r#"{"meta":{"message":"decoy"},"message":{"content":[{"type":"tool_result","content":[{"type":"text","text":"decoy"}]},{"type":"text","text":"real"}]}}"#;
+        let updated = replace_message_content_in_line(line, "changed").unwrap();
+        assert_eq!(
+            updated,
+            line.replace(r#""text":"real""#, r#""text":"changed""#)
+        );
+    }
+
+    #[test]
+    fn test_replace_message_content_no_text_block() {
+        let line = r#"{"message":{"content":[{"type":"image","source":{}}]}}"#;
+        assert_eq!(replace_message_content_in_line(line, "x"), None);
+        assert_eq!(
+            replace_message_content_in_line(r#"{"uuid":"1"}"#, "x"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_line_byte_range() {
+        let content = "a\r\nbb\nccc";
+        assert_eq!(line_byte_range(content, 0), Some(0..1));
+        assert_eq!(line_byte_range(content, 1), Some(3..5));
+        assert_eq!(line_byte_range(content, 2), Some(6..9));
+        assert_eq!(line_byte_range(content, 3), None);
+    }
+
+    #[tokio::test]
+    async fn test_rename_session_native_array_content_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let home = temp_dir.path().canonicalize().unwrap();
+        std::env::set_var("HOME", &home);
+
+        let project_dir = home.join(".claude/projects/-test-project");
+        fs::create_dir_all(&project_dir).unwrap();
+        let session_path = project_dir.join("session-1.jsonl");
+
+        let snapshot = r#"{"type":"file-history-snapshot","messageId":"u-1","snapshot":{"trackedFileBackups":{}},"isSnapshotUpdate":false}"#;
+        let user = r#"{"parentUuid":null,"type":"user","message":{"role":"user","content":[{"type":"image","source":{"type":"base64","media_type":"image/png","data":"AAAA"}},{"type":"text","text":"Describe this"}]},"uuid":"u-1","timestamp":"2025-06-26T10:00:00.000Z"}"#;
+        let assistant = r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"An image"}]},"uuid":"a-1"}"#;
+        let original = format!("{snapshot}\n{user}\n{assistant}\n");
+        fs::write(&session_path, &original).unwrap();
+
+        let path = session_path.to_string_lossy().to_string();
+        let result = rename_session_native(path.clone(), "Screenshot".to_string())
+            .await
+            .unwrap();
+        assert_eq!(result.previous_title, "Describe this");
+        assert_eq!(result.new_title, "[Screenshot] Describe this");
+
+        let renamed = fs::read_to_string(&session_path).unwrap();
+        assert_eq!(
+            renamed,
+            original.replace(
+                r#""text":"Describe this""#,
+                r#""text":"[Screenshot] Describe this""#
+            )
+        );
+
+        // Resetting restores the original file byte-for-byte
+        reset_session_native_name(path).await.unwrap();
+        assert_eq!(fs::read_to_string(&session_path).unwrap(), original);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_validate_claude_path_follows_trusted_symlink() {
+        use crate::commands::trusted_symlinks::set_trusted_symlink_targets;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let home = root.join("home");
+        let synced = root.join("sync/claude");
+        fs::create_dir_all(synced.join("projects/-test-project")).unwrap();
+        fs::create_dir_all(&home).unwrap();
+        std::os::unix::fs::symlink(&synced, home.join(".claude")).unwrap();
+        std::env::set_var("HOME", &home);
+
+        let session_path = home.join(".claude/projects/-test-project/session-1.jsonl");
+        fs::write(&session_path, "{}\n").unwrap();
+        let path = session_path.to_string_lossy().to_string();
+
+        let err = validate_claude_path(&path).unwrap_err();
+        assert!(err.contains("not a trusted symlink target"));
+
+        let sync = root.join("sync").to_string_lossy().to_string();
+        let status = set_trusted_symlink_targets(vec![sync]).await.unwrap();
+        assert!(status.untrusted_links.is_empty());
+        let result = validate_claude_path(&path);
+        set_trusted_symlink_targets(Vec::new()).await.unwrap();
+        assert!(result.is_ok(), "{result:?}");
+    }
 }
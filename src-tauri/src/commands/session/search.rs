@@ -1,12 +1,14 @@
 //! Session search functions
 
+use crate::commands::trusted_symlinks::{check_claude_folder, check_symlinks};
 use crate::models::{ClaudeMessage, RawLogEntry};
 use crate::utils::find_line_ranges;
 use chrono::Utc;
+use memchr::memmem;
 use memmap2::Mmap;
 use rayon::prelude::*;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 use walkdir::WalkDir;
 
@@ -145,6 +147,50 @@ fn search_in_file(file_path: &PathBuf, query: &str) -> Vec<ClaudeMessage> {
     results
 }
 
+/// Whether any user or assistant message in a session file contains the query
+///
+/// Stops at the first match; lines that can't contain the text are skipped
+/// before being parsed.
+#[allow(unsafe_code)] // Required for mmap performance optimization
+pub(crate) fn session_contains_text(file_path: &Path, query: &str) -> bool {
+    let query_lower = query.to_lowercase();
+    let Ok(file) = fs::File::open(file_path) else {
+        return false;
+    };
+    // SAFETY: We're only reading the file, and the file handle is kept open
+    // for the duration of the mmap's lifetime. Session files are append-only.
+    let Ok(mmap) = (unsafe { Mmap::map(&file) }) else {
+        return false;
+    };
+
+    // JSON escapes non-ASCII text, quotes, backslashes and control characters
+    // such as tab and newline, so only other queries can rule out lines by
+    // their raw bytes
+    let prefilter = (query_lower.is_ascii()
+        && !query_lower.contains(['"', '\\'])
+        && !query_lower.contains(|c: char| c.is_ascii_control()))
+    .then(|| memmem::Finder::new(query_lower.as_bytes()));
+
+    let mut parse_buffer = Vec::with_capacity(PARSE_BUFFER_INITIAL_CAPACITY);
+    find_line_ranges(&mmap).into_iter().any(|(start, end)| {
+        let line = &mmap[start..end];
+        if let Some(finder) = &prefilter {
+            if finder.find(&line.to_ascii_lowercase()).is_none() {
+                return false;
+            }
+        }
+        parse_buffer.clear();
+        parse_buffer.extend_from_slice(line);
+        let Ok(log_entry) = simd_json::serde::from_slice::<RawLogEntry>(&mut parse_buffer) else {
+            return false;
+        };
+        (log_entry.message_type == "user" || log_entry.message_type == "assistant")
+            && log_entry
+                .message
+                .is_some_and(|message| search_in_value(&message.content, &query_lower))
+    })
+}
+
 /// Default limit for search results
 const DEFAULT_SEARCH_LIMIT: usize = 100;
 
@@ -159,17 +205,20 @@ pub async fn search_messages(
     let start_time = std::time::Instant::now();
 
     let max_results = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
-    let projects_path = PathBuf::from(&claude_path).join("projects");
+    let claude_folder = PathBuf::from(&claude_path);
+    let projects_path = claude_folder.join("projects");
 
     if !projects_path.exists() {
         return Ok(vec![]);
     }
+    check_claude_folder(&claude_folder)?;
 
-    // 1. Collect all JSONL file paths
+    // 1. Collect all JSONL file paths; links are only followed into trusted targets
     let file_paths: Vec<PathBuf> = WalkDir::new(&projects_path)
         .into_iter()
         .filter_map(std::result::Result::ok)
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .filter(|e| !e.path_is_symlink() || check_symlinks(&claude_folder, e.path()).is_ok())
         .map(|e| e.path().to_path_buf())
         .collect();
 
@@ -323,4 +372,23 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap().is_empty());
     }
+
+    #[test]
+    fn test_session_contains_text_with_escaped_characters() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("session.jsonl");
+        // Escaped as it is in the JSONL line
+        let content = r#"first line\n\tindented \"quoted\" caf\u00e9"#;
+        fs::write(
+            &file_path,
+            create_sample_user_message("uuid-1", "session-1", content) + "\n",
+        )
+        .unwrap();
+
+        assert!(session_contains_text(&file_path, "line\n\tindented"));
+        assert!(session_contains_text(&file_path, "\"quoted\""));
+        assert!(session_contains_text(&file_path, "café"));
+        assert!(session_contains_text(&file_path, "FIRST"));
+        assert!(!session_contains_text(&file_path, "line\tindented"));
+    }
 }
@@ -0,0 +1,797 @@
+//! Best-effort analysis of Bash tool commands
+//!
+//! Files deleted, moved or rewritten through Bash never show up as Edit/Write
+//! tool results. This module tokenizes the executed command line and recognizes
+//! the common file-mutating forms (`rm`, `mv`, `cp`, `git rm/mv/checkout --/restore`,
+//! `sed -i`, `perl -i`, `tee`, `truncate` and output redirects). Anything it
+//! can't understand (variables, subshells, scripts) is silently ignored.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Operation name for files removed by the command
+pub(crate) const SHELL_OP_DELETE: &str = "delete";
+/// Operation name for files renamed by the command
+pub(crate) const SHELL_OP_MOVE: &str = "move";
+/// Operation name for files whose content was replaced by the command
+pub(crate) const SHELL_OP_REWRITE: &str = "rewrite";
+
+/// A file affected by a shell command
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ShellFileEffect {
+    pub operation: &'static str,
+    pub file_path: String,
+    /// New location for moves
+    pub destination: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    /// `;`, `&&`, `||`, `|`, `&` or a newline
+    Separator,
+    /// Output redirect whose target is the next word
+    WriteRedirect,
+    /// Input redirect, fd duplication or here-string; the next word is not a written file
+    OtherRedirect,
+}
+
+/// Analyze a Bash command line and return the files it deletes, moves or rewrites
+///
+/// Relative paths are resolved against `cwd`, following any `cd` in the command.
+pub(crate) fn analyze_shell_command(command: &str, cwd: Option<&str>) -> Vec<ShellFileEffect> {
+    let mut effects = Vec::new();
+    let mut cwd = cwd.map(PathBuf::from);
+    let mut words: Vec<String> = Vec::new();
+    let mut redirect_targets: Vec<String> = Vec::new();
+
+    let mut tokens = tokenize(command).into_iter().peekable();
+    loop {
+        let token = tokens.next();
+        match token {
+            Some(Token::Word(word)) => words.push(word),
+            Some(Token::WriteRedirect) => {
+                if let Some(Token::Word(target)) = tokens.next() {
+                    redirect_targets.push(target);
+                }
+            }
+            Some(Token::OtherRedirect) => {
+                if let Some(Token::Word(_)) = tokens.peek() {
+                    tokens.next();
+                }
+            }
+            Some(Token::Separator) | None => {
+                let cmd_cwd = analyze_simple_command(&words, cwd.as_deref(), &mut effects);
+                for target in redirect_targets.drain(..) {
+                    if !is_device_path(&target) {
+                        effects.push(ShellFileEffect {
+                            operation: SHELL_OP_REWRITE,
+                            file_path: resolve_path(cmd_cwd.as_deref(), &target),
+                            destination: None,
+                        });
+                    }
+                }
+                // `cd` affects the commands that follow it
+                if let Some(dir) = cd_target(&words) {
+                    cwd = Some(PathBuf::from(resolve_path(cwd.as_deref(), dir)));
+                }
+                words.clear();
+                if token.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    effects
+}
+
+/// Programs run by a command line, as file names (`/usr/bin/terraform` -> `terraform`)
+///
+/// Wrappers like `sudo` and environment assignments are skipped.
+pub(crate) fn command_programs(command: &str) -> Vec<String> {
+    let mut programs = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut tokens = tokenize(command).into_iter().peekable();
+    loop {
+        let token = tokens.next();
+        match token {
+            Some(Token::Word(word)) => words.push(word),
+            Some(Token::WriteRedirect | Token::OtherRedirect) => {
+                if let Some(Token::Word(_)) = tokens.peek() {
+                    tokens.next();
+                }
+            }
+            Some(Token::Separator) | None => {
+                if let Some(program) = strip_command_prefix(&words).first() {
+                    let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+                    if !name.is_empty() && !programs.iter().any(|p| p == name) {
+                        programs.push(name.to_string());
+                    }
+                }
+                words.clear();
+                if token.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+    programs
+}
+
+/// Analyze one simple command and return the directory its paths are relative to
+fn analyze_simple_command(
+    words: &[String],
+    cwd: Option<&Path>,
+    effects: &mut Vec<ShellFileEffect>,
+) -> Option<PathBuf> {
+    let args = strip_command_prefix(words);
+    let Some((program, args)) = args.split_first() else {
+        return cwd.map(Path::to_path_buf);
+    };
+    let program = program.rsplit('/').next().unwrap_or(program);
+    let mut cwd = cwd.map(Path::to_path_buf);
+
+    match program {
+        "rm" | "unlink" | "rmdir" => {
+            push_all(
+                effects,
+                SHELL_OP_DELETE,
+                cwd.as_deref(),
+                &operands(args, &[]),
+            );
+        }
+        "mv" => push_moves(effects, cwd.as_deref(), args),
+        "cp" => {
+            let (sources, dest) = transfer_operands(args);
+            if let Some(dest) = dest {
+                let targets = transfer_targets(&sources, &dest);
+                push_all(effects, SHELL_OP_REWRITE, cwd.as_deref(), &targets);
+            }
+        }
+        "tee" | "truncate" => {
+            let with_value = if program == "truncate" {
+                &["-s", "-r", "--size", "--reference"][..]
+            } else {
+                &[][..]
+            };
+            push_all(
+                effects,
+                SHELL_OP_REWRITE,
+                cwd.as_deref(),
+                &operands(args, with_value),
+            );
+        }
+        "sed" => {
+            if let Some(files) = sed_in_place_files(args) {
+                push_all(effects, SHELL_OP_REWRITE, cwd.as_deref(), &files);
+            }
+        }
+        "perl" => {
+            if let Some(files) = perl_in_place_files(args) {
+                push_all(effects, SHELL_OP_REWRITE, cwd.as_deref(), &files);
+            }
+        }
+        "git" => {
+            let (subcommand, sub_args, git_cwd) = git_subcommand(args, cwd.as_deref());
+            cwd = git_cwd;
+            match subcommand {
+                Some("rm") if !sub_args.iter().any(|a| a == "--cached") => push_all(
+                    effects,
+                    SHELL_OP_DELETE,
+                    cwd.as_deref(),
+                    &operands(sub_args, &[]),
+                ),
+                Some("mv") => push_moves(effects, cwd.as_deref(), sub_args),
+                Some("checkout") => {
+                    // Only `git checkout [<tree-ish>] -- <paths>` is unambiguous
+                    if let Some(pos) = sub_args.iter().position(|a| a == "--") {
+                        let paths: Vec<&str> =
+                            sub_args[pos + 1..].iter().map(String::as_str).collect();
+                        push_all(effects, SHELL_OP_REWRITE, cwd.as_deref(), &paths);
+                    }
+                }
+                Some("restore") => {
+                    let staged_only = sub_args.iter().any(|a| a == "--staged" || a == "-S")
+                        && !sub_args.iter().any(|a| a == "--worktree" || a == "-W");
+                    if !staged_only {
+                        push_all(
+                            effects,
+                            SHELL_OP_REWRITE,
+                            cwd.as_deref(),
+                            &operands(sub_args, &["-s", "--source"]),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+
+    cwd
+}
+
+/// Skip environment assignments and wrappers like `sudo` or `command`
+fn strip_command_prefix(words: &[String]) -> &[String] {
+    let mut rest = words;
+    while let Some((first, tail)) = rest.split_first() {
+        let is_assignment = first.split_once('=').is_some_and(|(name, _)| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        if is_assignment
+            || matches!(
+                first.as_str(),
+                "sudo" | "command" | "exec" | "nohup" | "time"
+            )
+        {
+            rest = tail;
+        } else {
+            break;
+        }
+    }
+    rest
+}
+
+/// Target directory of a `cd` command
+fn cd_target(words: &[String]) -> Option<&str> {
+    match strip_command_prefix(words) {
+        [cd, dir] if cd == "cd" && dir != "-" => Some(dir),
+        _ => None,
+    }
+}
+
+/// Non-option arguments; options listed in `with_value` consume the next argument
+fn operands<'a>(args: &'a [String], with_value: &[&str]) -> Vec<&'a str> {
+    let mut result = Vec::new();
+    let mut iter = args.iter();
+    let mut options_done = false;
+    while let Some(arg) = iter.next() {
+        if options_done || !arg.starts_with('-') || arg == "-" {
+            result.push(arg.as_str());
+        } else if arg == "--" {
+            options_done = true;
+        } else if with_value.contains(&arg.as_str()) {
+            iter.next();
+        }
+    }
+    result
+}
+
+/// Split `mv`/`cp` arguments into sources and destination, honouring `-t DIR`
+fn transfer_operands(args: &[String]) -> (Vec<&str>, Option<String>) {
+    let mut target_dir: Option<String> = None;
+    let mut files: Vec<&str> = Vec::new();
+    let mut options_done = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if options_done || !arg.starts_with('-') || arg == "-" {
+            files.push(arg.as_str());
+        } else if arg == "--" {
+            options_done = true;
+        } else if arg == "-t" || arg == "--target-directory" {
+            target_dir = iter
+                .next()
+                .map(|dir| format!("{}/", dir.trim_end_matches('/')));
+        } else if let Some(dir) = arg.strip_prefix("--target-directory=") {
+            target_dir = Some(format!("{}/", dir.trim_end_matches('/')));
+        } else if arg == "-S" || arg == "--suffix" {
+            iter.next();
+        }
+    }
+
+    if target_dir.is_some() {
+        return (files, target_dir);
+    }
+    let dest = files.pop().map(str::to_string);
+    (files, dest)
+}
+
+/// Final path of each source after a transfer into `dest`
+fn transfer_targets(sources: &[&str], dest: &str) -> Vec<String> {
+    let into_dir = sources.len() > 1 || dest.ends_with('/');
+    sources
+        .iter()
+        .map(|source| {
+            if into_dir {
+                let name = source
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or(source);
+                format!("{}/{}", dest.trim_end_matches('/'), name)
+            } else {
+                dest.to_string()
+            }
+        })
+        .collect()
+}
+
+fn push_moves(effects: &mut Vec<ShellFileEffect>, cwd: Option<&Path>, args: &[String]) {
+    let (sources, dest) = transfer_operands(args);
+    let Some(dest) = dest else {
+        return;
+    };
+    for (source, target) in sources.iter().zip(transfer_targets(&sources, &dest)) {
+        effects.push(ShellFileEffect {
+            operation: SHELL_OP_MOVE,
+            file_path: resolve_path(cwd, source),
+            destination: Some(resolve_path(cwd, &target)),
+        });
+    }
+}
+
+fn push_all<S: AsRef<str>>(
+    effects: &mut Vec<ShellFileEffect>,
+    operation: &'static str,
+    cwd: Option<&Path>,
+    paths: &[S],
+) {
+    for path in paths {
+        effects.push(ShellFileEffect {
+            operation,
+            file_path: resolve_path(cwd, path.as_ref()),
+            destination: None,
+        });
+    }
+}
+
+/// Files edited by `sed -i`, or None when not editing in place
+fn sed_in_place_files(args: &[String]) -> Option<Vec<&str>> {
+    let mut in_place = false;
+    let mut has_script_option = false;
+    let mut positional: Vec<&str> = Vec::new();
+    let mut iter = args.iter().peekable();
+    while let Some(arg) = iter.next() {
+        if arg == "--in-place" || arg.starts_with("--in-place=") {
+            in_place = true;
+        } else if arg == "-e" || arg == "-f" || arg == "--expression" || arg == "--file" {
+            has_script_option = true;
+            iter.next();
+        } else if arg.starts_with("--expression=") || arg.starts_with("--file=") {
+            has_script_option = true;
+        } else if arg == "-l" {
+            iter.next();
+        } else if arg.starts_with('-') && arg.len() > 1 && !arg.starts_with("--") {
+            let flags: Vec<char> = arg[1..].chars().collect();
+            for (pos, flag) in flags.iter().enumerate() {
+                let is_last = pos + 1 == flags.len();
+                match flag {
+                    // Anything after `i` is the backup suffix
+                    'i' => {
+                        in_place = true;
+                        // BSD sed takes the suffix as a separate (often empty) argument
+                        if is_last && iter.peek().is_some_and(|next| next.is_empty()) {
+                            iter.next();
+                        }
+                        break;
+                    }
+                    'e' | 'f' | 'l' => {
+                        has_script_option |= *flag != 'l';
+                        if is_last {
+                            iter.next();
+                        }
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        } else {
+            positional.push(arg.as_str());
+        }
+    }
+
+    if !in_place {
+        return None;
+    }
+    if !has_script_option && !positional.is_empty() {
+        positional.remove(0);
+    }
+    Some(positional)
+}
+
+/// Files edited by `perl -i`, or None when not editing in place
+fn perl_in_place_files(args: &[String]) -> Option<Vec<&str>> {
+    let mut in_place = false;
+    let mut has_script_option = false;
+    let mut positional: Vec<&str> = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if !positional.is_empty() || !arg.starts_with('-') || arg == "-" {
+            positional.push(arg.as_str());
+            continue;
+        }
+        if arg == "--" {
+            continue;
+        }
+        let flags = &arg[1..];
+        if flags.contains('i') && !flags.starts_with('I') {
+            in_place = true;
+        }
+        if let Some(pos) = flags.find(['e', 'E']) {
+            has_script_option = true;
+            // `-e CODE` or `-pie CODE`: the code is the next argument
+            if pos + 1 == flags.len() {
+                iter.next();
+            }
+        }
+    }
+
+    if !in_place {
+        return None;
+    }
+    if !has_script_option && !positional.is_empty() {
+        positional.remove(0);
+    }
+    Some(positional)
+}
+
+/// Find the git subcommand, skipping global options and applying `-C <dir>`
+fn git_subcommand<'a>(
+    args: &'a [String],
+    cwd: Option<&Path>,
+) -> (Option<&'a str>, &'a [String], Option<PathBuf>) {
+    let mut cwd = cwd.map(Path::to_path_buf);
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "-C" => {
+                if let Some(dir) = args.get(i + 1) {
+                    cwd = Some(PathBuf::from(resolve_path(cwd.as_deref(), dir)));
+                }
+                i += 2;
+            }
+            "-c" | "--git-dir" | "--work-tree" | "--namespace" => i += 2,
+            _ if arg.starts_with('-') => i += 1,
+            _ => return (Some(arg), &args[i + 1..], cwd),
+        }
+    }
+    (None, &[], cwd)
+}
+
+fn is_device_path(path: &str) -> bool {
+    path.starts_with("/dev/")
+}
+
+/// Resolve a path against the working directory and normalize `.`/`..` lexically
+fn resolve_path(cwd: Option<&Path>, path: &str) -> String {
+    let path = Path::new(path);
+    let joined = match cwd {
+        Some(cwd) if path.is_relative() => cwd.join(path),
+        _ => path.to_path_buf(),
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized.to_string_lossy().to_string()
+}
+
+/// Split a command line into words, separators and redirects
+///
+/// Handles single/double quotes, backslash escapes, comments and here-documents
+/// well enough for the commands an agent typically runs.
+fn tokenize(command: &str) -> Vec<Token> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut pending_heredocs: Vec<String> = Vec::new();
+    let mut i = 0;
+
+    macro_rules! flush {
+        () => {
+            if in_word {
+                tokens.push(Token::Word(std::mem::take(&mut word)));
+                in_word = false;
+            }
+        };
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\'' => {
+                in_word = true;
+                i += 1;
+                while i < chars.len() && chars[i] != '\'' {
+                    word.push(chars[i]);
+                    i += 1;
+                }
+            }
+            '"' => {
+                in_word = true;
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\'
+                        && i + 1 < chars.len()
+                        && matches!(chars[i + 1], '"' | '\\' | '$' | '`')
+                    {
+                        i += 1;
+                    }
+                    word.push(chars[i]);
+                    i += 1;
+                }
+            }
+            '\\' => {
+                if i + 1 < chars.len() {
+                    if chars[i + 1] != '\n' {
+                        in_word = true;
+                        word.push(chars[i + 1]);
+                    }
+                    i += 1;
+                }
+            }
+            '#' if !in_word => {
+                while i + 1 < chars.len() && chars[i + 1] != '\n' {
+                    i += 1;
+                }
+            }
+            ' ' | '\t' => flush!(),
+            '\n' | ';' | '|' | '&' => {
+                // `&>` redirects both stdout and stderr
+                if c == '&' && chars.get(i + 1) == Some(&'>') {
+                    flush!();
+                    i += 1;
+                    if chars.get(i + 1) == Some(&'>') {
+                        i += 1;
+                    }
+                    tokens.push(Token::WriteRedirect);
+                    i += 1;
+                    continue;
+                }
+                flush!();
+                if tokens.last() != Some(&Token::Separator) {
+                    tokens.push(Token::Separator);
+                }
+                if matches!(c, '|' | '&') && chars.get(i + 1) == Some(&c) {
+                    i += 1;
+                }
+                if c == '\n' && !pending_heredocs.is_empty() {
+                    i = skip_heredoc_bodies(&chars, i + 1, &pending_heredocs);
+                    pending_heredocs.clear();
+                    continue;
+                }
+            }
+            '>' => {
+                // A numeric word right before `>` is a file descriptor
+                if in_word && word.chars().all(|d| d.is_ascii_digit()) {
+                    word.clear();
+                    in_word = false;
+                }
+                flush!();
+                if matches!(chars.get(i + 1), Some('>' | '|')) {
+                    i += 1;
+                }
+                if chars.get(i + 1) == Some(&'&') {
+                    i += 1;
+                    tokens.push(Token::OtherRedirect);
+                } else {
+                    tokens.push(Token::WriteRedirect);
+                }
+            }
+            '<' => {
+                if in_word && word.chars().all(|d| d.is_ascii_digit()) {
+                    word.clear();
+                    in_word = false;
+                }
+                flush!();
+                if chars.get(i + 1) == Some(&'<') && chars.get(i + 2) != Some(&'<') {
+                    // Here-document: remember the delimiter and skip its body later
+                    i += 1;
+                    if chars.get(i + 1) == Some(&'-') {
+                        i += 1;
+                    }
+                    while chars.get(i + 1).is_some_and(|c| *c == ' ' || *c == '\t') {
+                        i += 1;
+                    }
+                    let mut delimiter = String::new();
+                    while let Some(&next) = chars.get(i + 1) {
+                        if next.is_whitespace() || matches!(next, ';' | '|' | '&' | '>' | '<') {
+                            break;
+                        }
+                        if next != '\'' && next != '"' && next != '\\' {
+                            delimiter.push(next);
+                        }
+                        i += 1;
+                    }
+                    pending_heredocs.push(delimiter);
+                } else {
+                    while chars.get(i + 1) == Some(&'<') {
+                        i += 1;
+                    }
+                    tokens.push(Token::OtherRedirect);
+                }
+            }
+            _ => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+        i += 1;
+    }
+    if in_word {
+        tokens.push(Token::Word(word));
+    }
+
+    tokens
+}
+
+/// Skip here-document bodies starting at `start`; returns the index after the last delimiter line
+fn skip_heredoc_bodies(chars: &[char], start: usize, delimiters: &[String]) -> usize {
+    let mut i = start;
+    for delimiter in delimiters {
+        loop {
+            if i >= chars.len() {
+                return i;
+            }
+            let line_end = chars[i..]
+                .iter()
+                .position(|c| *c == '\n')
+                .map_or(chars.len(), |p| i + p);
+            let line: String = chars[i..line_end].iter().collect();
+            i = line_end + 1;
+            if line.trim_start_matches('\t') == delimiter.as_str() {
+                break;
+            }
+        }
+    }
+    i.min(chars.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effects(command: &str) -> Vec<(&'static str, String, Option<String>)> {
+        analyze_shell_command(command, Some("/repo"))
+            .into_iter()
+            .map(|e| (e.operation, e.file_path, e.destination))
+            .collect()
+    }
+
+    #[test]
+    fn test_rm_with_flags_and_quotes() {
+        assert_eq!(
+            effects("rm -rf build 'my file.txt' -- -weird"),
+            vec![
+                (SHELL_OP_DELETE, "/repo/build".to_string(), None),
+                (SHELL_OP_DELETE, "/repo/my file.txt".to_string(), None),
+                (SHELL_OP_DELETE, "/repo/-weird".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mv_single_and_into_directory() {
+        assert_eq!(
+            effects("mv src/old.rs src/new.rs"),
+            vec![(
+                SHELL_OP_MOVE,
+                "/repo/src/old.rs".to_string(),
+                Some("/repo/src/new.rs".to_string())
+            )]
+        );
+        assert_eq!(
+            effects("git mv a.txt b.txt docs/"),
+            vec![
+                (
+                    SHELL_OP_MOVE,
+                    "/repo/a.txt".to_string(),
+                    Some("/repo/docs/a.txt".to_string())
+                ),
+                (
+                    SHELL_OP_MOVE,
+                    "/repo/b.txt".to_string(),
+                    Some("/repo/docs/b.txt".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_checkout_and_restore() {
+        assert_eq!(
+            effects("git checkout HEAD~1 -- src/lib.rs"),
+            vec![(SHELL_OP_REWRITE, "/repo/src/lib.rs".to_string(), None)]
+        );
+        // Without `--` the argument may be a branch name
+        assert!(effects("git checkout main").is_empty());
+        assert_eq!(
+            effects("git restore --source=HEAD a.rs"),
+            vec![(SHELL_OP_REWRITE, "/repo/a.rs".to_string(), None)]
+        );
+        assert!(effects("git restore --staged a.rs").is_empty());
+        assert!(effects("git rm --cached secret.env").is_empty());
+    }
+
+    #[test]
+    fn test_sed_and_perl_in_place() {
+        assert_eq!(
+            effects("sed -i 's/foo/bar/g' a.txt b.txt"),
+            vec![
+                (SHELL_OP_REWRITE, "/repo/a.txt".to_string(), None),
+                (SHELL_OP_REWRITE, "/repo/b.txt".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            effects("sed -i '' -e 's/a/b/' c.txt"),
+            vec![(SHELL_OP_REWRITE, "/repo/c.txt".to_string(), None)]
+        );
+        assert!(effects("sed 's/a/b/' c.txt").is_empty());
+        assert_eq!(
+            effects("perl -pi -e 's/a/b/' d.pl"),
+            vec![(SHELL_OP_REWRITE, "/repo/d.pl".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn test_redirects() {
+        assert_eq!(
+            effects("echo hi > out.txt 2>&1 && cat a >> log.txt 2>/dev/null"),
+            vec![
+                (SHELL_OP_REWRITE, "/repo/out.txt".to_string(), None),
+                (SHELL_OP_REWRITE, "/repo/log.txt".to_string(), None),
+            ]
+        );
+        assert!(effects("cargo test 2>&1 | tail -20").is_empty());
+        assert!(effects("wc -l < input.txt").is_empty());
+    }
+
+    #[test]
+    fn test_heredoc_body_is_not_parsed() {
+        let command = "cat > notes.md <<'EOF'\nrm -rf /\nmv a b\nEOF\nrm stale.txt";
+        assert_eq!(
+            effects(command),
+            vec![
+                (SHELL_OP_REWRITE, "/repo/notes.md".to_string(), None),
+                (SHELL_OP_DELETE, "/repo/stale.txt".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cd_and_git_dash_c_change_directory() {
+        assert_eq!(
+            effects("cd web && rm -f ../tmp.txt dist/app.js"),
+            vec![
+                (SHELL_OP_DELETE, "/repo/tmp.txt".to_string(), None),
+                (SHELL_OP_DELETE, "/repo/web/dist/app.js".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            effects("git -C sub rm old.txt"),
+            vec![(SHELL_OP_DELETE, "/repo/sub/old.txt".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn test_read_only_commands_are_ignored() {
+        assert!(effects("ls -la && grep -rn 'rm -rf' src | head # rm x").is_empty());
+        assert!(effects("FOO=1 cargo build --release").is_empty());
+    }
+
+    #[test]
+    fn test_command_programs() {
+        assert_eq!(
+            command_programs(
+                "cd infra && TF_LOG=1 /usr/local/bin/terraform plan > plan.txt | tee log"
+            ),
+            vec!["cd", "terraform", "tee"]
+        );
+        assert_eq!(
+            command_programs("sudo terraform apply; echo 'terraform'"),
+            vec!["terraform", "echo"]
+        );
+        assert!(command_programs("").is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+//! Messages appended to a session file since a byte offset
+//!
+//! Claude Code appends one JSON line per entry, so whatever lies past the last
+//! offset we parsed up to is new. The last line may still be half written; it
+//! is left for the next read.
+
+use super::load::{is_system_message_type, parse_line_simd};
+use crate::models::ClaudeMessage;
+use memchr::{memchr_iter, memrchr};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes read per step when searching backwards for a newline
+const BACKWARD_CHUNK: u64 = 64 * 1024;
+
+/// End of the last complete line among the first `size` bytes of `file`
+pub(crate) fn complete_len(file: &mut File, size: u64) -> io::Result<u64> {
+    let mut end = size;
+    let mut buffer = Vec::new();
+    while end > 0 {
+        let start = end.saturating_sub(BACKWARD_CHUNK);
+        buffer.resize(usize::try_from(end - start).unwrap_or(0), 0);
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut buffer)?;
+        if let Some(pos) = memrchr(b'\n', &buffer) {
+            return Ok(start + pos as u64 + 1);
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+/// Complete lines appended after `offset`, parsed into messages
+#[derive(Debug, Default)]
+pub(crate) struct AppendedMessages {
+    pub messages: Vec<ClaudeMessage>,
+    /// Complete lines read, including ones that aren't displayable messages
+    pub line_count: usize,
+    /// Where the next read should start: just past the last complete line
+    pub end_offset: u64,
+}
+
+/// Read the complete lines between `offset` and the current end of the file
+///
+/// `offset` must be at a line start. With `parse` false the lines are only
+/// counted, for appends too large to send along.
+pub(crate) fn read_appended_messages(
+    path: &Path,
+    offset: u64,
+    max_bytes: Option<u64>,
+    parse: bool,
+) -> Result<AppendedMessages, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read file metadata: {e}"))?
+        .len();
+    if size <= offset {
+        return Ok(AppendedMessages {
+            end_offset: offset,
+            ..Default::default()
+        });
+    }
+    let limit = max_bytes.map_or(size, |max| size.min(offset.saturating_add(max)));
+
+    let mut bytes = Vec::new();
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek session file: {e}"))?;
+    file.take(limit - offset)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read session file: {e}"))?;
+    let complete = memrchr(b'\n', &bytes).map_or(0, |pos| pos + 1);
+    bytes.truncate(complete);
+
+    let mut appended = AppendedMessages {
+        end_offset: offset + complete as u64,
+        ..Default::default()
+    };
+    let mut start = 0;
+    for end in memchr_iter(b'\n', &bytes).collect::<Vec<_>>() {
+        let line_num = appended.line_count;
+        appended.line_count += 1;
+        if parse && end > start {
+            let line = &mut bytes[start..end];
+            if let Some(message) = parse_line_simd(line_num, line, false)
+                .filter(|message| !is_system_message_type(&message.message_type))
+            {
+                appended.messages.push(message);
+            }
+        }
+        start = end + 1;
+    }
+    Ok(appended)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn line(uuid: &str) -> String {
+        json!({
+            "type": "user",
+            "uuid": uuid,
+            "sessionId": "s",
+            "timestamp": "2025-06-01T10:00:00Z",
+            "message": {"role": "user", "content": uuid},
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_read_appended_messages_leaves_partial_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("s.jsonl");
+        fs::write(&path, format!("{}\n", line("a"))).unwrap();
+        let offset = fs::metadata(&path).unwrap().len();
+
+        let second = line("b");
+        let (head, tail) = second.split_at(10);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{}\n{head}", line("a2")).unwrap();
+
+        let appended = read_appended_messages(&path, offset, None, true).unwrap();
+        let uuids: Vec<&str> = appended.messages.iter().map(|m| m.uuid.as_str()).collect();
+        assert_eq!(uuids, ["a2"]);
+        assert_eq!(appended.line_count, 1);
+        let partial_start = appended.end_offset;
+        assert_eq!(
+            complete_len(
+                &mut File::open(&path).unwrap(),
+                fs::metadata(&path).unwrap().len()
+            )
+            .unwrap(),
+            partial_start
+        );
+
+        writeln!(file, "{tail}").unwrap();
+        let appended = read_appended_messages(&path, partial_start, None, true).unwrap();
+        assert_eq!(appended.messages[0].uuid, "b");
+        assert_eq!(appended.end_offset, fs::metadata(&path).unwrap().len());
+
+        let counted = read_appended_messages(&path, 0, None, false).unwrap();
+        assert_eq!(counted.line_count, 3);
+        assert!(counted.messages.is_empty());
+    }
+}
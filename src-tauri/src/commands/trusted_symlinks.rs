@@ -0,0 +1,432 @@
+//! Symlink targets the user has approved for `~/.claude`
+//!
+//! Paths under `~/.claude` are refused when they pass through a symlink, which
+//! stops a planted link from pointing the app at files elsewhere. Only the
+//! Claude folder and what's below it are checked; folders above it (a home
+//! folder under `/home -> /var/home`, say) are the user's choice. Dotfile
+//! setups often link `~/.claude` (or `projects`) into a synced folder though,
+//! so the user can approve target folders: a symlink is followed only when it
+//! resolves inside one of them.
+//!
+//! Approved targets are stored in `~/.claude-history-viewer/trusted-symlinks.json`,
+//! apart from user-data.json so metadata imports and sync can't add any. They
+//! are resolved once at startup (and again when the user changes them), and
+//! every check compares against those resolved paths.
+
+use super::fs_utils::atomic_rename;
+use super::metadata::{ensure_metadata_folder, get_metadata_folder};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// File name of the approved targets inside the metadata folder
+const TRUSTED_SYMLINKS_FILE: &str = "trusted-symlinks.json";
+
+/// Contents of the approved targets file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrustedSymlinksFile {
+    #[serde(default)]
+    targets: Vec<String>,
+}
+
+/// An approved target and what it resolved to
+#[derive(Debug, Clone)]
+struct TrustedTarget {
+    path: String,
+    resolved: Result<PathBuf, String>,
+}
+
+lazy_static! {
+    /// Approved targets as resolved at startup or on the last change
+    static ref TRUSTED_TARGETS: RwLock<Vec<TrustedTarget>> = RwLock::new(Vec::new());
+}
+
+/// An approved symlink target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedSymlinkTarget {
+    /// The folder as approved by the user
+    pub path: String,
+    /// Where it resolved to; symlinks are followed into this folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_path: Option<String>,
+    /// Why it could not be resolved, in which case it trusts nothing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A symlink on the way to `~/.claude/projects` that isn't trusted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UntrustedSymlink {
+    pub link: String,
+    /// Where the link resolves to, if it resolves at all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+/// Approved targets and the symlinks still blocking `~/.claude/projects`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedSymlinkStatus {
+    pub targets: Vec<TrustedSymlinkTarget>,
+    pub untrusted_links: Vec<UntrustedSymlink>,
+}
+
+fn trusted_symlinks_path() -> Result<PathBuf, String> {
+    Ok(get_metadata_folder()?.join(TRUSTED_SYMLINKS_FILE))
+}
+
+/// Resolve an approved target to the real folder it names
+///
+/// A filesystem root, the home folder or a folder holding it would trust
+/// nearly everything, so all are refused.
+fn resolve_target(path: &str) -> Result<PathBuf, String> {
+    let raw = Path::new(path);
+    if !raw.is_absolute() {
+        return Err(format!("Trusted symlink target must be absolute: {path}"));
+    }
+    let resolved = raw
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve trusted symlink target {path}: {e}"))?;
+    if !resolved.is_dir() {
+        return Err(format!("Trusted symlink target is not a directory: {path}"));
+    }
+    let home = dirs::home_dir().and_then(|home| home.canonicalize().ok());
+    if resolved.parent().is_none() || home.is_some_and(|home| home.starts_with(&resolved)) {
+        return Err(format!("Trusted symlink target is too broad: {path}"));
+    }
+    Ok(resolved)
+}
+
+fn resolve_targets(paths: Vec<String>) -> Vec<TrustedTarget> {
+    paths
+        .into_iter()
+        .map(|path| TrustedTarget {
+            resolved: resolve_target(&path),
+            path,
+        })
+        .collect()
+}
+
+fn replace_targets(targets: Vec<TrustedTarget>) -> Result<(), String> {
+    *TRUSTED_TARGETS
+        .write()
+        .map_err(|e| format!("Failed to lock trusted symlink targets: {e}"))? = targets;
+    Ok(())
+}
+
+fn resolved_roots() -> Vec<PathBuf> {
+    TRUSTED_TARGETS
+        .read()
+        .map(|targets| {
+            targets
+                .iter()
+                .filter_map(|target| target.resolved.clone().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Read and resolve the approved targets; called once at startup
+///
+/// Targets that no longer resolve are kept in the file but trust nothing.
+pub(crate) fn load_trusted_targets() {
+    let approved = trusted_symlinks_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| {
+            serde_json::from_str::<TrustedSymlinksFile>(&content).unwrap_or_else(|e| {
+                log::warn!("Ignoring unreadable trusted symlink targets: {e}");
+                TrustedSymlinksFile::default()
+            })
+        })
+        .unwrap_or_default();
+    let targets = resolve_targets(approved.targets);
+    for target in &targets {
+        if let Err(e) = &target.resolved {
+            log::warn!("{e}");
+        }
+    }
+    if let Err(e) = replace_targets(targets) {
+        log::error!("{e}");
+    }
+}
+
+fn save_approved(paths: &[String]) -> Result<(), String> {
+    ensure_metadata_folder()?;
+    let path = trusted_symlinks_path()?;
+    let content = serde_json::to_string_pretty(&TrustedSymlinksFile {
+        targets: paths.to_vec(),
+    })
+    .map_err(|e| format!("Failed to serialize trusted symlink targets: {e}"))?;
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, content)
+        .map_err(|e| format!("Failed to write trusted symlink targets: {e}"))?;
+    atomic_rename(&temp_path, &path)
+}
+
+fn is_within(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().any(|root| path.starts_with(root))
+}
+
+/// `path` and what it resolves to, if it is a symlink
+fn as_symlink(path: &Path) -> Option<(PathBuf, Option<PathBuf>)> {
+    fs::symlink_metadata(path)
+        .is_ok_and(|m| m.file_type().is_symlink())
+        .then(|| (path.to_path_buf(), path.canonicalize().ok()))
+}
+
+/// Symlinks among `path` and its ancestors up to `claude_folder`, with what
+/// each resolves to
+fn symlinks_below(claude_folder: &Path, path: &Path) -> Vec<(PathBuf, Option<PathBuf>)> {
+    path.ancestors()
+        .take_while(|ancestor| ancestor.starts_with(claude_folder))
+        .filter_map(as_symlink)
+        .collect()
+}
+
+fn check_link_within(link: &Path, target: Option<&Path>, roots: &[PathBuf]) -> Result<(), String> {
+    match target {
+        Some(target) if is_within(target, roots) => Ok(()),
+        Some(target) => Err(format!(
+            "Symlink {} points to {}, which is not a trusted symlink target",
+            link.display(),
+            target.display()
+        )),
+        None => Err(format!("Failed to resolve symlink {}", link.display())),
+    }
+}
+
+fn check_symlinks_within(
+    claude_folder: &Path,
+    path: &Path,
+    roots: &[PathBuf],
+) -> Result<(), String> {
+    let inside = path.starts_with(claude_folder)
+        && !path
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir));
+    if !inside {
+        return Err(format!(
+            "{} is not inside the Claude folder {}",
+            path.display(),
+            claude_folder.display()
+        ));
+    }
+    symlinks_below(claude_folder, path)
+        .iter()
+        .try_for_each(|(link, target)| check_link_within(link, target.as_deref(), roots))
+}
+
+/// Fail if `path` or an ancestor of it inside `claude_folder` (the Claude
+/// folder included) is a symlink that doesn't resolve into an approved target
+pub(crate) fn check_symlinks(claude_folder: &Path, path: &Path) -> Result<(), String> {
+    check_symlinks_within(claude_folder, path, &resolved_roots())
+}
+
+/// Fail if a Claude folder or its `projects` folder is a symlink that doesn't
+/// resolve into an approved target
+pub(crate) fn check_claude_folder(claude_folder: &Path) -> Result<(), String> {
+    check_symlinks(claude_folder, &claude_folder.join("projects"))
+}
+
+/// Check a project folder or session file the way [`check_symlinks`] does,
+/// taking the folder holding its `projects` folder as the Claude folder
+///
+/// A path not under any `projects` folder isn't in a Claude folder and has
+/// nothing to check.
+pub(crate) fn check_session_path(path: &Path) -> Result<(), String> {
+    let claude_folder = path
+        .ancestors()
+        .find(|ancestor| ancestor.file_name().is_some_and(|name| name == "projects"))
+        .and_then(Path::parent);
+    match claude_folder {
+        Some(claude_folder) => check_symlinks(claude_folder, path),
+        None => Ok(()),
+    }
+}
+
+/// Whether a resolved path lies inside an approved target
+pub(crate) fn is_trusted(resolved: &Path) -> bool {
+    is_within(resolved, &resolved_roots())
+}
+
+/// `~/.claude`, resolved through trusted symlinks only
+///
+/// Returned unresolved if it doesn't exist yet.
+pub(crate) fn claude_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let claude_dir = home.join(".claude");
+    check_symlinks(&claude_dir, &claude_dir)?;
+    if !claude_dir.exists() {
+        return Ok(claude_dir);
+    }
+    claude_dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve Claude folder: {e}"))
+}
+
+fn status() -> Result<TrustedSymlinkStatus, String> {
+    let targets = TRUSTED_TARGETS
+        .read()
+        .map_err(|e| format!("Failed to lock trusted symlink targets: {e}"))?
+        .iter()
+        .map(|target| TrustedSymlinkTarget {
+            path: target.path.clone(),
+            resolved_path: target
+                .resolved
+                .as_ref()
+                .ok()
+                .map(|p| p.to_string_lossy().to_string()),
+            error: target.resolved.as_ref().err().cloned(),
+        })
+        .collect();
+
+    let roots = resolved_roots();
+    let claude_folder = dirs::home_dir()
+        .ok_or("Could not find home directory")?
+        .join(".claude");
+    let untrusted_links = symlinks_below(&claude_folder, &claude_folder.join("projects"))
+        .into_iter()
+        .filter(|(_, target)| !target.as_deref().is_some_and(|t| is_within(t, &roots)))
+        .map(|(link, target)| UntrustedSymlink {
+            link: link.to_string_lossy().to_string(),
+            target: target.map(|t| t.to_string_lossy().to_string()),
+        })
+        .collect();
+
+    Ok(TrustedSymlinkStatus {
+        targets,
+        untrusted_links,
+    })
+}
+
+/// Approved symlink targets, and any symlinks on the way to
+/// `~/.claude/projects` that still need approving
+#[tauri::command]
+pub async fn get_trusted_symlink_targets() -> Result<TrustedSymlinkStatus, String> {
+    tauri::async_runtime::spawn_blocking(status)
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Replace the approved symlink targets
+///
+/// Every target must resolve to a folder; nothing is saved otherwise. Session
+/// watchers that were refused need starting again afterwards.
+#[tauri::command]
+pub async fn set_trusted_symlink_targets(
+    targets: Vec<String>,
+) -> Result<TrustedSymlinkStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut paths: Vec<String> = Vec::new();
+        for path in targets {
+            let path = path.trim().to_string();
+            if !path.is_empty() && !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        let resolved = resolve_targets(paths.clone());
+        if let Some(Err(e)) = resolved.iter().map(|t| &t.resolved).find(|r| r.is_err()) {
+            return Err(e.clone());
+        }
+        save_approved(&paths)?;
+        replace_targets(resolved)?;
+        status()
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_followed_only_into_trusted_targets() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let synced = root.join("sync/claude");
+        fs::create_dir_all(synced.join("projects/-p")).unwrap();
+        fs::create_dir_all(root.join("elsewhere")).unwrap();
+        fs::create_dir_all(root.join("home")).unwrap();
+        symlink(&synced, root.join("home/.claude")).unwrap();
+        let claude = root.join("home/.claude");
+        let session = claude.join("projects/-p/s.jsonl");
+
+        let err = check_symlinks_within(&claude, &session, &[]).unwrap_err();
+        assert!(err.contains("not a trusted symlink target"));
+        let trusted = [resolve_target(root.join("sync").to_str().unwrap()).unwrap()];
+        assert!(check_symlinks_within(&claude, &session, &trusted).is_ok());
+
+        // A link inside the trusted folder can't lead back out of it
+        symlink(root.join("elsewhere"), synced.join("projects/-q")).unwrap();
+        let escaped = claude.join("projects/-q/s.jsonl");
+        assert!(check_symlinks_within(&claude, &escaped, &trusted).is_err());
+
+        // So can't a link that no longer resolves
+        symlink(root.join("missing"), synced.join("projects/-r")).unwrap();
+        let dangling = claude.join("projects/-r/s.jsonl");
+        assert!(check_symlinks_within(&claude, &dangling, &trusted).is_err());
+
+        // Nor a path leaving the Claude folder
+        let outside = claude.join("../elsewhere/s.jsonl");
+        assert!(check_symlinks_within(&claude, &outside, &trusted).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_folders_above_claude_folder_not_checked() {
+        use std::os::unix::fs::symlink;
+
+        // A home folder reached through a symlink, like /home -> /var/home
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("var/home/me/.claude/projects/-p")).unwrap();
+        symlink(root.join("var/home"), root.join("home")).unwrap();
+        let claude = root.join("home/me/.claude");
+        let session = claude.join("projects/-p/s.jsonl");
+
+        assert!(check_symlinks_within(&claude, &session, &[]).is_ok());
+        assert!(check_session_path(&session).is_ok());
+        assert!(check_claude_folder(&claude).is_ok());
+
+        // Below the Claude folder links still need approving
+        symlink(root.join("var"), claude.join("projects/-q")).unwrap();
+        let linked = claude.join("projects/-q/s.jsonl");
+        let err = check_session_path(&linked).unwrap_err();
+        assert!(err.contains("not a trusted symlink target"));
+        fs::write(root.join("var/s.jsonl"), "{}\n").unwrap();
+        let located =
+            crate::commands::session::locate_messages(linked.to_str().unwrap(), &[], false);
+        assert!(located.is_err());
+    }
+
+    #[test]
+    fn test_resolve_target_rejects_unsafe_targets() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("file");
+        fs::write(&file, "").unwrap();
+
+        assert!(resolve_target("relative/dir").is_err());
+        assert!(resolve_target(file.to_str().unwrap()).is_err());
+        assert!(resolve_target(temp_dir.path().join("missing").to_str().unwrap()).is_err());
+        assert!(resolve_target("/").is_err());
+        assert!(resolve_target(temp_dir.path().to_str().unwrap()).is_ok());
+
+        // The home folder and anything holding it trust too much
+        if let Some(home) = dirs::home_dir().and_then(|home| home.canonicalize().ok()) {
+            assert!(resolve_target(home.to_str().unwrap()).is_err());
+            if let Some(parent) = home.parent() {
+                assert!(resolve_target(parent.to_str().unwrap()).is_err());
+            }
+        }
+    }
+}
@@ -0,0 +1,575 @@
+//! One supervised session watcher per Claude folder
+//!
+//! Each root gets a thread that owns its debouncer and [`SessionTracker`].
+//! The thread re-arms the watcher after a watch error or when `projects/` is
+//! deleted and recreated (a watch follows the old directory, not the path),
+//! rescanning so nothing written in between is missed. Where native events
+//! are unavailable or unreliable (network filesystems, exhausted inotify
+//! watches) it rescans on an interval instead. [`WatcherManager::stop_all`]
+//! stops and joins every thread.
+
+use super::tracker::{file_id, SessionChange, SessionTracker};
+use chrono::Utc;
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebouncedEvent, Debouncer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How a root is watched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum WatchMode {
+    /// Native events, or polling on network filesystems and when they fail
+    Auto,
+    Native,
+    Poll,
+}
+
+impl WatchMode {
+    pub(super) fn parse(mode: Option<&str>) -> Result<Self, String> {
+        match mode.unwrap_or("auto") {
+            "auto" => Ok(Self::Auto),
+            "native" => Ok(Self::Native),
+            "poll" => Ok(Self::Poll),
+            other => Err(format!("Invalid watch mode: {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct WatcherOptions {
+    pub mode: WatchMode,
+    pub debounce: Duration,
+    /// How often the projects folder is checked for replacement
+    pub check_interval: Duration,
+    /// How often a polled root is rescanned
+    pub poll_interval: Duration,
+}
+
+impl Default for WatcherOptions {
+    fn default() -> Self {
+        Self {
+            mode: WatchMode::Auto,
+            debounce: Duration::from_millis(500),
+            check_interval: Duration::from_secs(2),
+            poll_interval: Duration::from_secs(3),
+        }
+    }
+}
+
+/// State of the watcher on one Claude folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherHealth {
+    pub root: String,
+    /// "native" or "poll"
+    pub mode: String,
+    /// "watching", "waiting" (no projects folder to watch yet) or "stopped"
+    pub status: String,
+    pub started_at: String,
+    /// Last time a session change was seen
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_event_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Times the watch was set up again after an error or a replaced folder
+    pub rearm_count: u32,
+}
+
+/// What a root watcher reports
+pub(super) enum WatcherNotice {
+    Change {
+        path: PathBuf,
+        change: SessionChange,
+        size: u64,
+    },
+    Health(WatcherHealth),
+}
+
+pub(super) type NoticeSink = Arc<dyn Fn(WatcherNotice) + Send + Sync>;
+
+enum Message {
+    Events(Vec<PathBuf>),
+    Error(String),
+    Stop,
+}
+
+/// Filesystems whose native change events can't be relied on
+#[cfg(target_os = "linux")]
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smbfs",
+    "smb3",
+    "9p",
+    "afs",
+    "ceph",
+    "glusterfs",
+    "fuse.sshfs",
+    "fuse.rclone",
+];
+
+/// Whether `path` lies on a network filesystem, by its mount in `/proc/mounts`
+#[cfg(target_os = "linux")]
+fn is_network_filesystem(path: &Path) -> bool {
+    let Ok(mounts) = fs::read_to_string("/proc/mounts") else {
+        return false;
+    };
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            path.starts_with(&mount_point)
+                .then(|| (mount_point.len(), fs_type.to_string()))
+        })
+        .max_by_key(|(len, _)| *len)
+        .is_some_and(|(_, fs_type)| NETWORK_FILESYSTEMS.contains(&fs_type.as_str()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_network_filesystem(_path: &Path) -> bool {
+    false
+}
+
+/// Identity of the projects folder, `None` while it is missing
+///
+/// Without inodes every folder looks alike, and a replaced one is only
+/// noticed through the errors its old watch reports.
+fn folder_id(path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    metadata
+        .is_dir()
+        .then(|| file_id(&metadata).unwrap_or_default())
+}
+
+struct Supervisor {
+    projects: PathBuf,
+    options: WatcherOptions,
+    sink: NoticeSink,
+    sender: Sender<Message>,
+    health: Arc<Mutex<WatcherHealth>>,
+    tracker: SessionTracker,
+    debouncer: Option<Debouncer<RecommendedWatcher>>,
+    polling: bool,
+    /// Identity of the folder the current watch was set up on
+    armed_on: Option<(u64, u64)>,
+}
+
+impl Supervisor {
+    fn update_health(&self, update: impl FnOnce(&mut WatcherHealth)) {
+        let snapshot = {
+            let Ok(mut health) = self.health.lock() else {
+                return;
+            };
+            let before = health.clone();
+            update(&mut health);
+            // Event times alone aren't worth a notice
+            let mut quiet = health.clone();
+            quiet.last_event_at.clone_from(&before.last_event_at);
+            (quiet != before).then(|| health.clone())
+        };
+        if let Some(snapshot) = snapshot {
+            (self.sink)(WatcherNotice::Health(snapshot));
+        }
+    }
+
+    fn apply(&mut self, paths: &[PathBuf]) {
+        let changes = self.tracker.update(paths);
+        self.publish(changes);
+    }
+
+    fn publish(&self, changes: Vec<(PathBuf, SessionChange)>) {
+        if changes.is_empty() {
+            return;
+        }
+        self.update_health(|health| health.last_event_at = Some(Utc::now().to_rfc3339()));
+        for (path, change) in changes {
+            let size = self.tracker.size(&path);
+            (self.sink)(WatcherNotice::Change { path, change, size });
+        }
+    }
+
+    fn rescan(&mut self) {
+        let changes = self.tracker.rescan(&self.projects);
+        self.publish(changes);
+    }
+
+    fn native_watcher(&self) -> Result<Debouncer<RecommendedWatcher>, String> {
+        let sender = self.sender.clone();
+        let mut debouncer = new_debouncer(
+            self.options.debounce,
+            move |result: Result<Vec<DebouncedEvent>, notify::Error>| {
+                let message = match result {
+                    Ok(events) => Message::Events(events.into_iter().map(|e| e.path).collect()),
+                    Err(error) => Message::Error(format!("{error:?}")),
+                };
+                // The supervisor is gone once stopped
+                let _ = sender.send(message);
+            },
+        )
+        .map_err(|e| format!("Failed to create file watcher: {e}"))?;
+        debouncer
+            .watcher()
+            .watch(&self.projects, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch directory: {e}"))?;
+        Ok(debouncer)
+    }
+
+    /// Set up the watch on the projects folder as it is now
+    fn arm(&mut self) {
+        self.debouncer = None;
+        let Some(id) = folder_id(&self.projects) else {
+            self.armed_on = None;
+            self.update_health(|health| health.status = "waiting".to_string());
+            return;
+        };
+        // Catch up on whatever happened while nothing was watching
+        self.rescan();
+
+        let mut error = None;
+        self.polling = match self.options.mode {
+            WatchMode::Poll => true,
+            WatchMode::Auto if is_network_filesystem(&self.projects) => true,
+            WatchMode::Auto | WatchMode::Native => match self.native_watcher() {
+                Ok(debouncer) => {
+                    self.debouncer = Some(debouncer);
+                    false
+                }
+                Err(e) => {
+                    log::warn!("Native file watcher unavailable: {e}");
+                    error = Some(e);
+                    self.options.mode == WatchMode::Auto
+                }
+            },
+        };
+
+        let watching = self.polling || self.debouncer.is_some();
+        // A failed native watch is retried on the next check
+        self.armed_on = watching.then_some(id);
+        let polling = self.polling;
+        self.update_health(|health| {
+            health.mode = if polling { "poll" } else { "native" }.to_string();
+            health.status = if watching { "watching" } else { "waiting" }.to_string();
+            if error.is_some() {
+                health.last_error = error;
+            }
+        });
+    }
+
+    fn rearm(&mut self) {
+        self.update_health(|health| health.rearm_count += 1);
+        self.arm();
+    }
+
+    /// Notice a missing or replaced projects folder; rescan when polling
+    fn check(&mut self) {
+        let current = folder_id(&self.projects);
+        if current.is_none() {
+            if self.armed_on.is_some() {
+                self.debouncer = None;
+                self.armed_on = None;
+                self.rescan();
+                self.update_health(|health| health.status = "waiting".to_string());
+            }
+            return;
+        }
+        if current != self.armed_on {
+            self.rearm();
+        } else if self.polling {
+            self.rescan();
+        }
+    }
+
+    fn run(mut self, receiver: &Receiver<Message>) {
+        self.arm();
+        let mut last_check = Instant::now();
+        loop {
+            let interval = if self.polling {
+                self.options.poll_interval
+            } else {
+                self.options.check_interval
+            };
+            let wait = interval.saturating_sub(last_check.elapsed());
+            match receiver.recv_timeout(wait) {
+                Ok(Message::Events(paths)) => self.apply(&paths),
+                Ok(Message::Error(error)) => {
+                    log::error!("File watcher error: {error}");
+                    self.update_health(|health| health.last_error = Some(error));
+                    self.rearm();
+                    last_check = Instant::now();
+                }
+                Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if last_check.elapsed() >= interval {
+                self.check();
+                last_check = Instant::now();
+            }
+        }
+        self.debouncer = None;
+        self.update_health(|health| health.status = "stopped".to_string());
+    }
+}
+
+struct RootWatcher {
+    sender: Sender<Message>,
+    health: Arc<Mutex<WatcherHealth>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RootWatcher {
+    fn stop(mut self) {
+        let _ = self.sender.send(Message::Stop);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::error!("File watcher thread panicked");
+            }
+        }
+    }
+}
+
+/// Session watchers by Claude folder
+#[derive(Default)]
+pub struct WatcherManager {
+    roots: Mutex<HashMap<PathBuf, RootWatcher>>,
+}
+
+impl WatcherManager {
+    /// Watch `root/projects`, replacing any watcher already on `root`
+    ///
+    /// The folder may be missing; it is picked up once it appears.
+    pub(super) fn start(
+        &self,
+        root: &Path,
+        options: WatcherOptions,
+        sink: NoticeSink,
+    ) -> Result<(), String> {
+        self.stop(root)?;
+
+        let projects = root.join("projects");
+        let (sender, receiver) = mpsc::channel();
+        let health = Arc::new(Mutex::new(WatcherHealth {
+            root: root.to_string_lossy().to_string(),
+            mode: if options.mode == WatchMode::Poll {
+                "poll"
+            } else {
+                "native"
+            }
+            .to_string(),
+            status: "waiting".to_string(),
+            started_at: Utc::now().to_rfc3339(),
+            last_event_at: None,
+            last_error: None,
+            rearm_count: 0,
+        }));
+        let supervisor = Supervisor {
+            tracker: SessionTracker::scan(&projects),
+            projects,
+            options,
+            sink,
+            sender: sender.clone(),
+            health: Arc::clone(&health),
+            debouncer: None,
+            polling: false,
+            armed_on: None,
+        };
+        let thread = std::thread::Builder::new()
+            .name("session-watcher".to_string())
+            .spawn(move || supervisor.run(&receiver))
+            .map_err(|e| format!("Failed to start file watcher thread: {e}"))?;
+
+        // A concurrent start may have put its own watcher there in the meantime
+        let replaced = self
+            .roots
+            .lock()
+            .map_err(|e| format!("Failed to lock file watchers: {e}"))?
+            .insert(
+                root.to_path_buf(),
+                RootWatcher {
+                    sender,
+                    health,
+                    thread: Some(thread),
+                },
+            );
+        if let Some(replaced) = replaced {
+            replaced.stop();
+        }
+        Ok(())
+    }
+
+    /// Stop the watcher on `root`; false if there was none
+    pub(super) fn stop(&self, root: &Path) -> Result<bool, String> {
+        let watcher = self
+            .roots
+            .lock()
+            .map_err(|e| format!("Failed to lock file watchers: {e}"))?
+            .remove(root);
+        Ok(watcher.map(RootWatcher::stop).is_some())
+    }
+
+    /// Stop every watcher and wait for their threads; returns how many ran
+    pub(crate) fn stop_all(&self) -> usize {
+        let watchers: Vec<RootWatcher> = match self.roots.lock() {
+            Ok(mut roots) => roots.drain().map(|(_, watcher)| watcher).collect(),
+            Err(_) => return 0,
+        };
+        let count = watchers.len();
+        watchers.into_iter().for_each(RootWatcher::stop);
+        count
+    }
+
+    pub(super) fn health(&self) -> Vec<WatcherHealth> {
+        let Ok(roots) = self.roots.lock() else {
+            return Vec::new();
+        };
+        let mut health: Vec<WatcherHealth> = roots
+            .values()
+            .filter_map(|watcher| watcher.health.lock().ok().map(|h| h.clone()))
+            .collect();
+        health.sort_by(|a, b| a.root.cmp(&b.root));
+        health
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn options(mode: WatchMode) -> WatcherOptions {
+        WatcherOptions {
+            mode,
+            debounce: Duration::from_millis(50),
+            check_interval: Duration::from_millis(50),
+            poll_interval: Duration::from_millis(50),
+        }
+    }
+
+    fn channel_sink() -> (NoticeSink, Receiver<WatcherNotice>) {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let sink: NoticeSink = Arc::new(move |notice| {
+            let _ = sender.lock().unwrap().send(notice);
+        });
+        (sink, receiver)
+    }
+
+    /// Wait for a notice matching `want`, skipping others
+    fn wait_for(receiver: &Receiver<WatcherNotice>, want: impl Fn(&WatcherNotice) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            match receiver.recv_timeout(left) {
+                Ok(notice) if want(&notice) => return,
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        panic!("Expected watcher notice did not arrive");
+    }
+
+    fn created(path: &Path) -> impl Fn(&WatcherNotice) -> bool + '_ {
+        move |notice| matches!(notice, WatcherNotice::Change { path: p, change: SessionChange::Created, .. } if p == path)
+    }
+
+    fn status(want: &str) -> impl Fn(&WatcherNotice) -> bool + '_ {
+        move |notice| matches!(notice, WatcherNotice::Health(h) if h.status == want)
+    }
+
+    #[test]
+    fn test_manager_polls_roots_and_stops() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let project = first.path().join("projects").join("-Users-me-app");
+        fs::create_dir_all(&project).unwrap();
+
+        let manager = WatcherManager::default();
+        let (sink, receiver) = channel_sink();
+        manager
+            .start(first.path(), options(WatchMode::Poll), Arc::clone(&sink))
+            .unwrap();
+        manager
+            .start(second.path(), options(WatchMode::Poll), sink)
+            .unwrap();
+        wait_for(&receiver, status("watching"));
+
+        let session = project.join("s.jsonl");
+        fs::write(&session, "{}\n").unwrap();
+        wait_for(&receiver, created(&session));
+
+        let health = manager.health();
+        assert_eq!(health.len(), 2);
+        let first_health = health
+            .iter()
+            .find(|h| h.root == first.path().to_string_lossy())
+            .unwrap();
+        assert_eq!(first_health.mode, "poll");
+        assert!(first_health.last_event_at.is_some());
+        // The second root has no projects folder yet
+        assert!(health.iter().any(|h| h.status == "waiting"));
+
+        assert!(manager.stop(first.path()).unwrap());
+        assert!(!manager.stop(first.path()).unwrap());
+        assert_eq!(manager.stop_all(), 1);
+        assert!(manager.health().is_empty());
+    }
+
+    #[test]
+    fn test_manager_rearms_after_projects_folder_replaced() {
+        let root = TempDir::new().unwrap();
+        let projects = root.path().join("projects");
+        fs::create_dir_all(projects.join("-Users-me-app")).unwrap();
+
+        let manager = WatcherManager::default();
+        let (sink, receiver) = channel_sink();
+        manager
+            .start(root.path(), options(WatchMode::Native), sink)
+            .unwrap();
+        wait_for(&receiver, status("watching"));
+
+        fs::remove_dir_all(&projects).unwrap();
+        wait_for(&receiver, status("waiting"));
+
+        let project = projects.join("-Users-me-new");
+        fs::create_dir_all(&project).unwrap();
+        wait_for(&receiver, status("watching"));
+
+        // Written after the re-arm, so only the new watch can report it
+        let session = project.join("s.jsonl");
+        fs::write(&session, "{}\n").unwrap();
+        wait_for(&receiver, created(&session));
+
+        let health = manager.health();
+        assert_eq!(health[0].mode, "native");
+        assert!(health[0].rearm_count >= 1);
+        assert_eq!(manager.stop_all(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_starts_stop_replaced_watchers() {
+        let root = TempDir::new().unwrap();
+        let manager = WatcherManager::default();
+        let (sink, receiver) = channel_sink();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let sink = Arc::clone(&sink);
+                let manager = &manager;
+                let root = root.path();
+                scope.spawn(move || manager.start(root, options(WatchMode::Poll), sink).unwrap());
+            }
+        });
+        assert_eq!(manager.stop_all(), 1);
+
+        // Every watcher thread ended, not just the one left in the map
+        for _ in 0..4 {
+            wait_for(&receiver, status("stopped"));
+        }
+    }
+}
@@ -0,0 +1,432 @@
+mod manager;
+mod tracker;
+
+pub use manager::{WatcherHealth, WatcherManager};
+pub(crate) use tracker::FileState;
+
+use super::claude_settings::{config_files, is_unseen_change};
+use super::metadata::{reload_if_changed, MetadataState, USER_DATA_FILE};
+use super::session::read_appended_messages;
+use super::trusted_symlinks::{check_claude_folder, is_trusted};
+use crate::models::ClaudeMessage;
+use manager::{NoticeSink, WatchMode, WatcherNotice, WatcherOptions};
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebouncedEvent, Debouncer};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tracker::SessionChange;
+
+/// Appends larger than this are reported by line count and byte range only
+const MAX_INLINE_APPEND_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileWatchEvent {
+    pub project_path: String,
+    pub session_path: String,
+    /// "created", "appended", "rewritten", "deleted" or "renamed"
+    pub event_type: String,
+    /// The old path, for "renamed"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_path: Option<String>,
+    /// File size after the change
+    pub size: u64,
+    /// Start and end (exclusive) of the appended complete lines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub byte_range: Option<[u64; 2]>,
+    /// Messages parsed from an append, or lines appended if they weren't parsed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_count: Option<usize>,
+    /// The appended messages, omitted for appends over 1 MiB
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages: Option<Vec<ClaudeMessage>>,
+}
+
+/// Start watching the Claude projects directory for file changes
+///
+/// Each Claude folder gets its own watcher, and starting one again replaces
+/// it. `mode` is "auto" (the default), "native" or "poll"; polling suits
+/// network filesystems that don't deliver change events. Health changes are
+/// emitted as `file-watcher-health`.
+#[tauri::command]
+pub async fn start_file_watcher(
+    app_handle: AppHandle,
+    claude_folder_path: String,
+    mode: Option<String>,
+) -> Result<String, String> {
+    let mode = WatchMode::parse(mode.as_deref())?;
+    let base_path = PathBuf::from(&claude_folder_path);
+    let projects_path = base_path.join("projects");
+
+    // Reject symlinks to prevent symlink attacks, unless they resolve into a
+    // target the user trusts
+    std::fs::symlink_metadata(&base_path)
+        .map_err(|e| format!("Cannot read metadata for base path: {e}"))?;
+    std::fs::symlink_metadata(&projects_path)
+        .map_err(|e| format!("Cannot read metadata for projects path: {e}"))?;
+    check_claude_folder(&base_path)?;
+
+    // Canonicalize and verify path traversal safety
+    let canonical_base = std::fs::canonicalize(&base_path)
+        .map_err(|e| format!("Failed to canonicalize base path: {e}"))?;
+    let canonical_projects = std::fs::canonicalize(&projects_path)
+        .map_err(|e| format!("Failed to canonicalize projects path: {e}"))?;
+
+    if !canonical_projects.starts_with(&canonical_base) && !is_trusted(&canonical_projects) {
+        return Err("Projects path escapes the allowed base directory".to_string());
+    }
+
+    // Verify it is a directory
+    if !canonical_projects.is_dir() {
+        return Err(format!(
+            "Projects path is not a directory: {}",
+            canonical_projects.display()
+        ));
+    }
+
+    // Report paths under the folder as given, like the loaders do, even when
+    // it is a trusted symlink
+    let as_given = {
+        let canonical_base = canonical_base.clone();
+        move |path: PathBuf| match path.strip_prefix(&canonical_base) {
+            Ok(rest) => base_path.join(rest),
+            Err(_) => path,
+        }
+    };
+    let sink: NoticeSink = {
+        let app_handle = app_handle.clone();
+        Arc::new(move |notice| match notice {
+            WatcherNotice::Change { path, change, size } => {
+                let change = match change {
+                    SessionChange::Renamed { from } => SessionChange::Renamed {
+                        from: as_given(from),
+                    },
+                    change => change,
+                };
+                emit_session_change(&app_handle, &as_given(path), change, size);
+            }
+            WatcherNotice::Health(health) => {
+                if let Err(e) = app_handle.emit("file-watcher-health", &health) {
+                    log::error!("Failed to emit file watcher health: {e}");
+                }
+            }
+        })
+    };
+
+    // Recording every session file takes a while on large folders
+    tauri::async_runtime::spawn_blocking(move || {
+        let options = WatcherOptions {
+            mode,
+            ..Default::default()
+        };
+        app_handle
+            .state::<WatcherManager>()
+            .start(&canonical_base, options, sink)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))??;
+
+    log::info!("File watcher started for: {}", canonical_projects.display());
+    Ok("watcher-started".to_string())
+}
+
+/// Stop the file watcher on one Claude folder, or all of them
+#[tauri::command]
+pub async fn stop_file_watcher(
+    app_handle: AppHandle,
+    claude_folder_path: Option<String>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let manager = app_handle.state::<WatcherManager>();
+        let stopped = match claude_folder_path {
+            Some(path) => {
+                let root = std::fs::canonicalize(&path).unwrap_or_else(|_| PathBuf::from(path));
+                usize::from(manager.stop(&root)?)
+            }
+            None => manager.stop_all(),
+        };
+        if stopped == 0 {
+            return Err("No active file watcher found".to_string());
+        }
+        log::info!("File watcher stopped");
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))?
+}
+
+/// Status of each running file watcher
+#[tauri::command]
+pub async fn get_file_watcher_health(
+    manager: State<'_, WatcherManager>,
+) -> Result<Vec<WatcherHealth>, String> {
+    Ok(manager.health())
+}
+
+/// Watch user-data.json for changes saved by another app instance or a sync tool
+///
+/// When the file no longer matches the cached metadata, the cache is refreshed
+/// and `user-metadata-changed` is emitted with the new metadata.
+pub(crate) fn watch_user_metadata(
+    app_handle: AppHandle,
+    metadata_folder: &Path,
+) -> Result<Debouncer<RecommendedWatcher>, String> {
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(500),
+        move |result: Result<Vec<DebouncedEvent>, notify::Error>| match result {
+            Ok(events) => {
+                let touched = events
+                    .iter()
+                    .any(|event| event.path.file_name().is_some_and(|n| n == USER_DATA_FILE));
+                if !touched {
+                    return;
+                }
+                let state: tauri::State<MetadataState> = app_handle.state();
+                match reload_if_changed(&state) {
+                    Ok(Some(metadata)) => {
+                        if let Err(e) = app_handle.emit("user-metadata-changed", &metadata) {
+                            log::error!("Failed to emit metadata change event: {e}");
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::error!("Failed to reload metadata: {e}"),
+                }
+            }
+            Err(error) => {
+                log::error!("Metadata watcher error: {error:?}");
+            }
+        },
+    )
+    .map_err(|e| format!("Failed to create metadata watcher: {e}"))?;
+
+    // Watch the folder: the file itself is replaced on every atomic save
+    debouncer
+        .watcher()
+        .watch(metadata_folder, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch metadata folder: {e}"))?;
+
+    Ok(debouncer)
+}
+
+/// A Claude Code config file changed outside the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFileChangeEvent {
+    /// "user", "project", "local", "managed", "`user_mcp`", "`project_mcp`" or "`claude_json`"
+    pub scope: String,
+    pub path: String,
+    /// The project whose files are watched, for project scopes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    /// False when the file was deleted
+    pub exists: bool,
+}
+
+/// Watcher for the files shown in the settings manager
+#[derive(Default)]
+pub struct ConfigWatcherState {
+    watcher: Mutex<Option<Debouncer<RecommendedWatcher>>>,
+}
+
+/// Watch the Claude Code config files and emit `claude-config-changed`
+///
+/// Covers `~/.claude/settings.json`, `~/.claude/.mcp.json`, `~/.claude.json`,
+/// managed settings and, with `project_path`, that project's
+/// `.claude/settings*.json` and `.mcp.json`. Writes by the app itself and
+/// content already read are not reported. Replaces any previous config
+/// watcher, so call it again when the selected project changes.
+#[tauri::command]
+pub async fn start_config_watcher(
+    app_handle: AppHandle,
+    project_path: Option<String>,
+    state: State<'_, ConfigWatcherState>,
+) -> Result<(), String> {
+    let files = config_files(project_path.as_deref())?;
+    // Folders are watched since the files are replaced on atomic saves; a
+    // folder that doesn't exist yet (e.g. a project without `.claude`) is skipped
+    let folders: BTreeSet<PathBuf> = files
+        .iter()
+        .filter_map(|(_, path)| path.parent())
+        .filter(|folder| folder.is_dir())
+        .map(Path::to_path_buf)
+        .collect();
+
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(500),
+        move |result: Result<Vec<DebouncedEvent>, notify::Error>| match result {
+            Ok(events) => {
+                for (scope, path) in &files {
+                    if !events.iter().any(|event| event.path == *path) || !is_unseen_change(path) {
+                        continue;
+                    }
+                    let is_project_scope = matches!(*scope, "project" | "local" | "project_mcp");
+                    let change = ConfigFileChangeEvent {
+                        scope: (*scope).to_string(),
+                        path: path.to_string_lossy().to_string(),
+                        project_path: project_path.clone().filter(|_| is_project_scope),
+                        exists: path.exists(),
+                    };
+                    if let Err(e) = app_handle.emit("claude-config-changed", &change) {
+                        log::error!("Failed to emit config change event: {e}");
+                    }
+                }
+            }
+            Err(error) => {
+                log::error!("Config watcher error: {error:?}");
+            }
+        },
+    )
+    .map_err(|e| format!("Failed to create config watcher: {e}"))?;
+
+    for folder in &folders {
+        debouncer
+            .watcher()
+            .watch(folder, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch config folder: {e}"))?;
+    }
+
+    *state
+        .watcher
+        .lock()
+        .map_err(|e| format!("Failed to lock config watcher: {e}"))? = Some(debouncer);
+    Ok(())
+}
+
+/// Stop the config watcher; nothing to do if it isn't running
+#[tauri::command]
+pub async fn stop_config_watcher(state: State<'_, ConfigWatcherState>) -> Result<(), String> {
+    state
+        .watcher
+        .lock()
+        .map_err(|e| format!("Failed to lock config watcher: {e}"))?
+        .take();
+    Ok(())
+}
+
+/// Emit a session change; appends carry the new messages
+fn emit_session_change(app_handle: &AppHandle, path: &Path, change: SessionChange, size: u64) {
+    let Some((project_path, session_path)) = extract_paths(path) else {
+        log::warn!("Could not extract paths from: {}", path.display());
+        return;
+    };
+    let mut watch_event = FileWatchEvent {
+        project_path: project_path.to_string_lossy().to_string(),
+        session_path: session_path.to_string_lossy().to_string(),
+        event_type: String::new(),
+        previous_path: None,
+        size,
+        byte_range: None,
+        message_count: None,
+        messages: None,
+    };
+
+    let (event_name, event_type) = match change {
+        SessionChange::Created => ("session-file-created", "created"),
+        SessionChange::Renamed { from } => {
+            watch_event.previous_path = Some(from.to_string_lossy().to_string());
+            ("session-file-created", "renamed")
+        }
+        SessionChange::Deleted => ("session-file-deleted", "deleted"),
+        SessionChange::Rewritten => ("session-file-changed", "rewritten"),
+        SessionChange::Appended { from, to } => {
+            let parse = to - from <= MAX_INLINE_APPEND_BYTES;
+            match read_appended_messages(path, from, Some(to - from), parse) {
+                Ok(appended) => {
+                    watch_event.message_count = Some(if parse {
+                        appended.messages.len()
+                    } else {
+                        appended.line_count
+                    });
+                    watch_event.messages = parse.then_some(appended.messages);
+                    watch_event.byte_range = Some([from, appended.end_offset]);
+                }
+                Err(e) => {
+                    log::warn!("Failed to read appended messages: {e}");
+                    watch_event.byte_range = Some([from, to]);
+                }
+            }
+            ("session-file-changed", "appended")
+        }
+    };
+    watch_event.event_type = event_type.to_string();
+
+    // Emit Tauri event to frontend
+    if let Err(e) = app_handle.emit(event_name, &watch_event) {
+        log::error!("Failed to emit file watch event: {e}");
+    }
+}
+
+/// Extract project path and session path from a `.jsonl` file path
+///
+/// Expected format: `~/.claude/projects/{project_name}/{session_file}.jsonl`
+fn extract_paths(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let components: Vec<_> = path.components().collect();
+    let len = components.len();
+
+    // Need at least: [..., "projects", "project_name", "file.jsonl"]
+    if len < 3 {
+        return None;
+    }
+
+    // Find the "projects" component
+    let projects_idx = components
+        .iter()
+        .position(|c| c.as_os_str() == "projects")?;
+
+    // Ensure we have at least project_name and filename after "projects"
+    if projects_idx + 2 >= len {
+        return None;
+    }
+
+    // Reconstruct project path: everything up to and including project_name
+    let mut project_path = PathBuf::new();
+    for component in &components[..=projects_idx + 1] {
+        project_path.push(component);
+    }
+
+    // Session path is the full path
+    let session_path = path.to_path_buf();
+
+    Some((project_path, session_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_paths() {
+        let path = PathBuf::from("/Users/test/.claude/projects/my-project/session.jsonl");
+        let result = extract_paths(&path);
+
+        assert!(result.is_some());
+        let (project_path, session_path) = result.unwrap();
+
+        assert!(project_path.ends_with("projects/my-project"));
+        assert_eq!(session_path, path);
+    }
+
+    #[test]
+    fn test_extract_paths_nested() {
+        let path = PathBuf::from("/Users/test/.claude/projects/my-project/subfolder/session.jsonl");
+        let result = extract_paths(&path);
+
+        assert!(result.is_some());
+        let (project_path, session_path) = result.unwrap();
+
+        assert!(project_path.ends_with("projects/my-project"));
+        assert_eq!(session_path, path);
+    }
+
+    #[test]
+    fn test_extract_paths_invalid() {
+        let path = PathBuf::from("/Users/test/session.jsonl");
+        let result = extract_paths(&path);
+
+        assert!(result.is_none());
+    }
+}
@@ -0,0 +1,433 @@
+//! What changed in a session file since it was last seen
+//!
+//! notify only reports that a path was touched, so [`SessionTracker`] keeps
+//! the size, identity and hashes of the start and the last complete line of
+//! each session file and compares them to tell appends from rewrites,
+//! renames, creations and deletions.
+
+use crate::commands::session::complete_len;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Bytes hashed at the start of each file to notice rewrites
+const HEAD_BYTES: u64 = 4096;
+
+/// Bytes hashed before the end of the last complete line, which catch
+/// rewrites past the head that don't shrink the file where there are no
+/// inodes to compare
+const TAIL_BYTES: u64 = 4096;
+
+/// What the watcher last saw of a session file
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileState {
+    size: u64,
+    /// End of the last complete line; appends are read from here
+    offset: u64,
+    modified: Option<SystemTime>,
+    /// Device and inode, to tell a replaced file from an appended one
+    file_id: Option<(u64, u64)>,
+    /// Hash of the first `head_len` bytes
+    head: u64,
+    head_len: u64,
+    /// Hash of the `tail_len` bytes before `offset`
+    tail: u64,
+    tail_len: u64,
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)] // Other platforms have no inode to return
+pub(super) fn file_id(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+pub(super) fn file_id(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+fn hash_range(file: &mut File, start: u64, len: u64) -> Option<u64> {
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut buffer = Vec::new();
+    file.take(len).read_to_end(&mut buffer).ok()?;
+    if buffer.len() as u64 != len {
+        return None;
+    }
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&buffer);
+    Some(hasher.finish())
+}
+
+impl FileState {
+    pub(crate) fn read(path: &Path) -> Option<Self> {
+        let mut file = File::open(path).ok()?;
+        let metadata = file.metadata().ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        let size = metadata.len();
+        let head_len = size.min(HEAD_BYTES);
+        let head = hash_range(&mut file, 0, head_len)?;
+        let offset = complete_len(&mut file, size).ok()?;
+        let tail_len = offset.min(TAIL_BYTES);
+        let tail = hash_range(&mut file, offset - tail_len, tail_len)?;
+        Some(Self {
+            size,
+            offset,
+            modified: metadata.modified().ok(),
+            file_id: file_id(&metadata),
+            head,
+            head_len,
+            tail,
+            tail_len,
+        })
+    }
+
+    /// Whether `current` is this file with bytes added at the end
+    ///
+    /// Both the start and the bytes up to the old end of the last complete
+    /// line must be as they were.
+    pub(crate) fn is_extended_by(&self, path: &Path, current: &Self) -> bool {
+        if current.size < self.size || current.file_id != self.file_id {
+            return false;
+        }
+        let Ok(mut file) = File::open(path) else {
+            return false;
+        };
+        hash_range(&mut file, 0, self.head_len) == Some(self.head)
+            && hash_range(&mut file, self.offset - self.tail_len, self.tail_len) == Some(self.tail)
+    }
+
+    /// End of the last complete line when the state was read
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Same size, identity and timestamp on disk, checked without opening the file
+    fn is_current(&self, path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|metadata| {
+            metadata.is_file()
+                && metadata.len() == self.size
+                && metadata.modified().ok() == self.modified
+                && file_id(&metadata) == self.file_id
+        })
+    }
+
+    /// Same content as far as size, identity and timestamps tell
+    pub(crate) fn is_unchanged(&self, current: &Self) -> bool {
+        self.size == current.size
+            && self.modified == current.modified
+            && self.file_id == current.file_id
+            && self.head == current.head
+            && self.tail == current.tail
+    }
+}
+
+/// How a session file changed
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum SessionChange {
+    Created,
+    /// Complete lines were added in `from..to`
+    Appended {
+        from: u64,
+        to: u64,
+    },
+    /// Truncated, replaced or edited in place
+    Rewritten,
+    Deleted,
+    Renamed {
+        from: PathBuf,
+    },
+}
+
+/// Size and offset of every session file under the watched folder
+#[derive(Debug, Default)]
+pub(super) struct SessionTracker {
+    files: HashMap<PathBuf, FileState>,
+}
+
+fn is_session_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "jsonl")
+}
+
+fn session_files(folder: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkDir::new(folder)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.file_type().is_file() && is_session_file(e.path()))
+        .map(walkdir::DirEntry::into_path)
+}
+
+impl SessionTracker {
+    pub(super) fn scan(folder: &Path) -> Self {
+        let files = session_files(folder)
+            .filter_map(|path| FileState::read(&path).map(|state| (path, state)))
+            .collect();
+        Self { files }
+    }
+
+    /// Session files affected by events on `paths`
+    ///
+    /// An event on a folder stands for the session files directly in it,
+    /// known or there now, and for the whole of subfolders that are new to
+    /// the tracker or gone. Changes inside subfolders that were already
+    /// there get events of their own.
+    fn affected(&self, paths: &[PathBuf]) -> BTreeSet<PathBuf> {
+        let mut affected = BTreeSet::new();
+        for path in paths {
+            if is_session_file(path) {
+                affected.insert(path.clone());
+                continue;
+            }
+            affected.extend(
+                self.files
+                    .keys()
+                    .filter(|known| {
+                        let Ok(rest) = known.strip_prefix(path) else {
+                            return false;
+                        };
+                        let mut components = rest.components();
+                        let first = components.next();
+                        components.next().is_none()
+                            || first.is_some_and(|first| !path.join(first).is_dir())
+                    })
+                    .cloned(),
+            );
+            let Ok(entries) = fs::read_dir(path) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let entry = entry.path();
+                if entry.is_dir() {
+                    if !self.files.keys().any(|known| known.starts_with(&entry)) {
+                        affected.extend(session_files(&entry));
+                    }
+                } else if is_session_file(&entry) {
+                    affected.insert(entry);
+                }
+            }
+        }
+        affected
+    }
+
+    /// Size of a tracked file, 0 if it isn't tracked
+    pub(super) fn size(&self, path: &Path) -> u64 {
+        self.files.get(path).map_or(0, |state| state.size)
+    }
+
+    /// Compare the files behind a batch of events with what was seen before
+    pub(super) fn update(&mut self, paths: &[PathBuf]) -> Vec<(PathBuf, SessionChange)> {
+        let affected = self.affected(paths);
+        self.compare(affected)
+    }
+
+    /// Compare every session file under `folder`, for polling and catching
+    /// up after a re-arm
+    pub(super) fn rescan(&mut self, folder: &Path) -> Vec<(PathBuf, SessionChange)> {
+        let mut affected: BTreeSet<PathBuf> = self
+            .files
+            .keys()
+            .filter(|known| known.starts_with(folder))
+            .cloned()
+            .collect();
+        affected.extend(session_files(folder));
+        self.compare(affected)
+    }
+
+    /// Files whose size, identity and timestamp are unchanged are not read
+    fn compare(&mut self, affected: BTreeSet<PathBuf>) -> Vec<(PathBuf, SessionChange)> {
+        let mut changes = Vec::new();
+        let mut appeared = Vec::new();
+        let mut vanished = Vec::new();
+        for path in affected {
+            if self
+                .files
+                .get(&path)
+                .is_some_and(|previous| previous.is_current(&path))
+            {
+                continue;
+            }
+            let current = FileState::read(&path);
+            match (self.files.get(&path), current) {
+                (None, None) => {}
+                (None, Some(current)) => appeared.push((path, current)),
+                (Some(_), None) => vanished.push(path),
+                (Some(previous), Some(current)) => {
+                    let change = if previous.is_unchanged(&current) {
+                        None
+                    } else if previous.is_extended_by(&path, &current) {
+                        (current.offset > previous.offset).then_some(SessionChange::Appended {
+                            from: previous.offset,
+                            to: current.offset,
+                        })
+                    } else {
+                        Some(SessionChange::Rewritten)
+                    };
+                    if let Some(change) = change {
+                        changes.push((path.clone(), change));
+                    }
+                    self.files.insert(path, current);
+                }
+            }
+        }
+
+        // A file that vanished and one that appeared in the same batch are a
+        // rename if they are the same file (or, without inodes, look alike)
+        for (path, current) in appeared {
+            let renamed_from = vanished.iter().position(|old| {
+                let previous = &self.files[old];
+                match (previous.file_id, current.file_id) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => previous.size == current.size && previous.head == current.head,
+                }
+            });
+            let change = match renamed_from {
+                Some(index) => {
+                    let from = vanished.swap_remove(index);
+                    self.files.remove(&from);
+                    SessionChange::Renamed { from }
+                }
+                None => SessionChange::Created,
+            };
+            changes.push((path.clone(), change));
+            self.files.insert(path, current);
+        }
+        for path in vanished {
+            self.files.remove(&path);
+            changes.push((path, SessionChange::Deleted));
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn append(path: &Path, text: &str) {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_tracker_classifies_changes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let project = temp_dir.path().join("projects").join("-Users-me-app");
+        fs::create_dir_all(&project).unwrap();
+        let session = project.join("s.jsonl");
+        append(&session, "{\"a\":1}\n");
+        let mut tracker = SessionTracker::scan(temp_dir.path());
+        let batch = |tracker: &mut SessionTracker, paths: &[&Path]| {
+            let paths: Vec<PathBuf> = paths.iter().map(|p| p.to_path_buf()).collect();
+            tracker.update(&paths)
+        };
+
+        // A partial line is only reported once it is complete
+        append(&session, "{\"b\":");
+        assert!(batch(&mut tracker, &[&session]).is_empty());
+        append(&session, "2}\n");
+        assert_eq!(
+            batch(&mut tracker, &[&session]),
+            [(session.clone(), SessionChange::Appended { from: 8, to: 16 })]
+        );
+
+        fs::write(&session, "{\"c\":3}\n").unwrap();
+        assert_eq!(
+            batch(&mut tracker, &[&session]),
+            [(session.clone(), SessionChange::Rewritten)]
+        );
+
+        let created = project.join("new.jsonl");
+        append(&created, "{}\n");
+        assert_eq!(
+            batch(&mut tracker, &[&created]),
+            [(created.clone(), SessionChange::Created)]
+        );
+
+        let renamed = project.join("moved.jsonl");
+        fs::rename(&created, &renamed).unwrap();
+        assert_eq!(
+            batch(&mut tracker, &[&created, &renamed]),
+            [(renamed.clone(), SessionChange::Renamed { from: created })]
+        );
+
+        // Removing the whole folder reports each session in it
+        fs::remove_dir_all(&project).unwrap();
+        let mut changes = batch(&mut tracker, &[&project]);
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            changes,
+            [
+                (renamed, SessionChange::Deleted),
+                (session, SessionChange::Deleted)
+            ]
+        );
+        assert!(tracker.files.is_empty());
+    }
+
+    #[test]
+    fn test_tracker_notices_rewrites_past_the_head() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let session = temp_dir.path().join("s.jsonl");
+        let line = format!("{{\"text\":\"{}\"}}\n", "a".repeat(100));
+        append(&session, &line.repeat(60));
+        let mut tracker = SessionTracker::scan(temp_dir.path());
+
+        // Edit the last line in place, keeping the size and the file, then append
+        let mut file = fs::OpenOptions::new().write(true).open(&session).unwrap();
+        file.seek(SeekFrom::End(-10)).unwrap();
+        std::io::Write::write_all(&mut file, b"bbbbbbbbb\n").unwrap();
+        drop(file);
+        append(&session, &line);
+        assert_eq!(
+            tracker.update(std::slice::from_ref(&session)),
+            [(session, SessionChange::Rewritten)]
+        );
+    }
+
+    #[test]
+    fn test_folder_events_only_rescan_that_folder() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let (app, web) = (root.join("-Users-me-app"), root.join("-Users-me-web"));
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(&web).unwrap();
+        append(&app.join("a.jsonl"), "{}\n");
+        append(&web.join("w.jsonl"), "{}\n");
+        let mut tracker = SessionTracker::scan(root);
+
+        append(&web.join("w.jsonl"), "{}\n");
+        append(&app.join("new.jsonl"), "{}\n");
+        assert_eq!(
+            tracker.update(std::slice::from_ref(&app)),
+            [(app.join("new.jsonl"), SessionChange::Created)]
+        );
+
+        // A new folder is read as a whole; folders already tracked are not
+        let added = root.join("-Users-me-cli");
+        fs::create_dir_all(added.join("sub")).unwrap();
+        append(&added.join("sub").join("c.jsonl"), "{}\n");
+        assert_eq!(
+            tracker.update(&[root.to_path_buf()]),
+            [(added.join("sub").join("c.jsonl"), SessionChange::Created)]
+        );
+
+        assert_eq!(
+            tracker.rescan(root),
+            [(
+                web.join("w.jsonl"),
+                SessionChange::Appended { from: 3, to: 6 }
+            )]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! Glob patterns for hidden projects, auto-tag rules and file filters
+//!
+//! Patterns are translated to a [`regex::Regex`], whose engine runs in time
+//! linear in the input, so no pattern can make matching blow up.
+//!
+//! - `*` matches any run of characters except `/`, `?` any single one
+//! - `**` also matches across `/`; `**/` matches zero or more directories
+//! - `[abc]` and `[a-z]` match one listed character, `[!abc]` or `[^abc]`
+//!   one that isn't listed (never `/`)
+//! - `{a,b}` matches either alternative; alternatives may contain globs
+//! - in a [`GlobRules`] list, a leading `!` excludes what earlier rules matched
+//!
+//! An unclosed `[` or `{` is literal, and so is `\`, so Windows paths can be
+//! written as they are. Use `[*]` to match a literal `*`.
+//!
+//! [`GlobRules`] skips patterns over the length and wildcard limits the
+//! frontend matcher (`src/utils/globUtils.ts`) needs against backtracking, so
+//! both sides hide the same projects.
+
+use regex::{Regex, RegexBuilder};
+
+/// Upper bound on the compiled size of one pattern
+const MAX_COMPILED_SIZE: usize = 1 << 20;
+
+/// Longest rule pattern, in UTF-16 code units like JavaScript's `length`
+const MAX_PATTERN_LENGTH: usize = 256;
+
+/// Most wildcards in a rule pattern; a run of `*` counts once, as does `?`
+const MAX_WILDCARDS: usize = 10;
+
+/// Whether a rule pattern is within the limits shared with the frontend
+pub(crate) fn within_limits(pattern: &str) -> bool {
+    let mut wildcards = 0;
+    let mut previous = None;
+    for c in pattern.chars() {
+        if c == '?' || (c == '*' && previous != Some('*')) {
+            wildcards += 1;
+        }
+        previous = Some(c);
+    }
+    pattern.encode_utf16().count() <= MAX_PATTERN_LENGTH && wildcards <= MAX_WILDCARDS
+}
+
+/// A compiled glob pattern
+#[derive(Debug, Clone)]
+pub struct GlobPattern {
+    regex: Regex,
+    /// The pattern has no `/`, so paths are matched by their last component
+    match_name: bool,
+}
+
+impl GlobPattern {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let regex = RegexBuilder::new(&format!("^(?:{})$", translate(&chars)))
+            .size_limit(MAX_COMPILED_SIZE)
+            .build()
+            .map_err(|e| format!("Invalid glob pattern '{pattern}': {e}"))?;
+        Ok(Self {
+            regex,
+            match_name: !pattern.contains('/'),
+        })
+    }
+
+    /// Whether the whole text matches
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Match a path; patterns without `/` match its last component, like
+    /// `.gitignore` entries, so `*.rs` matches `src/lib.rs`
+    pub fn matches_path(&self, path: &str) -> bool {
+        if self.match_name {
+            let name = path
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or(path);
+            self.regex.is_match(name)
+        } else {
+            self.regex.is_match(path)
+        }
+    }
+}
+
+/// Ordered include/exclude patterns; the last rule that matches decides
+#[derive(Debug, Clone, Default)]
+pub struct GlobRules {
+    /// (excludes, pattern)
+    rules: Vec<(bool, GlobPattern)>,
+}
+
+impl GlobRules {
+    /// Compile a rule list, skipping patterns that don't compile or are over
+    /// the shared limits
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let rules = patterns
+            .iter()
+            .filter_map(|pattern| {
+                let pattern = pattern.as_ref();
+                let (excludes, glob) = match pattern.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, pattern),
+                };
+                if !within_limits(glob) {
+                    return None;
+                }
+                GlobPattern::new(glob).ok().map(|glob| (excludes, glob))
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn matches_path(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(_, glob)| glob.matches_path(path))
+            .is_some_and(|(excludes, _)| !excludes)
+    }
+}
+
+/// Translate glob syntax into an unanchored regex
+fn translate(chars: &[char]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => {
+                let run_end = chars[i..]
+                    .iter()
+                    .position(|&c| c != '*')
+                    .map_or(chars.len(), |n| i + n);
+                if run_end - i == 1 {
+                    out.push_str("[^/]*");
+                    i = run_end;
+                } else if chars.get(run_end) == Some(&'/') {
+                    out.push_str("(?:.*/)?");
+                    i = run_end + 1;
+                } else {
+                    out.push_str(".*");
+                    i = run_end;
+                }
+                continue;
+            }
+            '?' => out.push_str("[^/]"),
+            '[' => {
+                if let Some((class, end)) = translate_class(chars, i) {
+                    out.push_str(&class);
+                    i = end + 1;
+                    continue;
+                }
+                out.push_str(r"\[");
+            }
+            '{' => {
+                if let Some((alternatives, end)) = split_braces(chars, i) {
+                    out.push_str("(?:");
+                    for (n, alternative) in alternatives.iter().enumerate() {
+                        if n > 0 {
+                            out.push('|');
+                        }
+                        out.push_str(&translate(alternative));
+                    }
+                    out.push(')');
+                    i = end + 1;
+                    continue;
+                }
+                out.push_str(r"\{");
+            }
+            c => out.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Regex for the `[...]` class starting at `start`, with the index of its `]`
+fn translate_class(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut first = start + 1;
+    let negated = matches!(chars.get(first), Some('!' | '^'));
+    if negated {
+        first += 1;
+    }
+    // A `]` right after the opening bracket is a member, not the end
+    let end = first + 1 + chars.get(first + 1..)?.iter().position(|&c| c == ']')?;
+
+    let members = &chars[first..end];
+    let mut class = String::from(if negated { "[^/" } else { "[" });
+    for (n, &c) in members.iter().enumerate() {
+        let is_range = c == '-' && n > 0 && n + 1 < members.len();
+        if !is_range && matches!(c, '\\' | '[' | ']' | '^' | '-' | '&' | '~') {
+            class.push('\\');
+        }
+        class.push(c);
+    }
+    class.push(']');
+    Some((class, end))
+}
+
+/// Top-level alternatives of the `{...}` group starting at `start`, with the
+/// index of its `}`
+fn split_braces(chars: &[char], start: usize) -> Option<(Vec<&[char]>, usize)> {
+    let mut depth = 0;
+    let mut alternatives = Vec::new();
+    let mut alternative_start = start + 1;
+    for (i, &c) in chars.iter().enumerate().skip(start + 1) {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            '}' => {
+                alternatives.push(&chars[alternative_start..i]);
+                return Some((alternatives, i));
+            }
+            ',' if depth == 0 => {
+                alternatives.push(&chars[alternative_start..i]);
+                alternative_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(text: &str, pattern: &str) -> bool {
+        GlobPattern::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches("abc", "abc"));
+        assert!(matches("abc", "a*"));
+        assert!(matches("abc", "*c"));
+        assert!(matches("abc", "a?c"));
+        assert!(matches("abc", "*"));
+        assert!(!matches("abc", "ab"));
+        assert!(!matches("abc", "a?"));
+        assert!(matches("", ""));
+        assert!(!matches("a/b", "a*"));
+        assert!(!matches("a/b", "a?b"));
+        assert!(matches("a/b", "a**"));
+        assert!(matches("a.b+c(d)", "a.b+c(d)"));
+        assert!(!matches("axb", "a.b"));
+        assert!(matches(r"C:\Users\me", r"C:\Users\*"));
+    }
+
+    #[test]
+    fn test_double_star_spans_directories() {
+        assert!(matches("migrations/001.sql", "migrations/**"));
+        assert!(matches("migrations/2024/001.sql", "migrations/**"));
+        assert!(!matches("db/migrations/001.sql", "migrations/**"));
+        assert!(matches("db/migrations/001.sql", "**/migrations/**"));
+        assert!(matches("migrations/001.sql", "**/migrations/**"));
+        assert!(matches("src/lib.rs", "src/**/*.rs"));
+        assert!(matches("src/a/b/lib.rs", "src/**/*.rs"));
+        assert!(!matches("src/a/blib.rs", "src/**/b*/*.rs"));
+    }
+
+    #[test]
+    fn test_classes_and_braces() {
+        assert!(matches("v1", "v[0-9]"));
+        assert!(!matches("vx", "v[0-9]"));
+        assert!(matches("vx", "v[!0-9]"));
+        assert!(matches("vx", "v[^0-9]"));
+        assert!(!matches("v/", "v[!0-9]"));
+        assert!(matches("a]", "a[]]"));
+        assert!(matches("a-", "a[x-]"));
+        assert!(matches("*", "[*]"));
+        assert!(!matches("x", "[*]"));
+        assert!(matches("test[1", "test[1"));
+
+        assert!(matches("lib.rs", "*.{rs,toml}"));
+        assert!(matches("Cargo.toml", "*.{rs,toml}"));
+        assert!(!matches("a.ts", "*.{rs,toml}"));
+        assert!(matches("src/a/b.tsx", "{src/**/*.ts{,x},docs}"));
+        assert!(matches("docs", "{src/**/*.ts{,x},docs}"));
+        assert!(matches("a{b", "a{b"));
+    }
+
+    #[test]
+    fn test_matches_path_uses_name_without_slash() {
+        let glob = GlobPattern::new("folders-dg-*").unwrap();
+        assert!(glob.matches_path("folders-dg-abc"));
+        assert!(glob.matches_path("/Users/me/folders-dg-abc"));
+        assert!(!glob.matches_path("/Users/folders-dg-abc/project"));
+
+        let anchored = GlobPattern::new("/Users/*/project").unwrap();
+        assert!(anchored.matches_path("/Users/jack/project"));
+        assert!(!anchored.matches_path("/Users/jack/other"));
+    }
+
+    #[test]
+    fn test_rules_apply_in_order() {
+        let rules = GlobRules::new(&["folders-*", "!folders-keep", "folders-keep-old*"]);
+        assert!(rules.matches_path("/x/folders-dg"));
+        assert!(!rules.matches_path("/x/folders-keep"));
+        assert!(rules.matches_path("/x/folders-keep-old1"));
+        assert!(!rules.matches_path("/x/other"));
+        assert!(!GlobRules::new::<&str>(&[]).matches_path("a"));
+    }
+
+    #[test]
+    fn test_rules_skip_patterns_over_limits() {
+        // Same limits as the frontend: 11 wildcards is one too many
+        assert!(!GlobRules::new(&["*a*b*c*d*e*f*g*h*i*j*k"]).matches_path("XaXbXcXdXeXfXgXhXiXjXk"));
+        assert!(GlobRules::new(&["*a*b*c*d*e*f*g*h*i*"]).matches_path("XaXbXcXdXeXfXgXhXiX"));
+        // A run of `*` is one wildcard
+        assert!(within_limits(&"**/a".repeat(10)));
+        assert!(!within_limits(&"a".repeat(MAX_PATTERN_LENGTH + 1)));
+        assert!(within_limits(&"a".repeat(MAX_PATTERN_LENGTH)));
+    }
+}
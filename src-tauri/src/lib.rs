@@ -1,4 +1,5 @@
 pub mod commands;
+pub mod glob;
 pub mod models;
 pub mod utils;
 
@@ -6,37 +7,56 @@ pub mod utils;
 pub mod test_utils;
 
 use crate::commands::{
+    archive::{
+        archive_sessions, empty_trash, list_session_archives, list_trash,
+        preview_retention_cleanup, restore_from_trash, restore_session_archive, trash_sessions,
+    },
+    bookmarks::{list_bookmarks, remove_message_annotation, set_message_annotation},
     claude_settings::{
         get_all_mcp_servers, get_all_settings, get_claude_json_config, get_mcp_servers,
         get_settings_by_scope, read_text_file, save_mcp_servers, save_settings, write_text_file,
     },
+    collections::{delete_saved_search, evaluate_smart_collection, query_sessions, save_search},
     feedback::{get_system_info, open_github_issues, send_feedback},
+    journal::{get_journal_history, revert_journal_entry},
+    live_tail::{get_active_sessions, start_session_tail, stop_session_tail, SessionTailState},
     mcp_presets::{delete_mcp_preset, get_mcp_preset, load_mcp_presets, save_mcp_preset},
     metadata::{
         get_metadata_folder_path, get_session_display_name, is_project_hidden, load_user_metadata,
         save_user_metadata, update_project_metadata, update_session_metadata, update_user_settings,
         MetadataState,
     },
+    metadata_gc::{clean_up_metadata, find_metadata_orphans},
+    metadata_sync::{export_user_metadata, import_user_metadata},
     project::{get_claude_folder_path, get_git_log, scan_projects, validate_claude_folder},
+    redaction::{preview_session_redaction, redact_session},
+    relocate::relocate_project,
+    secret_scan::scan_secret_exposure,
     session::{
-        get_recent_edits, get_session_message_count, load_project_sessions, load_session_messages,
-        load_session_messages_paginated, rename_session_native, reset_session_native_name,
-        restore_file, search_messages,
+        fork_session, get_file_edit_origins, get_file_history_snapshots, get_recent_edits,
+        get_session_message_count, get_shell_file_operations, load_project_sessions,
+        load_session_messages, load_session_messages_paginated, query_file_activity,
+        read_file_history_backup, rename_session_native, reset_session_native_name, restore_file,
+        search_messages,
     },
     settings::{delete_preset, get_preset, load_presets, save_preset},
     stats::{
         get_global_stats_summary, get_project_stats_summary, get_project_token_stats,
         get_session_comparison, get_session_token_stats,
     },
+    trusted_symlinks::{get_trusted_symlink_targets, set_trusted_symlink_targets},
     unified_presets::{
         delete_unified_preset, get_unified_preset, load_unified_presets, save_unified_preset,
     },
-    watcher::{start_file_watcher, stop_file_watcher},
+    watcher::{
+        get_file_watcher_health, start_config_watcher, start_file_watcher, stop_config_watcher,
+        stop_file_watcher, ConfigWatcherState, WatcherManager,
+    },
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    use std::sync::{Arc, Mutex};
+    use tauri::Manager;
 
     #[allow(unused_mut)]
     let mut builder = tauri::Builder::default()
@@ -49,12 +69,14 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_os::init());
 
+    // Every path check uses the targets as resolved here
+    crate::commands::trusted_symlinks::load_trusted_targets();
+
     builder
         .manage(MetadataState::default())
-        .manage(Arc::new(Mutex::new(None))
-            as Arc<
-                Mutex<Option<notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>>>,
-            >)
+        .manage(SessionTailState::default())
+        .manage(ConfigWatcherState::default())
+        .manage(WatcherManager::default())
         .invoke_handler(tauri::generate_handler![
             get_claude_folder_path,
             validate_claude_folder,
@@ -67,6 +89,11 @@ pub fn run() {
             search_messages,
             get_recent_edits,
             restore_file,
+            get_file_history_snapshots,
+            read_file_history_backup,
+            get_file_edit_origins,
+            get_shell_file_operations,
+            query_file_activity,
             get_session_token_stats,
             get_project_token_stats,
             get_project_stats_summary,
@@ -84,6 +111,12 @@ pub fn run() {
             update_user_settings,
             is_project_hidden,
             get_session_display_name,
+            // Metadata cleanup commands
+            find_metadata_orphans,
+            clean_up_metadata,
+            // Metadata export/import between machines
+            export_user_metadata,
+            import_user_metadata,
             // Settings preset commands
             save_preset,
             load_presets,
@@ -113,11 +146,56 @@ pub fn run() {
             // Native session rename commands
             rename_session_native,
             reset_session_native_name,
+            // Session fork command
+            fork_session,
+            // Session file journal commands
+            get_journal_history,
+            revert_journal_entry,
+            // Session redaction commands
+            preview_session_redaction,
+            redact_session,
+            // Secret exposure audit
+            scan_secret_exposure,
+            // Session trash, archive and retention commands
+            trash_sessions,
+            list_trash,
+            restore_from_trash,
+            empty_trash,
+            archive_sessions,
+            list_session_archives,
+            restore_session_archive,
+            preview_retention_cleanup,
+            // Project relocation command
+            relocate_project,
+            // Message bookmark and annotation commands
+            set_message_annotation,
+            remove_message_annotation,
+            list_bookmarks,
+            // Saved search and smart collection commands
+            save_search,
+            delete_saved_search,
+            query_sessions,
+            evaluate_smart_collection,
             // File watcher commands
             start_file_watcher,
-            stop_file_watcher
+            stop_file_watcher,
+            get_file_watcher_health,
+            // Config file watcher commands
+            start_config_watcher,
+            stop_config_watcher,
+            // Live session tail commands
+            start_session_tail,
+            stop_session_tail,
+            get_active_sessions,
+            // Trusted symlink commands
+            get_trusted_symlink_targets,
+            set_trusted_symlink_targets
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
-        .run(|_, _| {});
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                app_handle.state::<WatcherManager>().stop_all();
+            }
+        });
 }
@@ -2,9 +2,13 @@
 //!
 //! This module contains all the data structures used throughout the application.
 
+mod archive;
 mod edit;
+mod file_activity;
+mod journal;
 mod message;
 mod metadata;
+mod redaction;
 mod session;
 mod stats;
 
@@ -12,8 +16,12 @@ mod stats;
 mod snapshot_tests;
 
 // Re-export all types for backward compatibility
+pub use archive::*;
 pub use edit::*;
+pub use file_activity::*;
+pub use journal::*;
 pub use message::*;
 pub use metadata::*;
+pub use redaction::*;
 pub use session::*;
 pub use stats::*;
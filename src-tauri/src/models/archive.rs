@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use super::ClaudeSession;
+
+/// A session moved to the app-managed trash
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrashEntry {
+    pub id: String,
+    pub session_id: String,
+    /// Folder name under `projects/`
+    pub project_name: String,
+    pub original_path: String,
+    pub trashed_at: String,
+    /// Modification time of the session file when it was trashed
+    pub last_modified: String,
+    pub size_bytes: u64,
+    /// Names moved out of the project folder: the `.jsonl` file and, if any,
+    /// the session's subagent folder
+    pub files: Vec<String>,
+}
+
+/// A session stored in a compressed archive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedSession {
+    pub session_id: String,
+    pub project_name: String,
+    pub original_path: String,
+    pub last_modified: String,
+    pub size_bytes: u64,
+}
+
+/// A `.tar.gz` bundle of sessions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionArchive {
+    pub id: String,
+    pub archive_path: String,
+    pub created_at: String,
+    /// Uncompressed size of the archived files
+    pub original_size: u64,
+    pub compressed_size: u64,
+    /// Whether sessions were removed from `projects/` after archiving (all of
+    /// them, unless the archive command reported a failed removal)
+    pub originals_removed: bool,
+    pub sessions: Vec<ArchivedSession>,
+}
+
+/// Files written back to `~/.claude` from an archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveRestoreResult {
+    pub restored_files: Vec<String>,
+    /// Files left alone because they already exist
+    pub skipped_files: Vec<String>,
+}
+
+/// A session Claude Code's cleanup will delete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionCandidate {
+    pub session: ClaudeSession,
+    /// Claude session storage path of the project
+    pub project_path: String,
+    /// When the file becomes older than `cleanupPeriodDays`
+    pub expires_at: String,
+    /// Already past the cutoff: deleted the next time Claude Code starts
+    pub expired: bool,
+    pub size_bytes: u64,
+}
+
+/// What Claude Code's `cleanupPeriodDays` setting would delete next
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPreview {
+    pub cleanup_period_days: u64,
+    /// Scope the setting came from: "managed", "local", "project", "user" or "default"
+    pub setting_scope: String,
+    /// Sessions last modified before this are deleted
+    pub cutoff: String,
+    /// Candidates expiring within this many days are included
+    pub within_days: u64,
+    /// Soonest to be deleted first
+    pub candidates: Vec<RetentionCandidate>,
+}
@@ -12,6 +12,105 @@ pub struct RecentFileEdit {
     pub lines_added: usize,
     pub lines_removed: usize,
     pub cwd: Option<String>, // Working directory when edit was made
+    /// Claude Code file-history backup taken before the turn that made this edit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_backup: Option<FileHistoryBackup>,
+    /// UUID of the assistant message whose `tool_use` made this edit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_uuid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_use_id: Option<String>,
+    /// UUID of the user prompt that started the turn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_uuid: Option<String>,
+}
+
+/// Backup of a file recorded by a Claude Code `file-history-snapshot` entry
+///
+/// Backups are stored under `~/.claude/file-history/{session_id}/{backup_file_name}`
+/// and hold the file content before the turn of `message_id` modified it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileHistoryBackup {
+    pub file_path: String,
+    pub session_id: String,
+    /// UUID of the user message whose snapshot contains this backup
+    pub message_id: String,
+    pub version: u32,
+    /// None when the file did not exist at snapshot time
+    pub backup_file_name: Option<String>,
+    pub backup_time: Option<String>,
+    /// Resolved absolute path of the backup file
+    pub backup_path: Option<String>,
+}
+
+/// A message that edited a file, for tracing a change back to its reasoning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEditOrigin {
+    pub file_path: String,
+    pub session_id: String,
+    pub timestamp: String,
+    pub operation_type: String, // "edit", "write", or a shell operation
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub message_uuid: Option<String>,
+    pub tool_use_id: Option<String>,
+    pub prompt_uuid: Option<String>,
+    /// Exact command line for operations detected in a Bash tool call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl From<RecentFileEdit> for FileEditOrigin {
+    fn from(edit: RecentFileEdit) -> Self {
+        Self {
+            file_path: edit.file_path,
+            session_id: edit.session_id,
+            timestamp: edit.timestamp,
+            operation_type: edit.operation_type,
+            lines_added: edit.lines_added,
+            lines_removed: edit.lines_removed,
+            message_uuid: edit.message_uuid,
+            tool_use_id: edit.tool_use_id,
+            prompt_uuid: edit.prompt_uuid,
+            command: None,
+        }
+    }
+}
+
+/// File deleted, moved or rewritten by a Bash tool call
+///
+/// Detected by analyzing the command line, so it is best-effort: the content
+/// after the change is unknown and the command may have failed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellFileOperation {
+    pub file_path: String,
+    pub operation: String, // "delete", "move" or "rewrite"
+    /// New location for moves
+    pub destination: Option<String>,
+    pub command: String,
+    pub timestamp: String,
+    pub session_id: String,
+    pub cwd: Option<String>,
+    pub message_uuid: Option<String>,
+    pub tool_use_id: Option<String>,
+    pub prompt_uuid: Option<String>,
+}
+
+impl From<ShellFileOperation> for FileEditOrigin {
+    fn from(op: ShellFileOperation) -> Self {
+        Self {
+            file_path: op.file_path,
+            session_id: op.session_id,
+            timestamp: op.timestamp,
+            operation_type: op.operation,
+            lines_added: 0,
+            lines_removed: 0,
+            message_uuid: op.message_uuid,
+            tool_use_id: op.tool_use_id,
+            prompt_uuid: op.prompt_uuid,
+            command: Some(op.command),
+        }
+    }
 }
 
 /// Result container for recent edits query
@@ -39,6 +138,10 @@ mod tests {
             lines_added: 5,
             lines_removed: 3,
             cwd: Some("/path/to".to_string()),
+            snapshot_backup: None,
+            message_uuid: None,
+            tool_use_id: None,
+            prompt_uuid: None,
         };
 
         let serialized = serde_json::to_string(&edit).unwrap();
@@ -66,6 +169,10 @@ mod tests {
             lines_added: 1,
             lines_removed: 0,
             cwd: None,
+            snapshot_backup: None,
+            message_uuid: None,
+            tool_use_id: None,
+            prompt_uuid: None,
         };
 
         let serialized = serde_json::to_string(&edit).unwrap();
@@ -90,6 +197,10 @@ mod tests {
                     lines_added: 1,
                     lines_removed: 0,
                     cwd: Some("/project".to_string()),
+                    snapshot_backup: None,
+                    message_uuid: None,
+                    tool_use_id: None,
+                    prompt_uuid: None,
                 },
                 RecentFileEdit {
                     file_path: "/file2.rs".to_string(),
@@ -101,6 +212,10 @@ mod tests {
                     lines_added: 2,
                     lines_removed: 0,
                     cwd: Some("/project".to_string()),
+                    snapshot_backup: None,
+                    message_uuid: None,
+                    tool_use_id: None,
+                    prompt_uuid: None,
                 },
             ],
             total_edits_count: 5,
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Sessions and messages that touched a file (or files matching a glob)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileActivityResult {
+    pub pattern: String,
+    pub total_matches: usize,
+    /// Match counts keyed by tool name ("mention" for references in message text)
+    pub tool_counts: HashMap<String, usize>,
+    /// Sessions sorted by most recent activity first
+    pub sessions: Vec<FileSessionActivity>,
+}
+
+/// File activity within a single session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSessionActivity {
+    pub session_id: String,
+    pub session_path: String,
+    pub tool_counts: HashMap<String, usize>,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    pub messages: Vec<FileActivityMessage>,
+}
+
+/// A single message that accessed or referenced a matching file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileActivityMessage {
+    pub uuid: Option<String>,
+    pub timestamp: Option<String>,
+    /// Tool name, or "mention" for references in message text
+    pub tool_name: String,
+    pub access: String, // "read", "edit", "write", "grep", "shell" or "mention"
+    pub file_path: String,
+    pub tool_use_id: Option<String>,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// A journaled modification of a Claude Code session file
+///
+/// The file content before the write is kept at `snapshot_path` so the
+/// modification can be reverted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JournalEntry {
+    pub id: String,
+    pub file_path: String,
+    pub operation: String, // "rename", "reset_name", "revert", "redact", "relocate"
+    pub description: String,
+    pub timestamp: String,
+    pub snapshot_path: String,
+    pub original_size: u64,
+    pub new_size: u64,
+    /// CRC32 of the written content, checked before reverting; missing in
+    /// entries written before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_checksum: Option<u32>,
+    /// Id of the entry that reverted this modification
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverted_by: Option<String>,
+    /// Id of the entry this modification reverts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverts: Option<String>,
+}
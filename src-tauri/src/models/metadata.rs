@@ -4,11 +4,20 @@
 //! that is stored separately from Claude Code's original data.
 //! Location: ~/.claude-history-viewer/user-data.json
 
+use crate::glob::GlobRules;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Current schema version for migration support
-pub const METADATA_SCHEMA_VERSION: u32 = 1;
+pub const METADATA_SCHEMA_VERSION: u32 = 3;
+
+/// Days an entry cleared of all values keeps its times before it is dropped
+///
+/// Until then an import of an older copy can't bring the cleared values back.
+pub const CLEARED_ENTRY_RETENTION_DAYS: i64 = 180;
+
+/// When each field was last changed: field name (as serialized) to RFC 3339
+pub type FieldTimes = BTreeMap<String, String>;
 
 /// Root structure for all user metadata
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -18,6 +27,10 @@ pub struct UserMetadata {
     #[serde(default = "default_version")]
     pub version: u32,
 
+    /// Incremented on every save, so writers can tell the file changed under them
+    #[serde(default)]
+    pub revision: u64,
+
     /// Session-specific metadata, keyed by session ID
     #[serde(default)]
     pub sessions: HashMap<String, SessionMetadata>,
@@ -29,6 +42,10 @@ pub struct UserMetadata {
     /// Global user settings
     #[serde(default)]
     pub settings: UserSettings,
+
+    /// Saved searches, shown as smart collections
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub saved_searches: Vec<SavedSearch>,
 }
 
 fn default_version() -> u32 {
@@ -40,9 +57,11 @@ impl UserMetadata {
     pub fn new() -> Self {
         Self {
             version: METADATA_SCHEMA_VERSION,
+            revision: 0,
             sessions: HashMap::new(),
             projects: HashMap::new(),
             settings: UserSettings::default(),
+            saved_searches: Vec::new(),
         }
     }
 
@@ -66,7 +85,341 @@ impl UserMetadata {
         self.projects.entry(project_path.to_string()).or_default()
     }
 
+    /// Move session metadata to new keys after session files were moved
+    ///
+    /// Metadata already stored under a new key wins field by field; tags are
+    /// merged. Returns the number of sessions re-keyed.
+    pub fn rename_session_keys(&mut self, renames: &HashMap<String, String>) -> usize {
+        let mut renamed = 0;
+        for (from, to) in renames {
+            let Some(source) = self.sessions.remove(from) else {
+                continue;
+            };
+            let target = self.sessions.entry(to.clone()).or_default();
+            target.merge_missing(source);
+            renamed += 1;
+        }
+        renamed
+    }
+
+    /// Move project metadata from one project path to another
+    ///
+    /// Fields already set on the target win. Worktrees grouped under the old
+    /// path are re-pointed at the new one. Returns whether anything changed.
+    pub fn rename_project(&mut self, from: &str, to: &str) -> bool {
+        if from == to {
+            return false;
+        }
+        let mut changed = false;
+        if let Some(source) = self.projects.remove(from) {
+            let target = self.projects.entry(to.to_string()).or_default();
+            target.hidden = target.hidden.or(source.hidden);
+            target.alias = target.alias.take().or(source.alias);
+            target.parent_project = target.parent_project.take().or(source.parent_project);
+            merge_times(&mut target.updated, &source.updated);
+            changed = true;
+        }
+        for project in self.projects.values_mut() {
+            if project.parent_project.as_deref() == Some(from) {
+                project.parent_project = Some(to.to_string());
+                changed = true;
+            }
+        }
+        // A project can't be grouped under itself
+        if let Some(project) = self.projects.get_mut(to) {
+            if project.parent_project.as_deref() == Some(to) {
+                project.parent_project = None;
+            }
+            if project.is_empty() {
+                self.projects.remove(to);
+            }
+        }
+        changed
+    }
+
+    /// Three-way merge of two copies edited from `base`
+    ///
+    /// Fields changed in `ours` win; everything else comes from `theirs`, so
+    /// concurrent edits to different sessions, projects or fields are all kept.
+    /// Tags merge as sets and hidden patterns as ordered rules; annotations and
+    /// saved searches merge by id. The revision is taken from `theirs`.
+    pub fn merge_concurrent(base: &Self, ours: &Self, theirs: &Self) -> Self {
+        let sessions = merge_maps(
+            &base.sessions,
+            &ours.sessions,
+            &theirs.sessions,
+            |b, o, t| SessionMetadata {
+                custom_name: pick(&b.custom_name, &o.custom_name, &t.custom_name),
+                starred: pick(&b.starred, &o.starred, &t.starred),
+                tags: merge_sets(&b.tags, &o.tags, &t.tags),
+                notes: pick(&b.notes, &o.notes, &t.notes),
+                annotations: merge_annotations(&b.annotations, &o.annotations, &t.annotations),
+                updated: latest_times(&o.updated, &t.updated),
+            },
+            SessionMetadata::is_empty,
+        );
+        let projects = merge_maps(
+            &base.projects,
+            &ours.projects,
+            &theirs.projects,
+            |b, o, t| ProjectMetadata {
+                hidden: pick(&b.hidden, &o.hidden, &t.hidden),
+                alias: pick(&b.alias, &o.alias, &t.alias),
+                parent_project: pick(&b.parent_project, &o.parent_project, &t.parent_project),
+                updated: latest_times(&o.updated, &t.updated),
+            },
+            ProjectMetadata::is_empty,
+        );
+        let (b, o, t) = (&base.settings, &ours.settings, &theirs.settings);
+        let settings = UserSettings {
+            hidden_patterns: merge_rules(
+                &b.hidden_patterns,
+                &o.hidden_patterns,
+                &t.hidden_patterns,
+            ),
+            worktree_grouping: pick(
+                &b.worktree_grouping,
+                &o.worktree_grouping,
+                &t.worktree_grouping,
+            ),
+            worktree_grouping_user_set: pick(
+                &b.worktree_grouping_user_set,
+                &o.worktree_grouping_user_set,
+                &t.worktree_grouping_user_set,
+            ),
+            grouping_mode: pick(&b.grouping_mode, &o.grouping_mode, &t.grouping_mode),
+            auto_tag_rules: pick(&b.auto_tag_rules, &o.auto_tag_rules, &t.auto_tag_rules),
+            updated: latest_times(&o.updated, &t.updated),
+        };
+
+        Self {
+            version: ours.version.max(theirs.version),
+            revision: theirs.revision,
+            sessions,
+            projects,
+            settings,
+            saved_searches: merge_by_id(
+                &base.saved_searches,
+                &ours.saved_searches,
+                &theirs.saved_searches,
+                |search| &search.id,
+            ),
+        }
+    }
+
+    /// Record `now` as the change time of every field edited since `base`
+    ///
+    /// Entries removed since `base` come back empty if they had a timed field
+    /// set, so the time it was cleared is kept. Empty entries whose times are
+    /// all older than [`CLEARED_ENTRY_RETENTION_DAYS`] are dropped.
+    pub fn stamp_changes(&mut self, base: &Self, now: &str) {
+        for (key, b) in &base.sessions {
+            if b.custom_name.is_some() || b.starred.is_some() || b.notes.is_some() {
+                self.sessions
+                    .entry(key.clone())
+                    .or_insert_with(|| SessionMetadata {
+                        updated: b.updated.clone(),
+                        ..Default::default()
+                    });
+            }
+        }
+        for (key, b) in &base.projects {
+            if b.has_values() {
+                self.projects
+                    .entry(key.clone())
+                    .or_insert_with(|| ProjectMetadata {
+                        updated: b.updated.clone(),
+                        ..Default::default()
+                    });
+            }
+        }
+        let empty_session = SessionMetadata::default();
+        for (key, session) in &mut self.sessions {
+            let b = base.sessions.get(key).unwrap_or(&empty_session);
+            let changes = [
+                ("customName", session.custom_name != b.custom_name),
+                ("starred", session.starred != b.starred),
+                ("notes", session.notes != b.notes),
+            ];
+            stamp_fields(&mut session.updated, &b.updated, &changes, now);
+        }
+        let empty_project = ProjectMetadata::default();
+        for (key, project) in &mut self.projects {
+            let b = base.projects.get(key).unwrap_or(&empty_project);
+            let changes = [
+                ("hidden", project.hidden != b.hidden),
+                ("alias", project.alias != b.alias),
+                ("parentProject", project.parent_project != b.parent_project),
+            ];
+            stamp_fields(&mut project.updated, &b.updated, &changes, now);
+        }
+        let (settings, b) = (&mut self.settings, &base.settings);
+        let changes = [
+            (
+                "worktreeGrouping",
+                settings.worktree_grouping != b.worktree_grouping,
+            ),
+            (
+                "worktreeGroupingUserSet",
+                settings.worktree_grouping_user_set != b.worktree_grouping_user_set,
+            ),
+            ("groupingMode", settings.grouping_mode != b.grouping_mode),
+            ("autoTagRules", settings.auto_tag_rules != b.auto_tag_rules),
+        ];
+        stamp_fields(&mut settings.updated, &b.updated, &changes, now);
+
+        if let Ok(now) = chrono::DateTime::parse_from_rfc3339(now) {
+            let cutoff = (now - chrono::Duration::days(CLEARED_ENTRY_RETENTION_DAYS)).to_rfc3339();
+            let recent =
+                |times: &FieldTimes| times.values().any(|t| is_later(Some(t), Some(&cutoff)));
+            self.sessions
+                .retain(|_, session| session.has_values() || recent(&session.updated));
+            self.projects
+                .retain(|_, project| project.has_values() || recent(&project.updated));
+        }
+    }
+
+    /// Merge a copy exported from another machine into this one
+    ///
+    /// Tags are unioned; hidden patterns missing here are inserted after the
+    /// rule they follow in the incoming copy, and a different order of shared
+    /// rules is reported as a conflict (keeping ours). Annotations and saved
+    /// searches merge by id, the most recently updated copy winning. Every
+    /// other field goes to whichever side changed it last (see
+    /// [`FieldTimes`]); fields both sides set differently are reported as
+    /// conflicts. Nothing is deleted.
+    pub fn merge_import(&mut self, incoming: &Self) -> MetadataImportReport {
+        let mut report = MetadataImportReport::default();
+
+        let mut session_keys: Vec<&String> = incoming.sessions.keys().collect();
+        session_keys.sort();
+        for key in session_keys {
+            let theirs = &incoming.sessions[key];
+            let Some(ours) = self.sessions.get_mut(key) else {
+                if !theirs.is_empty() {
+                    self.sessions.insert(key.clone(), theirs.clone());
+                    report.sessions_added += usize::from(theirs.has_values());
+                }
+                continue;
+            };
+            let mut merge = FieldMerge::new("session", key, &mut report.conflicts);
+            let (times, their_times) = (&mut ours.updated, &theirs.updated);
+            merge.field(
+                "customName",
+                &mut ours.custom_name,
+                &theirs.custom_name,
+                times,
+                their_times,
+            );
+            merge.field(
+                "starred",
+                &mut ours.starred,
+                &theirs.starred,
+                times,
+                their_times,
+            );
+            merge.field("notes", &mut ours.notes, &theirs.notes, times, their_times);
+            merge.union(&mut ours.tags, &theirs.tags);
+            merge.by_id(
+                "annotations",
+                &mut ours.annotations,
+                &theirs.annotations,
+                |a| &a.id,
+                |a| &a.updated_at,
+            );
+            if merge.changed {
+                report.sessions_updated += 1;
+            }
+        }
+
+        let mut project_keys: Vec<&String> = incoming.projects.keys().collect();
+        project_keys.sort();
+        for key in project_keys {
+            let theirs = &incoming.projects[key];
+            let Some(ours) = self.projects.get_mut(key) else {
+                if !theirs.is_empty() {
+                    self.projects.insert(key.clone(), theirs.clone());
+                    report.projects_added += usize::from(theirs.has_values());
+                }
+                continue;
+            };
+            let mut merge = FieldMerge::new("project", key, &mut report.conflicts);
+            let (times, their_times) = (&mut ours.updated, &theirs.updated);
+            merge.field(
+                "hidden",
+                &mut ours.hidden,
+                &theirs.hidden,
+                times,
+                their_times,
+            );
+            merge.field("alias", &mut ours.alias, &theirs.alias, times, their_times);
+            merge.field(
+                "parentProject",
+                &mut ours.parent_project,
+                &theirs.parent_project,
+                times,
+                their_times,
+            );
+            if merge.changed {
+                report.projects_updated += 1;
+            }
+        }
+
+        let (ours, theirs) = (&mut self.settings, &incoming.settings);
+        let mut merge = FieldMerge::new("settings", "", &mut report.conflicts);
+        let (times, their_times) = (&mut ours.updated, &theirs.updated);
+        merge.rules(
+            "hiddenPatterns",
+            &mut ours.hidden_patterns,
+            &theirs.hidden_patterns,
+        );
+        merge.field(
+            "worktreeGrouping",
+            &mut ours.worktree_grouping,
+            &theirs.worktree_grouping,
+            times,
+            their_times,
+        );
+        merge.field(
+            "worktreeGroupingUserSet",
+            &mut ours.worktree_grouping_user_set,
+            &theirs.worktree_grouping_user_set,
+            times,
+            their_times,
+        );
+        merge.field(
+            "groupingMode",
+            &mut ours.grouping_mode,
+            &theirs.grouping_mode,
+            times,
+            their_times,
+        );
+        merge.field(
+            "autoTagRules",
+            &mut ours.auto_tag_rules,
+            &theirs.auto_tag_rules,
+            times,
+            their_times,
+        );
+        report.settings_updated = merge.changed;
+
+        let mut merge = FieldMerge::new("savedSearches", "", &mut report.conflicts);
+        merge.by_id(
+            "savedSearches",
+            &mut self.saved_searches,
+            &incoming.saved_searches,
+            |s| &s.id,
+            |s| &s.updated_at,
+        );
+        report.saved_searches_updated = merge.changed;
+
+        report
+    }
+
     /// Check if a project should be hidden based on settings
+    ///
+    /// Hidden patterns are glob rules applied in order; a `!pattern` shows
+    /// projects that earlier patterns hid.
     pub fn is_project_hidden(&self, project_path: &str) -> bool {
         // Check explicit hidden flag
         if let Some(project) = self.projects.get(project_path) {
@@ -76,69 +429,315 @@ impl UserMetadata {
         }
 
         // Check hidden patterns
-        for pattern in &self.settings.hidden_patterns {
-            if Self::matches_glob_pattern(project_path, pattern) {
-                return true;
-            }
-        }
+        GlobRules::new(&self.settings.hidden_patterns).matches_path(project_path)
+    }
+}
 
-        false
+/// Our value if we changed it, otherwise theirs
+fn pick<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> T {
+    if ours == base {
+        theirs.clone()
+    } else {
+        ours.clone()
     }
+}
 
-    /// Maximum pattern length to prevent `ReDoS` attacks
-    const MAX_PATTERN_LENGTH: usize = 256;
-    /// Maximum number of wildcards to prevent catastrophic backtracking
-    const MAX_WILDCARDS: usize = 10;
+/// Field-by-field import of one entry, collecting conflicts
+struct FieldMerge<'a> {
+    scope: &'static str,
+    key: &'a str,
+    conflicts: &'a mut Vec<MetadataConflict>,
+    changed: bool,
+}
 
-    /// Simple glob pattern matching (supports * and ?)
-    /// Returns false for patterns that exceed safety limits
-    fn matches_glob_pattern(text: &str, pattern: &str) -> bool {
-        // ReDoS protection: reject overly long patterns
-        if pattern.len() > Self::MAX_PATTERN_LENGTH {
-            return false;
+impl<'a> FieldMerge<'a> {
+    fn new(scope: &'static str, key: &'a str, conflicts: &'a mut Vec<MetadataConflict>) -> Self {
+        Self {
+            scope,
+            key,
+            conflicts,
+            changed: false,
         }
+    }
 
-        // ReDoS protection: reject patterns with too many wildcards
-        let wildcard_count = pattern.chars().filter(|&c| c == '*' || c == '?').count();
-        if wildcard_count > Self::MAX_WILDCARDS {
-            return false;
-        }
+    fn conflict<T: Serialize>(&mut self, field: &str, local: &T, incoming: &T, incoming_won: bool) {
+        self.conflicts.push(MetadataConflict {
+            scope: self.scope.to_string(),
+            key: self.key.to_string(),
+            field: field.to_string(),
+            local: serde_json::to_value(local).unwrap_or_default(),
+            incoming: serde_json::to_value(incoming).unwrap_or_default(),
+            incoming_won,
+        });
+    }
 
-        let pattern_chars: Vec<char> = pattern.chars().collect();
-        let text_chars: Vec<char> = text.chars().collect();
+    /// Last writer wins; a side that never set the field (no value, no time) defers
+    fn field<T: Clone + PartialEq + Default + Serialize>(
+        &mut self,
+        name: &str,
+        ours: &mut T,
+        theirs: &T,
+        times: &mut FieldTimes,
+        their_times: &FieldTimes,
+    ) {
+        let (our_time, their_time) = (times.get(name), their_times.get(name));
+        if *ours == *theirs {
+            if is_later(their_time, our_time) {
+                times.insert(name.to_string(), their_time.cloned().unwrap_or_default());
+            }
+            return;
+        }
+        let unset = T::default();
+        if *theirs == unset && their_time.is_none() {
+            return;
+        }
+        let take_theirs = if *ours == unset && our_time.is_none() {
+            true
+        } else {
+            let newer = is_later(their_time, our_time);
+            self.conflict(name, ours, theirs, newer);
+            newer
+        };
+        if take_theirs {
+            ours.clone_from(theirs);
+            match their_time {
+                Some(time) => times.insert(name.to_string(), time.clone()),
+                None => times.remove(name),
+            };
+            self.changed = true;
+        }
+    }
 
-        Self::glob_match_helper(&pattern_chars, &text_chars)
+    fn union(&mut self, ours: &mut Vec<String>, theirs: &[String]) {
+        for item in theirs {
+            if !ours.contains(item) {
+                ours.push(item.clone());
+                self.changed = true;
+            }
+        }
     }
 
-    fn glob_match_helper(pattern: &[char], text: &[char]) -> bool {
-        if pattern.is_empty() {
-            return text.is_empty();
+    /// Ordered union; shared rules in a different order are a conflict
+    fn rules(&mut self, name: &str, ours: &mut Vec<String>, theirs: &[String]) {
+        if !same_order(ours, theirs) {
+            self.conflict(name, &*ours, &theirs.to_vec(), false);
         }
+        let before = ours.len();
+        insert_added(ours, theirs, |_| false);
+        self.changed |= ours.len() != before;
+    }
 
-        match pattern[0] {
-            '*' => {
-                // Try matching zero or more characters
-                for i in 0..=text.len() {
-                    if Self::glob_match_helper(&pattern[1..], &text[i..]) {
-                        return true;
+    /// Merge items by id, the one with the later `updated` time winning
+    fn by_id<T: Clone + PartialEq + Serialize>(
+        &mut self,
+        name: &str,
+        ours: &mut Vec<T>,
+        theirs: &[T],
+        id: impl Fn(&T) -> &String,
+        updated: impl Fn(&T) -> &String,
+    ) {
+        for item in theirs {
+            match ours.iter_mut().find(|o| id(o) == id(item)) {
+                None => {
+                    ours.push(item.clone());
+                    self.changed = true;
+                }
+                Some(existing) if existing != item => {
+                    let newer = is_later(Some(updated(item)), Some(updated(existing)));
+                    let label = format!("{name}.{}", id(item));
+                    let local = existing.clone();
+                    if newer {
+                        *existing = item.clone();
+                        self.changed = true;
                     }
+                    self.conflict(&label, &local, item, newer);
                 }
-                false
-            }
-            '?' => {
-                // Match exactly one character
-                !text.is_empty() && Self::glob_match_helper(&pattern[1..], &text[1..])
-            }
-            c => {
-                // Match literal character
-                !text.is_empty()
-                    && text[0] == c
-                    && Self::glob_match_helper(&pattern[1..], &text[1..])
+                Some(_) => {}
             }
         }
     }
 }
 
+/// Whether RFC 3339 time `a` is later than `b`; a missing time is the earliest
+fn is_later(a: Option<&String>, b: Option<&String>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => match (
+            chrono::DateTime::parse_from_rfc3339(a),
+            chrono::DateTime::parse_from_rfc3339(b),
+        ) {
+            (Ok(a), Ok(b)) => a > b,
+            _ => a > b,
+        },
+        (a, b) => a.is_some() && b.is_none(),
+    }
+}
+
+/// Keep the later time of each field
+fn merge_times(times: &mut FieldTimes, other: &FieldTimes) {
+    for (field, time) in other {
+        if is_later(Some(time), times.get(field)) {
+            times.insert(field.clone(), time.clone());
+        }
+    }
+}
+
+fn latest_times(ours: &FieldTimes, theirs: &FieldTimes) -> FieldTimes {
+    let mut times = theirs.clone();
+    merge_times(&mut times, ours);
+    times
+}
+
+/// Record `now` for fields changed since `base` whose time the edit didn't set
+///
+/// `changes` pairs each field name with whether its value changed. Times of
+/// other fields carry over from `base`, so an edit that didn't send them back
+/// doesn't lose them.
+fn stamp_fields(times: &mut FieldTimes, base: &FieldTimes, changes: &[(&str, bool)], now: &str) {
+    for (field, changed) in changes {
+        let base_time = base.get(*field);
+        if *changed && times.get(*field) == base_time {
+            times.insert((*field).to_string(), now.to_string());
+        } else if let Some(base_time) = base_time.filter(|t| is_later(Some(t), times.get(*field))) {
+            times.insert((*field).to_string(), base_time.clone());
+        }
+    }
+}
+
+/// Their items, minus what we removed, plus what we added
+fn merge_sets(base: &[String], ours: &[String], theirs: &[String]) -> Vec<String> {
+    let mut merged: Vec<String> = theirs
+        .iter()
+        .filter(|item| ours.contains(item) || !base.contains(item))
+        .cloned()
+        .collect();
+    for item in ours {
+        if !base.contains(item) && !merged.contains(item) {
+            merged.push(item.clone());
+        }
+    }
+    merged
+}
+
+/// Three-way merge of ordered rules
+///
+/// Keeps the order of the side that reordered the rules (ours if both did,
+/// otherwise theirs), drops rules the other side removed and inserts the ones
+/// it added after the rule they follow there.
+fn merge_rules(base: &[String], ours: &[String], theirs: &[String]) -> Vec<String> {
+    if ours == base {
+        return theirs.to_vec();
+    }
+    if theirs == base {
+        return ours.to_vec();
+    }
+    let (first, second) = if same_order(base, ours) {
+        (theirs, ours)
+    } else {
+        (ours, theirs)
+    };
+    let mut merged: Vec<String> = first
+        .iter()
+        .filter(|rule| second.contains(rule) || !base.contains(rule))
+        .cloned()
+        .collect();
+    insert_added(&mut merged, second, |rule| base.contains(rule));
+    merged
+}
+
+/// Whether the rules both lists contain appear in the same order
+fn same_order(a: &[String], b: &[String]) -> bool {
+    a.iter()
+        .filter(|rule| b.contains(rule))
+        .eq(b.iter().filter(|rule| a.contains(rule)))
+}
+
+/// Insert the rules of `from` missing in `merged` after the rule they follow
+/// in `from`, or first if none does
+fn insert_added(merged: &mut Vec<String>, from: &[String], skip: impl Fn(&String) -> bool) {
+    for (index, rule) in from.iter().enumerate() {
+        if skip(rule) || merged.contains(rule) {
+            continue;
+        }
+        let at = from[..index]
+            .iter()
+            .rev()
+            .find_map(|previous| merged.iter().position(|m| m == previous))
+            .map_or(0, |position| position + 1);
+        merged.insert(at, rule.clone());
+    }
+}
+
+/// Merge lists of items with ids, each item as a whole
+///
+/// Keeps their order and appends items we added. An item one side deleted
+/// stays deleted unless the other side changed it.
+fn merge_by_id<T: Clone + PartialEq>(
+    base: &[T],
+    ours: &[T],
+    theirs: &[T],
+    id: impl Fn(&T) -> &String,
+) -> Vec<T> {
+    let find = |list: &'_ [T], key: &String| list.iter().find(|item| id(item) == key).cloned();
+    let mut merged = Vec::new();
+    for t in theirs {
+        let key = id(t);
+        match (find(base, key), find(ours, key)) {
+            (Some(b), None) if &b == t => {}
+            (b, Some(o)) if b.as_ref() != Some(&o) => merged.push(o),
+            _ => merged.push(t.clone()),
+        }
+    }
+    for o in ours {
+        let key = id(o);
+        if find(theirs, key).is_none() && find(base, key).as_ref() != Some(o) {
+            merged.push(o.clone());
+        }
+    }
+    merged
+}
+
+/// Merge annotations by id, oldest first
+fn merge_annotations(
+    base: &[MessageAnnotation],
+    ours: &[MessageAnnotation],
+    theirs: &[MessageAnnotation],
+) -> Vec<MessageAnnotation> {
+    let mut annotations = merge_by_id(base, ours, theirs, |a| &a.id);
+    annotations.sort_by(|a, b| {
+        a.created_at
+            .cmp(&b.created_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    annotations
+}
+
+/// Merge keyed entries field by field, treating a missing entry as empty
+fn merge_maps<V: Default + Clone + PartialEq>(
+    base: &HashMap<String, V>,
+    ours: &HashMap<String, V>,
+    theirs: &HashMap<String, V>,
+    merge: impl Fn(&V, &V, &V) -> V,
+    is_empty: impl Fn(&V) -> bool,
+) -> HashMap<String, V> {
+    let empty = V::default();
+    let mut keys: Vec<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter_map(|key| {
+            let b = base.get(key).unwrap_or(&empty);
+            let o = ours.get(key).unwrap_or(&empty);
+            let t = theirs.get(key).unwrap_or(&empty);
+            let merged = merge(b, o, t);
+            (!is_empty(&merged)).then(|| (key.clone(), merged))
+        })
+        .collect()
+}
+
 /// Metadata for individual sessions
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -158,18 +757,225 @@ pub struct SessionMetadata {
     /// User notes about the session
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notes: Option<String>,
+
+    /// Bookmarks and notes on individual messages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<MessageAnnotation>,
+
+    /// Change times of `customName`, `starred` and `notes`, so copies from
+    /// other machines can be merged
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub updated: FieldTimes,
 }
 
 impl SessionMetadata {
-    /// Check if metadata has any values set
+    /// Check if metadata has no values set and no change times
+    ///
+    /// An entry whose fields were all cleared keeps their change times, so a
+    /// merge doesn't bring back older values from another copy.
     pub fn is_empty(&self) -> bool {
-        self.custom_name.is_none()
-            && self.starred.is_none()
-            && self.tags.is_empty()
-            && self.notes.is_none()
+        !self.has_values() && self.updated.is_empty()
+    }
+
+    /// Whether any field is set; an entry without is only kept for its times
+    pub fn has_values(&self) -> bool {
+        self.custom_name.is_some()
+            || self.starred.is_some()
+            || !self.tags.is_empty()
+            || self.notes.is_some()
+            || !self.annotations.is_empty()
+    }
+
+    /// Fill fields not set here from `other`, merging tags
+    pub fn merge_missing(&mut self, other: SessionMetadata) {
+        merge_times(&mut self.updated, &other.updated);
+        self.custom_name = self.custom_name.take().or(other.custom_name);
+        self.starred = self.starred.or(other.starred);
+        self.notes = self.notes.take().or(other.notes);
+        for tag in other.tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        for annotation in other.annotations {
+            if !self.annotations.iter().any(|a| a.id == annotation.id) {
+                self.annotations.push(annotation);
+            }
+        }
     }
 }
 
+/// A bookmark or markdown note on one message of a session
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAnnotation {
+    /// Stable id, so one message can carry several annotations
+    pub id: String,
+    pub message_uuid: String,
+    /// Listed among bookmarks
+    #[serde(default)]
+    pub bookmarked: bool,
+    /// Markdown note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Part of the message the annotation refers to; the whole message if unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<TextAnchor>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A range of a message's text
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TextAnchor {
+    /// Offset in UTF-16 code units (as measured by the frontend)
+    pub start: usize,
+    /// Exclusive end offset
+    pub end: usize,
+    /// The selected text, to find the range again if rendering changes
+    pub quote: String,
+}
+
+/// A bookmarked message with what's needed to open it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkEntry {
+    /// Session key in `UserMetadata.sessions` (the session file path)
+    pub session_id: String,
+    pub project_name: String,
+    pub annotation: MessageAnnotation,
+    /// The message is still in the session file
+    pub found: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_type: Option<String>,
+    /// Start of the message text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+    /// Messages newer than this one: passed as `offset` to
+    /// `load_session_messages_paginated`, the page ends with this message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<usize>,
+}
+
+/// A field both copies set differently when importing metadata
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataConflict {
+    /// "session", "project", "settings" or "savedSearches"
+    pub scope: String,
+    /// Session key or project path; empty for settings and saved searches
+    pub key: String,
+    /// Field name; `annotations.<id>` or `savedSearches.<id>` for list items
+    pub field: String,
+    pub local: serde_json::Value,
+    pub incoming: serde_json::Value,
+    /// The imported value was changed more recently and replaced the local one
+    pub incoming_won: bool,
+}
+
+/// What importing metadata from another machine changed
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataImportReport {
+    pub sessions_added: usize,
+    pub sessions_updated: usize,
+    pub projects_added: usize,
+    pub projects_updated: usize,
+    pub settings_updated: bool,
+    pub saved_searches_updated: bool,
+    pub conflicts: Vec<MetadataConflict>,
+}
+
+/// Session metadata whose file now lives under another key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRelink {
+    pub from: String,
+    pub to: String,
+}
+
+/// Session metadata with no session file behind it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedSession {
+    /// Session key in `UserMetadata.sessions` (the session file path)
+    pub session_id: String,
+    /// What pruning would discard
+    pub metadata: SessionMetadata,
+}
+
+/// Metadata entries that no longer match anything under `projects/`
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataOrphanReport {
+    /// Sessions found again by file name in another project folder
+    pub relinks: Vec<SessionRelink>,
+    pub orphaned_sessions: Vec<OrphanedSession>,
+    /// Project paths with no project folder
+    pub orphaned_projects: Vec<String>,
+    /// Missing sessions kept because they are in the trash or an archive
+    pub parked_sessions: usize,
+}
+
+/// What a metadata cleanup changed
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataCleanupResult {
+    pub relinked_sessions: usize,
+    pub pruned_sessions: usize,
+    pub pruned_projects: usize,
+}
+
+/// A named query over sessions in all projects
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub query: SessionQuery,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Filters a session must pass; unset filters match everything
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionQuery {
+    /// Case-insensitive text in the session name, summary or messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Glob rules on the project's actual path (`!` excludes)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<String>,
+    /// Tags the session must all have, manual or derived
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Text contained in the name of a model the session used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Last activity at or after this RFC 3339 time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_from: Option<String>,
+    /// Last activity at or before this RFC 3339 time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_to: Option<String>,
+    /// Last activity within this many days of when the query runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub within_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub starred: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_errors: Option<bool>,
+    /// Also search projects hidden from the sidebar
+    #[serde(default)]
+    pub include_hidden: bool,
+}
+
 /// Metadata for individual projects
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -185,12 +991,23 @@ pub struct ProjectMetadata {
     /// Parent project path for worktree grouping
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parent_project: Option<String>,
+
+    /// Change times of `hidden`, `alias` and `parentProject`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub updated: FieldTimes,
 }
 
 impl ProjectMetadata {
-    /// Check if metadata has any values set
+    /// Check if metadata has no values set and no change times
+    ///
+    /// Cleared entries are kept for their change times, as for sessions.
     pub fn is_empty(&self) -> bool {
-        self.hidden.is_none() && self.alias.is_none() && self.parent_project.is_none()
+        !self.has_values() && self.updated.is_empty()
+    }
+
+    /// Whether any field is set; an entry without is only kept for its times
+    pub fn has_values(&self) -> bool {
+        self.hidden.is_some() || self.alias.is_some() || self.parent_project.is_some()
     }
 }
 
@@ -213,6 +1030,53 @@ pub struct UserSettings {
     /// Project tree grouping mode: "none", "worktree", or "directory"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grouping_mode: Option<String>,
+
+    /// Rules that derive tags from session content
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub auto_tag_rules: Vec<AutoTagRule>,
+
+    /// Change times of every setting but `hiddenPatterns`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub updated: FieldTimes,
+}
+
+/// Tag sessions whose content matches a condition
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoTagRule {
+    pub tag: String,
+    pub condition: AutoTagCondition,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// What a session must contain for an [`AutoTagRule`] to apply
+///
+/// Every condition can only become true as a session grows, which lets
+/// appended lines be evaluated on top of earlier results.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AutoTagCondition {
+    /// A tool call touched a file matching a glob, relative to the session's
+    /// cwd unless absolute. Without `tool`, any Edit, `MultiEdit`, Write or
+    /// `NotebookEdit` counts.
+    #[serde(rename_all = "camelCase")]
+    FileTouched {
+        pattern: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool: Option<String>,
+    },
+    /// An assistant message used a model whose name contains this text (case-insensitive)
+    Model { contains: String },
+    /// A Bash tool call ran this program
+    BashCommand { program: String },
+    /// Total session cost exceeded this many US dollars
+    #[serde(rename_all = "camelCase")]
+    CostAbove { usd: f64 },
 }
 
 #[cfg(test)]
@@ -264,14 +1128,13 @@ mod tests {
     }
 
     #[test]
-    fn test_glob_pattern_matching() {
-        assert!(UserMetadata::matches_glob_pattern("abc", "abc"));
-        assert!(UserMetadata::matches_glob_pattern("abc", "a*"));
-        assert!(UserMetadata::matches_glob_pattern("abc", "*c"));
-        assert!(UserMetadata::matches_glob_pattern("abc", "a?c"));
-        assert!(UserMetadata::matches_glob_pattern("abc", "*"));
-        assert!(!UserMetadata::matches_glob_pattern("abc", "ab"));
-        assert!(!UserMetadata::matches_glob_pattern("abc", "a?"));
+    fn test_is_project_hidden_negated_pattern() {
+        let mut metadata = UserMetadata::new();
+        metadata.settings.hidden_patterns =
+            vec!["folders-dg-*".to_string(), "!folders-dg-keep".to_string()];
+
+        assert!(metadata.is_project_hidden("/Users/me/folders-dg-abc123"));
+        assert!(!metadata.is_project_hidden("/Users/me/folders-dg-keep"));
     }
 
     #[test]
@@ -284,6 +1147,17 @@ mod tests {
             ..Default::default()
         };
         assert!(!with_name.is_empty());
+
+        // A cleared entry is kept for the time it was cleared
+        let cleared = SessionMetadata {
+            updated: FieldTimes::from([(
+                "customName".to_string(),
+                "2025-01-01T00:00:00Z".to_string(),
+            )]),
+            ..Default::default()
+        };
+        assert!(!cleared.is_empty());
+        assert!(!cleared.has_values());
     }
 
     #[test]
@@ -302,4 +1176,88 @@ mod tests {
 
         assert_eq!(metadata, deserialized);
     }
+
+    #[test]
+    fn test_rename_keys_merges_into_existing() {
+        let mut metadata = UserMetadata::new();
+        metadata.get_session_mut("/old/a.jsonl").tags = vec!["x".to_string()];
+        metadata.get_session_mut("/old/a.jsonl").starred = Some(true);
+        metadata.get_session_mut("/new/a.jsonl").tags = vec!["y".to_string()];
+        let renames = HashMap::from([("/old/a.jsonl".to_string(), "/new/a.jsonl".to_string())]);
+        assert_eq!(metadata.rename_session_keys(&renames), 1);
+        let session = metadata.get_session("/new/a.jsonl").unwrap();
+        assert_eq!(session.tags, ["y", "x"]);
+        assert_eq!(session.starred, Some(true));
+        assert!(metadata.get_session("/old/a.jsonl").is_none());
+
+        metadata.get_project_mut("/old/repo").alias = Some("Repo".to_string());
+        metadata.get_project_mut("/new/repo").hidden = Some(false);
+        metadata.get_project_mut("/old/repo-wt").parent_project = Some("/old/repo".to_string());
+        assert!(metadata.rename_project("/old/repo", "/new/repo"));
+        let project = metadata.get_project("/new/repo").unwrap();
+        assert_eq!(project.alias.as_deref(), Some("Repo"));
+        assert_eq!(project.hidden, Some(false));
+        assert_eq!(
+            metadata
+                .get_project("/old/repo-wt")
+                .unwrap()
+                .parent_project
+                .as_deref(),
+            Some("/new/repo")
+        );
+        assert!(!metadata.rename_project("/missing", "/other"));
+    }
+
+    #[test]
+    fn test_merge_concurrent_keeps_rule_order() {
+        let rules = |list: &[&str]| list.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let mut base = UserMetadata::new();
+        base.settings.hidden_patterns = rules(&["*tmp*", "!*tmp-keep*", "*scratch*"]);
+
+        // We move the exception first; they add a rule after `*tmp*`
+        let mut ours = base.clone();
+        ours.settings.hidden_patterns = rules(&["!*tmp-keep*", "*tmp*", "*scratch*"]);
+        let mut theirs = base.clone();
+        theirs.settings.hidden_patterns =
+            rules(&["*tmp*", "!*tmp-docs*", "!*tmp-keep*", "*scratch*"]);
+
+        let merged = UserMetadata::merge_concurrent(&base, &ours, &theirs);
+        assert_eq!(
+            merged.settings.hidden_patterns,
+            ["!*tmp-keep*", "*tmp*", "!*tmp-docs*", "*scratch*"]
+        );
+        let merged = UserMetadata::merge_concurrent(&base, &theirs, &ours);
+        assert_eq!(
+            merged.settings.hidden_patterns,
+            ["!*tmp-keep*", "*tmp*", "!*tmp-docs*", "*scratch*"]
+        );
+    }
+
+    #[test]
+    fn test_merge_concurrent_keeps_both_edits() {
+        let mut base = UserMetadata::new();
+        base.get_session_mut("a").tags = vec!["x".to_string(), "y".to_string()];
+        base.get_session_mut("b").starred = Some(true);
+        base.get_project_mut("/repo").alias = Some("Repo".to_string());
+
+        // We tag `a` and unstar `b`; they drop tag `y`, rename `a` and hide `/repo`
+        let mut ours = base.clone();
+        ours.get_session_mut("a").tags.push("z".to_string());
+        ours.sessions.remove("b");
+        let mut theirs = base.clone();
+        theirs.get_session_mut("a").tags.retain(|t| t != "y");
+        theirs.get_session_mut("a").custom_name = Some("Renamed".to_string());
+        theirs.get_project_mut("/repo").hidden = Some(true);
+        theirs.revision = 7;
+
+        let merged = UserMetadata::merge_concurrent(&base, &ours, &theirs);
+        let a = merged.get_session("a").unwrap();
+        assert_eq!(a.tags, ["x", "z"]);
+        assert_eq!(a.custom_name.as_deref(), Some("Renamed"));
+        assert!(merged.get_session("b").is_none());
+        let project = merged.get_project("/repo").unwrap();
+        assert_eq!(project.alias.as_deref(), Some("Repo"));
+        assert_eq!(project.hidden, Some(true));
+        assert_eq!(merged.revision, 7);
+    }
 }
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::JournalEntry;
+
+/// A user-defined redaction rule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RedactionRule {
+    /// Shown in findings and in the `[REDACTED:<name>]` replacement
+    pub name: String,
+    /// Regular expression; if it has capture groups, only the first group is redacted
+    pub pattern: String,
+}
+
+/// A secret or piece of personal data found in a transcript
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RedactionFinding {
+    pub detector: String,
+    /// 1-based line number in the session file
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_uuid: Option<String>,
+    pub field: String, // "prompt", "assistant", "thinking", "tool_input", "tool_output", "summary", "metadata", "raw"
+    /// The matched value with all but its first characters masked
+    pub masked_value: String,
+}
+
+/// Findings for one session file, without modifying it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionPreview {
+    pub file_path: String,
+    pub total_findings: usize,
+    pub detector_counts: HashMap<String, usize>,
+    pub findings: Vec<RedactionFinding>,
+}
+
+/// Outcome of writing a redacted session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionResult {
+    pub source_path: String,
+    pub output_path: String,
+    pub total_findings: usize,
+    pub redacted_lines: usize,
+    /// Set when the session was redacted in place and the original snapshot was kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub journal_entry: Option<JournalEntry>,
+}
+
+/// One place a secret appeared
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SecretOccurrence {
+    /// Folder name under `projects/`; `None` for user-level settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub file_path: String,
+    /// 1-based line number, for session files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_uuid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    pub field: String, // "prompt", "assistant", "thinking", "tool_input", "tool_output", "summary", "metadata", "raw", "settings_env"
+    /// Variable name, for `settings_env` findings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_var: Option<String>,
+}
+
+/// A distinct secret and everywhere it was exposed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretExposure {
+    pub detector: String,
+    pub masked_value: String,
+    /// Identifies the secret within one report (`secret-<n>` in report order);
+    /// not derived from the value, so it can't be compared across reports
+    pub fingerprint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<String>,
+    pub occurrences: Vec<SecretOccurrence>,
+}
+
+/// Read-only audit of credentials exposed across all history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretScanReport {
+    pub scanned_sessions: usize,
+    pub scanned_settings_files: usize,
+    pub total_occurrences: usize,
+    pub exposures: Vec<SecretExposure>,
+}
@@ -51,6 +51,64 @@ pub struct ClaudeSession {
     pub has_tool_use: bool,
     pub has_errors: bool,
     pub summary: Option<String>,
+    /// Tags produced by the user's auto-tag rules (kept apart from manual tags)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub auto_tags: Vec<String>,
+}
+
+/// Sessions matching a query, and the projects that couldn't be searched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionQueryResult {
+    pub sessions: Vec<ClaudeSession>,
+    pub skipped_projects: Vec<SkippedProject>,
+}
+
+/// A project left out of a query because its sessions couldn't be loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedProject {
+    pub project_path: String,
+    pub error: String,
+}
+
+/// A session forked at a message into a new session file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForkSessionResult {
+    pub source_session_id: String,
+    pub new_session_id: String,
+    pub file_path: String,
+    /// Lines written to the new session file
+    pub entry_count: usize,
+    /// The fork ends with a `tool_use` whose results were not included
+    pub ends_with_pending_tool_use: bool,
+}
+
+/// A session file written to recently, likely by a running agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveSession {
+    pub file_path: String,
+    /// The project folder under `projects/`
+    pub project_path: String,
+    pub last_modified: String,
+    /// Seconds since the last write
+    pub idle_seconds: u64,
+    pub size: u64,
+}
+
+/// Sessions moved from one project folder into another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRelocationResult {
+    pub source_project_path: String,
+    pub target_project_path: String,
+    /// The target folder already existed and the sessions were merged into it
+    pub merged: bool,
+    pub moved_sessions: usize,
+    /// Sessions already in the target folder with identical content
+    pub duplicate_sessions: usize,
+    /// Session files whose `cwd` fields were rewritten
+    pub rewritten_files: usize,
+    pub rewritten_lines: usize,
+    /// The source folder was empty afterwards and has been removed
+    pub source_removed: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,6 +138,7 @@ mod tests {
             has_tool_use: true,
             has_errors: false,
             summary: Some("Test conversation".to_string()),
+            auto_tags: Vec::new(),
         };
 
         let serialized = serde_json::to_string(&session).unwrap();
@@ -226,6 +226,7 @@ mod session_snapshots {
             has_tool_use: true,
             has_errors: false,
             summary: Some("Test conversation summary".to_string()),
+            auto_tags: Vec::new(),
         };
 
         assert_json_snapshot!("claude_session", session);
@@ -301,6 +302,10 @@ mod edit_snapshots {
             lines_added: 3,
             lines_removed: 1,
             cwd: Some("/path/to".to_string()),
+            snapshot_backup: None,
+            message_uuid: None,
+            tool_use_id: None,
+            prompt_uuid: None,
         };
 
         assert_json_snapshot!("recent_file_edit", edit);
@@ -319,6 +324,10 @@ mod edit_snapshots {
                 lines_added: 5,
                 lines_removed: 2,
                 cwd: Some("/project".to_string()),
+                snapshot_backup: None,
+                message_uuid: None,
+                tool_use_id: None,
+                prompt_uuid: None,
             }],
             total_edits_count: 10,
             unique_files_count: 3,
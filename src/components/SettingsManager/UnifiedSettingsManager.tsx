@@ -11,6 +11,7 @@
 import * as React from "react";
 import { useTranslation } from "react-i18next";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { Button } from "@/components/ui/button";
 import { LoadingState } from "@/components/ui/loading";
 import { RefreshCw, FolderTree } from "lucide-react";
@@ -21,6 +22,7 @@ import type {
   ClaudeCodeSettings,
   MCPServerConfig,
   MCPSource,
+  ConfigFileChangeEvent,
 } from "@/types";
 import { SettingsSidebar } from "./sidebar/SettingsSidebar";
 import { SettingsEditorPane } from "./editor/SettingsEditorPane";
@@ -110,6 +112,14 @@ export const UnifiedSettingsManager: React.FC<UnifiedSettingsManagerProps> = ({
   // Pending changes state (shared across components for dirty tracking)
   const [pendingSettings, setPendingSettings] = React.useState<ClaudeCodeSettings | null>(null);
 
+  // Version of each scope's content the editor is based on, sent with saves so
+  // edits don't overwrite changes made outside the app in the meantime
+  const [baseVersions, setBaseVersions] = React.useState<AllSettingsResponse["versions"]>({});
+  const pendingScopeRef = React.useRef<SettingsScope | null>(null);
+  React.useEffect(() => {
+    pendingScopeRef.current = pendingSettings ? activeScope : null;
+  }, [pendingSettings, activeScope]);
+
   // Sync with initial prop if it changes
   React.useEffect(() => {
     setProjectPath(initialProjectPath);
@@ -122,6 +132,7 @@ export const UnifiedSettingsManager: React.FC<UnifiedSettingsManagerProps> = ({
     userSettings: mcpUserSettings,
     userMcpFile: mcpUserMcpFile,
     projectMcpFile: mcpProjectMcpFile,
+    loadAllMCPServers,
     saveMCPServers,
   } = useMCPServers(projectPath);
 
@@ -132,6 +143,7 @@ export const UnifiedSettingsManager: React.FC<UnifiedSettingsManagerProps> = ({
       setError(null);
       const settingsResult = await invoke<AllSettingsResponse>("get_all_settings", { projectPath });
       setAllSettings(settingsResult);
+      setBaseVersions(settingsResult.versions ?? {});
     } catch (err) {
       setError(String(err));
     } finally {
@@ -143,6 +155,68 @@ export const UnifiedSettingsManager: React.FC<UnifiedSettingsManagerProps> = ({
     loadSettings();
   }, [loadSettings]);
 
+  // Follow edits made outside the app (Claude Code rewrites ~/.claude.json often).
+  // Refresh without the loading state so the editor stays mounted. Pending
+  // edits keep the version they were based on, so saving them over the
+  // outside change is rejected.
+  React.useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+
+    const refreshSettings = async () => {
+      try {
+        const settingsResult = await invoke<AllSettingsResponse>("get_all_settings", { projectPath });
+        setAllSettings(settingsResult);
+        setBaseVersions((previous) => {
+          const versions = { ...settingsResult.versions };
+          const pendingScope = pendingScopeRef.current;
+          if (pendingScope) {
+            versions[pendingScope] = previous?.[pendingScope];
+          }
+          return versions;
+        });
+      } catch (err) {
+        console.error("Failed to refresh settings:", err);
+      }
+    };
+
+    const startWatching = async () => {
+      try {
+        await invoke("start_config_watcher", { projectPath });
+        const stop = await listen<ConfigFileChangeEvent>("claude-config-changed", (event) => {
+          const { scope } = event.payload;
+          if (scope !== "user_mcp" && scope !== "project_mcp" && scope !== "claude_json") {
+            refreshSettings();
+          }
+          // settings.json may hold legacy mcpServers too
+          if (scope !== "project" && scope !== "local" && scope !== "managed") {
+            loadAllMCPServers();
+          }
+        });
+        if (cancelled) {
+          stop();
+        } else {
+          unlisten = stop;
+        }
+      } catch (err) {
+        console.error("Failed to watch settings files:", err);
+      }
+    };
+
+    startWatching();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [projectPath, loadAllMCPServers]);
+
+  // start_config_watcher replaces the previous watcher, so only stop on unmount
+  React.useEffect(() => {
+    return () => {
+      invoke("stop_config_watcher").catch(() => {});
+    };
+  }, []);
+
   // Parse current settings
   const currentSettings: ClaudeCodeSettings = React.useMemo(() => {
     const content = allSettings?.[activeScope] ?? null;
@@ -167,6 +241,9 @@ export const UnifiedSettingsManager: React.FC<UnifiedSettingsManagerProps> = ({
           scope,
           content: JSON.stringify(newSettings, null, 2),
           projectPath: scope !== "user" ? effectiveProjectPath : undefined,
+          // Another project's files weren't loaded here, so there's no base to check
+          expectedVersion:
+            scope === "user" || effectiveProjectPath === projectPath ? baseVersions?.[scope] : undefined,
         });
         await loadSettings();
       } catch (err) {
@@ -174,7 +251,7 @@ export const UnifiedSettingsManager: React.FC<UnifiedSettingsManagerProps> = ({
         throw err;
       }
     },
-    [activeScope, projectPath, loadSettings]
+    [activeScope, projectPath, baseVersions, loadSettings]
   );
 
   const isReadOnly = activeScope === "managed";
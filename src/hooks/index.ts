@@ -19,4 +19,4 @@ export type { UseMCPServersResult } from "./useMCPServers";
 
 // FILE WATCHER
 export { useFileWatcher } from "./useFileWatcher";
-export type { UseFileWatcherResult } from "./useFileWatcher";
+export type { FileWatcherEvent, FileWatcherHealth, UseFileWatcherResult } from "./useFileWatcher";
@@ -1,14 +1,45 @@
 import { useEffect, useCallback, useRef, useState } from 'react';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { toast } from 'sonner';
+import type { ClaudeMessage } from '../types';
 
 /**
  * Event payload structure from Tauri file watcher
+ *
+ * `session-file-changed` carries "appended" or "rewritten", `session-file-created`
+ * carries "created" or "renamed" and `session-file-deleted` carries "deleted".
  */
-interface FileWatcherEvent {
+export interface FileWatcherEvent {
   projectPath: string;
   sessionPath: string;
-  eventType: 'changed' | 'created' | 'deleted';
+  eventType: 'created' | 'appended' | 'rewritten' | 'deleted' | 'renamed';
+  /** The old path of a renamed session */
+  previousPath?: string;
+  /** File size after the change */
+  size?: number;
+  /** Start and end (exclusive) byte offsets of the appended lines */
+  byteRange?: [number, number];
+  /** Messages parsed from an append, or lines appended if they weren't parsed */
+  messageCount?: number;
+  /** The appended messages, omitted for appends over 1 MiB */
+  messages?: ClaudeMessage[];
+}
+
+/**
+ * Status of the backend watcher on one Claude folder, from
+ * `get_file_watcher_health` or the `file-watcher-health` event
+ */
+export interface FileWatcherHealth {
+  root: string;
+  mode: 'native' | 'poll';
+  /** "waiting" while there is no projects folder to watch */
+  status: 'watching' | 'waiting' | 'stopped';
+  startedAt: string;
+  /** Last time a session change was seen */
+  lastEventAt?: string;
+  lastError?: string;
+  /** Times the watch was set up again after an error or a replaced folder */
+  rearmCount: number;
 }
 
 /**
@@ -17,6 +48,11 @@ interface FileWatcherEvent {
 interface UseFileWatcherOptions {
   /** Callback fired when a session file is modified */
   onSessionChanged?: (event: FileWatcherEvent) => void;
+  /**
+   * Callback fired for every append, without debouncing, so each batch of new
+   * messages arrives exactly once
+   */
+  onMessagesAppended?: (event: FileWatcherEvent) => void;
   /** Callback fired when a new session file is created */
   onSessionCreated?: (event: FileWatcherEvent) => void;
   /** Callback fired when a session file is deleted */
@@ -60,6 +96,7 @@ export interface UseFileWatcherResult {
 export function useFileWatcher(options: UseFileWatcherOptions = {}): UseFileWatcherResult {
   const {
     onSessionChanged,
+    onMessagesAppended,
     onSessionCreated,
     onSessionDeleted,
     enabled = true,
@@ -137,6 +174,9 @@ export function useFileWatcher(options: UseFileWatcherOptions = {}): UseFileWatc
 
       // Listen to file changed events
       const unlistenChanged = await listen<FileWatcherEvent>('session-file-changed', (event) => {
+        if (event.payload.eventType === 'appended') {
+          onMessagesAppended?.(event.payload);
+        }
         createDebouncedCallback(onSessionChanged, event.payload);
       });
 
@@ -178,7 +218,13 @@ export function useFileWatcher(options: UseFileWatcherOptions = {}): UseFileWatc
       isWatchingRef.current = false;
       setIsWatching(false);
     }
-  }, [onSessionChanged, onSessionCreated, onSessionDeleted, createDebouncedCallback]);
+  }, [
+    onSessionChanged,
+    onMessagesAppended,
+    onSessionCreated,
+    onSessionDeleted,
+    createDebouncedCallback,
+  ]);
 
   /**
    * Auto-start/stop based on enabled prop
@@ -121,6 +121,9 @@ export const useMCPServers = (projectPath?: string): UseMCPServersResult => {
   // Project source
   const [projectMcpFile, setProjectMcpFile] = useState<Record<string, MCPServerConfig>>({});
 
+  // Version of each source as loaded, sent with saves to detect outside changes
+  const [versions, setVersions] = useState<AllMCPServersResponse["versions"]>({});
+
   const [isLoading, setIsLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
 
@@ -149,6 +152,8 @@ export const useMCPServers = (projectPath?: string): UseMCPServersResult => {
 
       // Project source
       setProjectMcpFile(response.projectMcpFile ?? {});
+
+      setVersions(response.versions ?? {});
     } catch (err) {
       const errorMessage = err instanceof Error ? err.message : String(err);
       setError(errorMessage);
@@ -180,6 +185,8 @@ export const useMCPServers = (projectPath?: string): UseMCPServersResult => {
           source,
           servers: JSON.stringify(servers),
           projectPath: effectiveProjectPath,
+          // Another project's sources weren't loaded here, so there's no base to check
+          expectedVersion: effectiveProjectPath === projectPath ? versions?.[source] : undefined,
         });
 
         // Reload from backend after save to ensure consistency
@@ -194,7 +201,7 @@ export const useMCPServers = (projectPath?: string): UseMCPServersResult => {
         setIsLoading(false);
       }
     },
-    [projectPath, loadAllMCPServers]
+    [projectPath, versions, loadAllMCPServers]
   );
 
   /**
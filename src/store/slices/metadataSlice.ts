@@ -5,6 +5,7 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type { StateCreator } from "zustand";
 import type {
   UserMetadata,
@@ -13,7 +14,7 @@ import type {
   UserSettings,
 } from "../../types";
 import { DEFAULT_USER_METADATA } from "../../types";
-import { matchGlobPattern } from "../../utils/globUtils";
+import { matchGlobRules } from "../../utils/globUtils";
 import type { FullAppStore } from "./types";
 
 // ============================================================================
@@ -84,6 +85,9 @@ export const initialMetadataState: MetadataSliceState = {
   metadataError: null,
 };
 
+/** Listener for `user-metadata-changed`, registered on first load */
+let metadataChangesListener: Promise<UnlistenFn> | null = null;
+
 // ============================================================================
 // Slice Creator
 // ============================================================================
@@ -106,6 +110,20 @@ export const createMetadataSlice: StateCreator<
         isMetadataLoaded: true,
         isMetadataLoading: false,
       });
+
+      // Follow edits saved by other windows or sync tools
+      if (!metadataChangesListener) {
+        metadataChangesListener = listen<UserMetadata>(
+          "user-metadata-changed",
+          (event) => {
+            set({ userMetadata: event.payload });
+          }
+        );
+        metadataChangesListener.catch((error) => {
+          console.warn("Failed to listen for metadata changes:", error);
+          metadataChangesListener = null;
+        });
+      }
     } catch (error) {
       console.error("Failed to load user metadata:", error);
       set({
@@ -210,15 +228,11 @@ export const createMetadataSlice: StateCreator<
       return true;
     }
 
-    // Check hidden patterns
-    const patterns = userMetadata.settings.hiddenPatterns || [];
-    for (const pattern of patterns) {
-      if (matchGlobPattern(projectPath, pattern)) {
-        return true;
-      }
-    }
-
-    return false;
+    // Check hidden patterns (ordered; `!pattern` un-hides)
+    return matchGlobRules(
+      projectPath,
+      userMetadata.settings.hiddenPatterns || []
+    );
   },
 
   hideProject: async (projectPath: string) => {
@@ -3,7 +3,11 @@
  */
 
 import { describe, it, expect } from "vitest";
-import { matchGlobPattern } from "../utils/globUtils";
+import {
+  matchGlobPath,
+  matchGlobPattern,
+  matchGlobRules,
+} from "../utils/globUtils";
 
 describe("matchGlobPattern", () => {
   describe("basic matching", () => {
@@ -74,7 +78,6 @@ describe("matchGlobPattern", () => {
     it("should escape regex special characters", () => {
       expect(matchGlobPattern("file.ts", "file.ts")).toBe(true);
       expect(matchGlobPattern("file-ts", "file.ts")).toBe(false);
-      expect(matchGlobPattern("test[1]", "test[1]")).toBe(true);
       expect(matchGlobPattern("test(1)", "test(1)")).toBe(true);
       expect(matchGlobPattern("test+1", "test+1")).toBe(true);
       expect(matchGlobPattern("test$1", "test$1")).toBe(true);
@@ -106,6 +109,11 @@ describe("matchGlobPattern", () => {
       expect(matchGlobPattern("test", manyWildcards)).toBe(false);
     });
 
+    it("should count a run of * as one wildcard, like the backend", () => {
+      const runs = "**/a".repeat(10);
+      expect(matchGlobPattern("x/a".repeat(10), runs)).toBe(true);
+    });
+
     it("should accept patterns within wildcard limit", () => {
       // Pattern with 10 wildcards should work
       const maxWildcards = "*a*b*c*d*e*f*g*h*i*";
@@ -151,4 +159,52 @@ describe("matchGlobPattern", () => {
       ).toBe(false);
     });
   });
+
+  describe("globstar, classes and braces", () => {
+    it("should keep * and ? within a path segment", () => {
+      expect(matchGlobPattern("a/b", "a*")).toBe(false);
+      expect(matchGlobPattern("a/b", "a?b")).toBe(false);
+      expect(matchGlobPattern("a/b", "a**")).toBe(true);
+    });
+
+    it("should match any depth with **", () => {
+      expect(matchGlobPattern("migrations/001.sql", "migrations/**")).toBe(true);
+      expect(matchGlobPattern("migrations/2024/001.sql", "migrations/**")).toBe(true);
+      expect(matchGlobPattern("db/migrations/001.sql", "migrations/**")).toBe(false);
+      expect(matchGlobPattern("db/migrations/001.sql", "**/migrations/**")).toBe(true);
+      expect(matchGlobPattern("migrations/001.sql", "**/migrations/**")).toBe(true);
+    });
+
+    it("should match character classes", () => {
+      expect(matchGlobPattern("test1", "test[1]")).toBe(true);
+      expect(matchGlobPattern("test[1]", "test[1]")).toBe(false);
+      expect(matchGlobPattern("v7", "v[0-9]")).toBe(true);
+      expect(matchGlobPattern("vx", "v[!0-9]")).toBe(true);
+      expect(matchGlobPattern("*", "[*]")).toBe(true);
+      expect(matchGlobPattern("test[1", "test[1")).toBe(true);
+    });
+
+    it("should match brace alternatives", () => {
+      expect(matchGlobPattern("Cargo.toml", "*.{rs,toml}")).toBe(true);
+      expect(matchGlobPattern("a.ts", "*.{rs,toml}")).toBe(false);
+      expect(matchGlobPattern("a{b", "a{b")).toBe(true);
+    });
+  });
+
+  describe("paths and rules", () => {
+    it("should match patterns without / against the last path component", () => {
+      expect(matchGlobPath("/Users/me/folders-dg-abc", "folders-dg-*")).toBe(true);
+      expect(matchGlobPath("/Users/folders-dg-abc/app", "folders-dg-*")).toBe(false);
+      expect(matchGlobPath("/Users/jack/project", "/Users/*/project")).toBe(true);
+    });
+
+    it("should let later negations override earlier matches", () => {
+      const rules = ["folders-*", "!folders-keep", "folders-keep-old*"];
+      expect(matchGlobRules("/x/folders-dg", rules)).toBe(true);
+      expect(matchGlobRules("/x/folders-keep", rules)).toBe(false);
+      expect(matchGlobRules("/x/folders-keep-old1", rules)).toBe(true);
+      expect(matchGlobRules("/x/other", rules)).toBe(false);
+      expect(matchGlobRules("/x/other", [])).toBe(false);
+    });
+  });
 });
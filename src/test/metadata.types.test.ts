@@ -22,15 +22,15 @@ import {
 // ============================================================================
 
 describe("METADATA_SCHEMA_VERSION", () => {
-  it("should be version 1", () => {
-    expect(METADATA_SCHEMA_VERSION).toBe(1);
+  it("should be version 3", () => {
+    expect(METADATA_SCHEMA_VERSION).toBe(3);
   });
 });
 
 describe("DEFAULT_USER_METADATA", () => {
   it("should have correct initial structure", () => {
     expect(DEFAULT_USER_METADATA).toEqual({
-      version: 1,
+      version: 3,
       sessions: {},
       projects: {},
       settings: {},
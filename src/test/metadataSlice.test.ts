@@ -67,8 +67,8 @@ describe("metadataSlice", () => {
       });
     });
 
-    it("should have userMetadata with version 1", () => {
-      expect(initialMetadataState.userMetadata.version).toBe(1);
+    it("should have userMetadata with version 2", () => {
+      expect(initialMetadataState.userMetadata.version).toBe(3);
     });
 
     it("should have empty sessions object", () => {
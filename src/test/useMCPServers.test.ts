@@ -74,6 +74,7 @@ const createMockResponse = (
   userSettings: mockUserSettingsServers,
   userMcpFile: mockUserMcpFileServers,
   projectMcpFile: mockProjectMcpServers,
+  versions: { user_claude_json: "user-v1", local_claude_json: "local-v1" },
   ...overrides,
 });
 
@@ -256,6 +257,8 @@ describe("useMCPServers", () => {
         source: "user_claude_json",
         servers: JSON.stringify(newServers),
         projectPath: "/test/project",
+        // The version loaded with the servers the edit was based on
+        expectedVersion: "user-v1",
       });
 
       // Local state should be updated via reload after save
@@ -289,6 +292,8 @@ describe("useMCPServers", () => {
         source: "local_claude_json",
         servers: JSON.stringify(newServers),
         projectPath: "/test/project",
+        // The version loaded with the servers the edit was based on
+        expectedVersion: "local-v1",
       });
 
       // Local state should be updated via reload after save
@@ -55,6 +55,8 @@ export interface ClaudeSession {
   has_errors: boolean;
   summary?: string;
   relevance?: number;
+  /** Tags produced by auto-tag rules, separate from the user's own tags */
+  auto_tags?: string[];
 }
 
 // ============================================================================
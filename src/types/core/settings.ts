@@ -418,6 +418,8 @@ export interface AllSettingsResponse {
   local: string | null;
   /** Managed settings JSON */
   managed: string | null;
+  /** Version of the user, project and local content, sent back with `save_settings` */
+  versions?: Partial<Record<SettingsScope, string>>;
 }
 
 /**
@@ -453,6 +455,26 @@ export interface AllMCPServersResponse {
   userClaudeJson: Record<string, MCPServerConfig> | null;
   /** MCP servers from ~/.claude.json projects.<path>.mcpServers (official local-scoped) */
   localClaudeJson: Record<string, MCPServerConfig> | null;
+  /** Version of each source's servers, sent back with `save_mcp_servers` */
+  versions?: Partial<Record<MCPSource, string>>;
+}
+
+/**
+ * Config file scopes reported by the config watcher: the settings scopes plus
+ * ~/.claude/.mcp.json, <project>/.mcp.json and ~/.claude.json
+ */
+export type ConfigFileScope = SettingsScope | "user_mcp" | "project_mcp" | "claude_json";
+
+/**
+ * Payload of the `claude-config-changed` event
+ */
+export interface ConfigFileChangeEvent {
+  scope: ConfigFileScope;
+  path: string;
+  /** The project whose files are watched, for project scopes */
+  projectPath?: string;
+  /** False when the file was deleted */
+  exists: boolean;
 }
 
 /**
@@ -18,6 +18,74 @@ export interface RecentFileEdit {
   lines_added: number;
   lines_removed: number;
   cwd?: string;
+  snapshot_backup?: FileHistoryBackup;
+  /** Assistant message whose tool_use made this edit */
+  message_uuid?: string;
+  tool_use_id?: string;
+  /** User prompt that started the turn */
+  prompt_uuid?: string;
+}
+
+// ============================================================================
+// File Edit Origin
+// ============================================================================
+
+/**
+ * A message that edited a file (reverse lookup from file to messages)
+ */
+export interface FileEditOrigin {
+  file_path: string;
+  session_id: string;
+  timestamp: string;
+  operation_type: "edit" | "write" | ShellOperationType;
+  lines_added: number;
+  lines_removed: number;
+  message_uuid?: string;
+  tool_use_id?: string;
+  prompt_uuid?: string;
+  /** Exact command line for shell operations */
+  command?: string;
+}
+
+// ============================================================================
+// Shell File Operation
+// ============================================================================
+
+export type ShellOperationType = "delete" | "move" | "rewrite";
+
+/**
+ * File deleted, moved or rewritten by a Bash tool call (best-effort detection)
+ */
+export interface ShellFileOperation {
+  file_path: string;
+  operation: ShellOperationType;
+  /** New location for moves */
+  destination?: string;
+  command: string;
+  timestamp: string;
+  session_id: string;
+  cwd?: string;
+  message_uuid?: string;
+  tool_use_id?: string;
+  prompt_uuid?: string;
+}
+
+// ============================================================================
+// File History Backup
+// ============================================================================
+
+/**
+ * Backup recorded by a Claude Code file-history snapshot
+ * (stored under ~/.claude/file-history/{session_id}/{backup_file_name})
+ */
+export interface FileHistoryBackup {
+  file_path: string;
+  session_id: string;
+  message_id: string;
+  version: number;
+  backup_file_name?: string;
+  backup_time?: string;
+  backup_path?: string;
 }
 
 // ============================================================================
@@ -43,3 +111,50 @@ export interface PaginatedRecentEdits {
   limit: number;
   has_more: boolean;
 }
+
+// ============================================================================
+// File Activity (cross-session file query)
+// ============================================================================
+
+export type FileAccessKind =
+  | "read"
+  | "edit"
+  | "write"
+  | "grep"
+  | "shell"
+  | "mention";
+
+/**
+ * A message that accessed or referenced a matching file
+ */
+export interface FileActivityMessage {
+  uuid?: string;
+  timestamp?: string;
+  /** Tool name, or "mention" for references in message text */
+  tool_name: string;
+  access: FileAccessKind;
+  file_path: string;
+  tool_use_id?: string;
+}
+
+/**
+ * File activity within a single session
+ */
+export interface FileSessionActivity {
+  session_id: string;
+  session_path: string;
+  tool_counts: Record<string, number>;
+  first_timestamp?: string;
+  last_timestamp?: string;
+  messages: FileActivityMessage[];
+}
+
+/**
+ * Sessions and messages that touched a file path or glob
+ */
+export interface FileActivityResult {
+  pattern: string;
+  total_matches: number;
+  tool_counts: Record<string, number>;
+  sessions: FileSessionActivity[];
+}
@@ -107,9 +107,25 @@ export type {
 // Project & Metadata Types
 export type {
   SessionMetadata,
+  TextAnchor,
+  MessageAnnotation,
+  BookmarkEntry,
+  SessionRelink,
+  OrphanedSession,
+  MetadataOrphanReport,
+  MetadataCleanupResult,
+  MetadataConflict,
+  MetadataImportReport,
   ProjectMetadata,
   GroupingMode,
+  AutoTagCondition,
+  AutoTagRule,
+  SavedSearch,
+  SessionQuery,
   UserSettings,
+  TrustedSymlinkTarget,
+  UntrustedSymlink,
+  TrustedSymlinkStatus,
   UserMetadata,
 } from "./core/project";
 export {
@@ -142,6 +158,8 @@ export type {
   AllSettingsResponse,
   MCPSource,
   AllMCPServersResponse,
+  ConfigFileScope,
+  ConfigFileChangeEvent,
   ClaudeJsonConfigResponse,
   ClaudeJsonProjectSettings,
   ScopedSettings,
@@ -186,6 +204,27 @@ export {
 // Session State
 export type {
   AppState,
+  JournalEntry,
+  RedactionRule,
+  RedactionField,
+  RedactionFinding,
+  RedactionPreview,
+  RedactionResult,
+  SecretOccurrence,
+  SecretExposure,
+  SecretScanReport,
+  TrashEntry,
+  ArchivedSession,
+  SessionArchive,
+  ArchiveRestoreResult,
+  RetentionCandidate,
+  RetentionPreview,
+  SessionQueryResult,
+  SkippedProject,
+  ForkSessionResult,
+  ProjectRelocationResult,
+  ActiveSession,
+  SessionTailEvent,
 } from "./session.types";
 
 // Stats Types
@@ -204,7 +243,19 @@ export type {
 } from "./stats.types";
 
 // Edit Types
-export type { RecentFileEdit, RecentEditsResult, PaginatedRecentEdits } from "./edit.types";
+export type {
+  RecentFileEdit,
+  RecentEditsResult,
+  PaginatedRecentEdits,
+  FileHistoryBackup,
+  FileEditOrigin,
+  ShellOperationType,
+  ShellFileOperation,
+  FileAccessKind,
+  FileActivityMessage,
+  FileSessionActivity,
+  FileActivityResult,
+} from "./edit.types";
 
 // Update Types
 export type {
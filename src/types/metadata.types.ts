@@ -10,10 +10,10 @@
  * @see src/types/core/project.ts for the canonical implementation
  */
 
-import { matchGlobPattern } from "../utils/globUtils";
+import { matchGlobRules } from "../utils/globUtils";
 
 /** Current schema version for migration support */
-export const METADATA_SCHEMA_VERSION = 1;
+export const METADATA_SCHEMA_VERSION = 3;
 
 /** Metadata for individual sessions */
 export interface SessionMetadata {
@@ -27,6 +27,103 @@ export interface SessionMetadata {
   notes?: string;
   /** Whether the session has been renamed via Claude Code native rename (synced with CLI) */
   hasClaudeCodeName?: boolean;
+  /** Per-message bookmarks and notes */
+  annotations?: MessageAnnotation[];
+  /** Change times (RFC 3339) of `customName`, `starred` and `notes`, for merging imports */
+  updated?: Record<string, string>;
+}
+
+/** Character range a note is anchored to, in UTF-16 offsets into the message text */
+export interface TextAnchor {
+  start: number;
+  end: number;
+  /** Anchored text, used to re-find the range if offsets drift */
+  quote: string;
+}
+
+/** Bookmark and/or note attached to a single message */
+export interface MessageAnnotation {
+  id: string;
+  messageUuid: string;
+  bookmarked?: boolean;
+  /** Markdown note */
+  note?: string;
+  anchor?: TextAnchor;
+  createdAt: string;
+  updatedAt: string;
+}
+
+/** A bookmarked message resolved against its session file */
+export interface BookmarkEntry {
+  /** Session key in `UserMetadata.sessions` (the session file path) */
+  sessionId: string;
+  projectName: string;
+  annotation: MessageAnnotation;
+  /** The message is still in the session file */
+  found: boolean;
+  timestamp?: string;
+  messageType?: string;
+  /** Start of the message text */
+  preview?: string;
+  /** Pass as `offset` to `load_session_messages_paginated`; the page ends with this message */
+  pageOffset?: number;
+  totalCount?: number;
+}
+
+/** Session metadata whose file now lives under another key */
+export interface SessionRelink {
+  from: string;
+  to: string;
+}
+
+/** Session metadata with no session file behind it */
+export interface OrphanedSession {
+  /** Session key in `UserMetadata.sessions` (the session file path) */
+  sessionId: string;
+  /** What pruning would discard */
+  metadata: SessionMetadata;
+}
+
+/** Metadata entries that no longer match anything under `projects/` */
+export interface MetadataOrphanReport {
+  /** Sessions found again by file name in another project folder */
+  relinks: SessionRelink[];
+  orphanedSessions: OrphanedSession[];
+  /** Project paths with no project folder */
+  orphanedProjects: string[];
+  /** Missing sessions kept because they are in the trash or an archive */
+  parkedSessions: number;
+}
+
+/** What `clean_up_metadata` changed */
+export interface MetadataCleanupResult {
+  relinkedSessions: number;
+  prunedSessions: number;
+  prunedProjects: number;
+}
+
+/** A field both copies set differently when importing metadata */
+export interface MetadataConflict {
+  scope: "session" | "project" | "settings" | "savedSearches";
+  /** Session key or project path; empty for settings and saved searches */
+  key: string;
+  /** Field name; `annotations.<id>` or `savedSearches.<id>` for list items */
+  field: string;
+  local: unknown;
+  incoming: unknown;
+  /** The imported value was changed more recently and replaced the local one */
+  incomingWon: boolean;
+}
+
+/** What importing metadata from another machine changed */
+export interface MetadataImportReport {
+  sessionsAdded: number;
+  sessionsUpdated: number;
+  projectsAdded: number;
+  projectsUpdated: number;
+  settingsUpdated: boolean;
+  savedSearchesUpdated: boolean;
+  conflicts: MetadataConflict[];
 }
 
 /** Metadata for individual projects */
@@ -37,6 +134,8 @@ export interface ProjectMetadata {
   alias?: string;
   /** Parent project path for worktree grouping */
   parentProject?: string;
+  /** Change times (RFC 3339) of `hidden`, `alias` and `parentProject` */
+  updated?: Record<string, string>;
 }
 
 /** Grouping mode for project tree display */
@@ -55,18 +154,76 @@ export interface UserSettings {
   worktreeGroupingUserSet?: boolean;
   /** Project tree grouping mode: none, worktree, or directory */
   groupingMode?: GroupingMode;
+  /** Rules that derive tags from session content */
+  autoTagRules?: AutoTagRule[];
+  /** Change times (RFC 3339) of every setting but `hiddenPatterns` */
+  updated?: Record<string, string>;
+}
+
+/** Condition an auto-tag rule checks against session content */
+export type AutoTagCondition =
+  /** A tool call touched a file matching the glob (relative to the session cwd unless absolute); any Edit/Write tool if `tool` is unset */
+  | { type: "fileTouched"; pattern: string; tool?: string }
+  /** An assistant message used a model whose name contains this text */
+  | { type: "model"; contains: string }
+  /** A Bash tool call ran this program */
+  | { type: "bashCommand"; program: string }
+  /** Estimated session cost exceeded this many USD */
+  | { type: "costAbove"; usd: number };
+
+/** Tag sessions matching a condition */
+export interface AutoTagRule {
+  tag: string;
+  condition: AutoTagCondition;
+  /** Defaults to true */
+  enabled?: boolean;
 }
 
 /** Root structure for all user metadata */
 export interface UserMetadata {
   /** Schema version for migration support */
   version: number;
+  /** Incremented on every save; used to merge concurrent writers */
+  revision?: number;
   /** Session-specific metadata, keyed by session ID */
   sessions: Record<string, SessionMetadata>;
   /** Project-specific metadata, keyed by project path */
   projects: Record<string, ProjectMetadata>;
   /** Global user settings */
   settings: UserSettings;
+  /** Saved searches (smart collections) */
+  savedSearches?: SavedSearch[];
+}
+
+/** Filters of a saved search; every filter that is set must match */
+export interface SessionQuery {
+  /** Text in the session name, summary or messages (case-insensitive) */
+  text?: string;
+  /** Glob rules on project paths; empty means all projects */
+  projects?: string[];
+  /** Tags the session must all have, manual or auto */
+  tags?: string[];
+  /** Substring of a model the session used */
+  model?: string;
+  /** RFC 3339 bounds on the last message time */
+  dateFrom?: string;
+  dateTo?: string;
+  /** Only sessions active in the last N days */
+  withinDays?: number;
+  starred?: boolean;
+  hasErrors?: boolean;
+  /** Also search projects hidden by `hiddenPatterns` */
+  includeHidden?: boolean;
+}
+
+/** A named query shown as a smart collection */
+export interface SavedSearch {
+  /** Empty when creating; assigned by the backend along with the timestamps */
+  id: string;
+  name: string;
+  query: SessionQuery;
+  createdAt: string;
+  updatedAt: string;
 }
 
 /** Default user metadata for initialization */
@@ -84,7 +241,8 @@ export const isSessionMetadataEmpty = (metadata: SessionMetadata): boolean => {
     !metadata.starred &&
     (!metadata.tags || metadata.tags.length === 0) &&
     !metadata.notes &&
-    !metadata.hasClaudeCodeName
+    !metadata.hasClaudeCodeName &&
+    (!metadata.annotations || metadata.annotations.length === 0)
   );
 };
 
@@ -116,13 +274,6 @@ export const isProjectHidden = (
     return true;
   }
 
-  // Check hidden patterns
-  const patterns = metadata.settings.hiddenPatterns || [];
-  for (const pattern of patterns) {
-    if (matchGlobPattern(projectPath, pattern)) {
-      return true;
-    }
-  }
-
-  return false;
+  // Check hidden patterns (ordered; `!pattern` un-hides)
+  return matchGlobRules(projectPath, metadata.settings.hiddenPatterns || []);
 };
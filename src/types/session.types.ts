@@ -59,6 +59,8 @@ export interface ClaudeSession {
   has_errors: boolean;
   summary?: string;
   relevance?: number;
+  /** Tags produced by auto-tag rules, separate from the user's own tags */
+  auto_tags?: string[];
 }
 
 // ============================================================================
@@ -98,3 +100,256 @@ export interface AppState {
   sessionTokenStats: SessionTokenStats | null;
   projectTokenStats: SessionTokenStats[];
 }
+
+// ============================================================================
+// Session File Journal
+// ============================================================================
+
+/**
+ * A journaled modification of a Claude Code session file.
+ * The content before the write is kept at `snapshot_path` for revert.
+ */
+export interface JournalEntry {
+  id: string;
+  file_path: string;
+  operation: "rename" | "reset_name" | "revert" | "redact" | "relocate";
+  description: string;
+  timestamp: string;
+  snapshot_path: string;
+  original_size: number;
+  new_size: number;
+  /** CRC32 of the written content, checked before reverting */
+  new_checksum?: number;
+  /** Id of the entry that reverted this modification */
+  reverted_by?: string;
+  /** Id of the entry this modification reverts */
+  reverts?: string;
+}
+
+// ============================================================================
+// Session Redaction
+// ============================================================================
+
+/** A user-defined redaction rule; only the first capture group is redacted if present */
+export interface RedactionRule {
+  name: string;
+  pattern: string;
+}
+
+export type RedactionField =
+  | "prompt"
+  | "assistant"
+  | "thinking"
+  | "tool_input"
+  | "tool_output"
+  | "summary"
+  | "metadata"
+  | "raw"
+  | "settings_env";
+
+export interface RedactionFinding {
+  /** Built-in detector ("aws_access_key", "github_token", "jwt", "private_key", "email", "high_entropy", ...) or rule name */
+  detector: string;
+  /** 1-based line number in the session file */
+  line: number;
+  message_uuid?: string;
+  field: RedactionField;
+  /** The matched value with all but its first characters masked */
+  masked_value: string;
+}
+
+export interface RedactionPreview {
+  file_path: string;
+  total_findings: number;
+  detector_counts: Record<string, number>;
+  findings: RedactionFinding[];
+}
+
+export interface RedactionResult {
+  source_path: string;
+  output_path: string;
+  total_findings: number;
+  redacted_lines: number;
+  /** Set when redacted in place with the original snapshot kept */
+  journal_entry?: JournalEntry;
+}
+
+// ============================================================================
+// Secret Exposure Audit
+// ============================================================================
+
+/** One place a secret appeared */
+export interface SecretOccurrence {
+  /** Folder name under `projects/`; absent for user-level settings */
+  project_name?: string;
+  session_id?: string;
+  file_path: string;
+  /** 1-based line number, for session files */
+  line?: number;
+  message_uuid?: string;
+  timestamp?: string;
+  field: RedactionField;
+  /** Variable name, for `settings_env` findings */
+  env_var?: string;
+}
+
+/** A distinct secret and everywhere it was exposed */
+export interface SecretExposure {
+  detector: string;
+  masked_value: string;
+  /** Identifies the same secret across occurrences within one report */
+  fingerprint: string;
+  first_seen?: string;
+  last_seen?: string;
+  occurrences: SecretOccurrence[];
+}
+
+export interface SecretScanReport {
+  scanned_sessions: number;
+  scanned_settings_files: number;
+  total_occurrences: number;
+  exposures: SecretExposure[];
+}
+
+// ============================================================================
+// Session Trash, Archives and Retention
+// ============================================================================
+
+/** A session moved to the app-managed trash */
+export interface TrashEntry {
+  id: string;
+  session_id: string;
+  /** Folder name under `projects/` */
+  project_name: string;
+  original_path: string;
+  trashed_at: string;
+  /** Modification time of the session file when it was trashed */
+  last_modified: string;
+  size_bytes: number;
+  /** The `.jsonl` file and, if any, the session's subagent folder */
+  files: string[];
+}
+
+export interface ArchivedSession {
+  session_id: string;
+  project_name: string;
+  original_path: string;
+  last_modified: string;
+  size_bytes: number;
+}
+
+/** A `.tar.gz` bundle of sessions */
+export interface SessionArchive {
+  id: string;
+  archive_path: string;
+  created_at: string;
+  original_size: number;
+  compressed_size: number;
+  /** Whether the sessions were removed from `projects/` after archiving */
+  originals_removed: boolean;
+  sessions: ArchivedSession[];
+}
+
+export interface ArchiveRestoreResult {
+  restored_files: string[];
+  /** Files left alone because they already exist */
+  skipped_files: string[];
+}
+
+/** A session Claude Code's cleanup will delete */
+export interface RetentionCandidate {
+  session: ClaudeSession;
+  project_path: string;
+  /** When the file becomes older than `cleanupPeriodDays` */
+  expires_at: string;
+  /** Already past the cutoff: deleted the next time Claude Code starts */
+  expired: boolean;
+  size_bytes: number;
+}
+
+/** What Claude Code's `cleanupPeriodDays` setting would delete next */
+export interface RetentionPreview {
+  cleanup_period_days: number;
+  setting_scope: "managed" | "local" | "project" | "user" | "default";
+  cutoff: string;
+  within_days: number;
+  /** Soonest to be deleted first */
+  candidates: RetentionCandidate[];
+}
+
+// ============================================================================
+// Session Queries
+// ============================================================================
+
+/** Sessions matching a query, and the projects that couldn't be searched */
+export interface SessionQueryResult {
+  sessions: ClaudeSession[];
+  skipped_projects: SkippedProject[];
+}
+
+/** A project left out of a query because its sessions couldn't be loaded */
+export interface SkippedProject {
+  project_path: string;
+  error: string;
+}
+
+// ============================================================================
+// Session Fork
+// ============================================================================
+
+/** A session forked at a message into a new session file */
+export interface ForkSessionResult {
+  source_session_id: string;
+  new_session_id: string;
+  file_path: string;
+  /** Lines written to the new session file */
+  entry_count: number;
+  /** The fork ends with a `tool_use` whose results were not included */
+  ends_with_pending_tool_use: boolean;
+}
+
+// ============================================================================
+// Project Relocation
+// ============================================================================
+
+/** Sessions moved from one project folder into another */
+export interface ProjectRelocationResult {
+  source_project_path: string;
+  target_project_path: string;
+  /** The target folder already existed and the sessions were merged into it */
+  merged: boolean;
+  moved_sessions: number;
+  /** Sessions already in the target folder with identical content */
+  duplicate_sessions: number;
+  /** Session files whose `cwd` fields were rewritten */
+  rewritten_files: number;
+  rewritten_lines: number;
+  /** The source folder was empty afterwards and has been removed */
+  source_removed: boolean;
+}
+
+// ============================================================================
+// Live Session Tail
+// ============================================================================
+
+/** A session file written to recently, likely by a running agent */
+export interface ActiveSession {
+  file_path: string;
+  /** The project folder under `projects/` */
+  project_path: string;
+  last_modified: string;
+  /** Seconds since the last write */
+  idle_seconds: number;
+  size: number;
+}
+
+/** Payload of the `session-tail` event */
+export interface SessionTailEvent {
+  sessionPath: string;
+  /** The file was truncated or replaced: reload it; the tail goes on from `endOffset` */
+  reset: boolean;
+  messages: ClaudeMessage[];
+  /** Bytes `startOffset..endOffset` of the file were read */
+  startOffset: number;
+  endOffset: number;
+}
@@ -1,7 +1,16 @@
 /**
  * Glob Pattern Matching Utilities
  *
- * Safe glob pattern matching with ReDoS protection.
+ * Same syntax as the backend glob engine (src-tauri/src/glob.rs):
+ * - `*` matches any run of characters except `/`, `?` any single one
+ * - `**` also matches across `/`; `**` followed by `/` matches zero or more directories
+ * - `[abc]` / `[a-z]` match one listed character, `[!abc]` / `[^abc]` one that isn't listed
+ * - `{a,b}` matches either alternative
+ * - in a rule list, a leading `!` excludes what earlier rules matched
+ *
+ * An unclosed `[` or `{` is literal, and so is `\`. The backend matcher runs in
+ * linear time; JavaScript regexes backtrack, so patterns here keep the
+ * length/wildcard limits as ReDoS protection.
  */
 
 /**
@@ -10,58 +19,185 @@
 const MAX_PATTERN_LENGTH = 256;
 
 /**
- * Maximum number of wildcards to prevent catastrophic backtracking
+ * Maximum number of wildcards to prevent catastrophic backtracking.
+ * A run of `*` counts once, as does each `?`; the backend uses the same limits.
  */
 const MAX_WILDCARDS = 10;
 
+/** Compiled patterns (null when the pattern is rejected or invalid) */
+const compiledPatterns = new Map<string, RegExp | null>();
+
+const escapeLiteral = (char: string): string =>
+  char.replace(/[.*+?^${}()|[\]\\/]/g, "\\$&");
+
+/** Regex for the `[...]` class starting at `start`, with the index of its `]` */
+const translateClass = (
+  chars: string[],
+  start: number
+): { regex: string; end: number } | null => {
+  let first = start + 1;
+  const negated = chars[first] === "!" || chars[first] === "^";
+  if (negated) first++;
+  if (first >= chars.length) return null;
+
+  // A `]` right after the opening bracket is a member, not the end
+  const end = chars.indexOf("]", first + 1);
+  if (end === -1) return null;
+
+  const members = chars.slice(first, end);
+  let regex = negated ? "[^/" : "[";
+  members.forEach((member, n) => {
+    const isRange = member === "-" && n > 0 && n < members.length - 1;
+    if (!isRange && "\\[]^-".includes(member)) {
+      regex += "\\";
+    }
+    regex += member;
+  });
+  return { regex: `${regex}]`, end };
+};
+
+/** Top-level alternatives of the `{...}` group starting at `start`, with the index of its `}` */
+const splitBraces = (
+  chars: string[],
+  start: number
+): { alternatives: string[][]; end: number } | null => {
+  let depth = 0;
+  const alternatives: string[][] = [];
+  let alternativeStart = start + 1;
+  for (let i = start + 1; i < chars.length; i++) {
+    const char = chars[i];
+    if (char === "{") {
+      depth++;
+    } else if (char === "}" && depth > 0) {
+      depth--;
+    } else if (char === "}") {
+      alternatives.push(chars.slice(alternativeStart, i));
+      return { alternatives, end: i };
+    } else if (char === "," && depth === 0) {
+      alternatives.push(chars.slice(alternativeStart, i));
+      alternativeStart = i + 1;
+    }
+  }
+  return null;
+};
+
+/** Translate glob syntax into an unanchored regex source */
+const translate = (chars: string[]): string => {
+  let out = "";
+  let i = 0;
+  while (i < chars.length) {
+    const char = chars[i];
+    if (char === "*") {
+      let runEnd = i;
+      while (runEnd < chars.length && chars[runEnd] === "*") runEnd++;
+      if (runEnd - i === 1) {
+        out += "[^/]*";
+        i = runEnd;
+      } else if (chars[runEnd] === "/") {
+        out += "(?:.*/)?";
+        i = runEnd + 1;
+      } else {
+        out += ".*";
+        i = runEnd;
+      }
+      continue;
+    }
+    if (char === "?") {
+      out += "[^/]";
+      i++;
+      continue;
+    }
+    if (char === "[") {
+      const cls = translateClass(chars, i);
+      if (cls) {
+        out += cls.regex;
+        i = cls.end + 1;
+        continue;
+      }
+    }
+    if (char === "{") {
+      const group = splitBraces(chars, i);
+      if (group) {
+        out += `(?:${group.alternatives.map(translate).join("|")})`;
+        i = group.end + 1;
+        continue;
+      }
+    }
+    out += escapeLiteral(char ?? "");
+    i++;
+  }
+  return out;
+};
+
+const compilePattern = (pattern: string): RegExp | null => {
+  const cached = compiledPatterns.get(pattern);
+  if (cached !== undefined) return cached;
+
+  let regex: RegExp | null = null;
+  if (pattern.length > MAX_PATTERN_LENGTH) {
+    console.warn(
+      `Glob pattern exceeds maximum length (${MAX_PATTERN_LENGTH}), skipping`
+    );
+  } else {
+    const wildcardCount = (pattern.match(/\*+|\?/g) || []).length;
+    if (wildcardCount > MAX_WILDCARDS) {
+      console.warn(
+        `Glob pattern has too many wildcards (${wildcardCount} > ${MAX_WILDCARDS}), skipping`
+      );
+    } else {
+      try {
+        regex = new RegExp(`^(?:${translate(Array.from(pattern))})$`, "u");
+      } catch {
+        // Invalid pattern, e.g. a reversed range like [z-a]
+        regex = null;
+      }
+    }
+  }
+  compiledPatterns.set(pattern, regex);
+  return regex;
+};
+
 /**
- * Match a text string against a glob pattern.
- * Supports * (any characters) and ? (single character) wildcards.
+ * Match a whole text string against a glob pattern.
  *
  * @param text - The text to match against
- * @param pattern - The glob pattern (supports * and ?)
+ * @param pattern - The glob pattern
  * @returns true if the text matches the pattern
  *
  * @example
  * matchGlobPattern("folders-dg-abc123", "folders-dg-*") // true
- * matchGlobPattern("test.ts", "*.ts") // true
+ * matchGlobPattern("Cargo.toml", "*.{rs,toml}") // true
  * matchGlobPattern("abc", "a?c") // true
  */
 export const matchGlobPattern = (text: string, pattern: string): boolean => {
-  // Safety check: limit pattern length to prevent ReDoS
-  if (pattern.length > MAX_PATTERN_LENGTH) {
-    console.warn(
-      `Glob pattern exceeds maximum length (${MAX_PATTERN_LENGTH}), skipping`
-    );
-    return false;
-  }
+  return compilePattern(pattern)?.test(text) ?? false;
+};
 
-  // Safety check: limit number of wildcards to prevent catastrophic backtracking
-  const wildcardCount = (pattern.match(/[*?]/g) || []).length;
-  if (wildcardCount > MAX_WILDCARDS) {
-    console.warn(
-      `Glob pattern has too many wildcards (${wildcardCount} > ${MAX_WILDCARDS}), skipping`
-    );
-    return false;
+/**
+ * Match a path against a glob pattern. Patterns without `/` match the last
+ * path component, like `.gitignore` entries.
+ */
+export const matchGlobPath = (path: string, pattern: string): boolean => {
+  if (pattern.includes("/")) {
+    return matchGlobPattern(path, pattern);
   }
+  const name = path.replace(/\/+$/, "").split("/").pop() ?? path;
+  return matchGlobPattern(name, pattern);
+};
 
-  // Convert glob pattern to regex
-  // Use placeholders to avoid replacement conflicts (e.g., * -> .*? then ? -> . would break .*?)
-  const STAR_PLACEHOLDER = "\x00STAR\x00";
-  const QUESTION_PLACEHOLDER = "\x00QUESTION\x00";
-
-  const regexPattern = pattern
-    .replace(/\*/g, STAR_PLACEHOLDER) // Temporarily replace *
-    .replace(/\?/g, QUESTION_PLACEHOLDER) // Temporarily replace ?
-    .replace(/[.+^${}()|[\]\\]/g, "\\$&") // Escape regex special chars
-    .replace(new RegExp(STAR_PLACEHOLDER, "g"), ".*") // * -> .* (greedy is fine with anchors)
-    .replace(new RegExp(QUESTION_PLACEHOLDER, "g"), "."); // ? -> .
-
-  try {
-    const regex = new RegExp(`^${regexPattern}$`);
-    return regex.test(text);
-  } catch {
-    // Invalid regex pattern
-    return false;
+/**
+ * Evaluate ordered glob rules against a path; the last matching rule decides.
+ *
+ * @example
+ * matchGlobRules("/x/folders-keep", ["folders-*", "!folders-keep"]) // false
+ */
+export const matchGlobRules = (path: string, patterns: string[]): boolean => {
+  for (let i = patterns.length - 1; i >= 0; i--) {
+    const rule = patterns[i] ?? "";
+    const excludes = rule.startsWith("!");
+    if (matchGlobPath(path, excludes ? rule.slice(1) : rule)) {
+      return !excludes;
+    }
   }
+  return false;
 };